@@ -0,0 +1,356 @@
+//! Glyph atlases.
+
+use std::io::Result;
+
+use crate::characters::Character;
+use crate::raster::{self, Bitmap};
+use crate::{Font, Number, Offset};
+
+/// A glyph atlas.
+#[derive(Clone, Debug)]
+pub struct GlyphAtlas {
+    /// The size in pixels.
+    pub size: Number,
+    /// The distance from the baseline to the top of a line in pixels.
+    pub ascender: Number,
+    /// The distance from the baseline to the bottom of a line in pixels.
+    pub descender: Number,
+    /// The distance between consecutive baselines in pixels.
+    pub line_height: Number,
+    /// The textures.
+    pub textures: Vec<Bitmap>,
+    /// The glyphs.
+    pub glyphs: Vec<Entry>,
+    /// The kerning of pairs of characters in pixels.
+    pub kerning: Vec<(char, char, Number)>,
+}
+
+/// A glyph in an atlas.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Entry {
+    /// The character.
+    pub character: char,
+    /// The index of the texture, which is zero for glyphs with no pixels.
+    pub texture: usize,
+    /// The left, top, width, and height of the rectangle in the texture in
+    /// pixels.
+    pub rectangle: (usize, usize, usize, usize),
+    /// The left, top, right, and bottom of the rectangle in the texture
+    /// normalized by the dimensions of the texture.
+    pub uv: (Number, Number, Number, Number),
+    /// The horizontal distance from the origin to the left edge and the
+    /// vertical distance from the baseline to the top edge in pixels.
+    pub bearings: (i32, i32),
+    /// The advance width in pixels.
+    pub advance: Number,
+}
+
+struct Shelf {
+    top: usize,
+    height: usize,
+    right: usize,
+}
+
+impl GlyphAtlas {
+    /// Rasterize characters and pack them into square textures.
+    ///
+    /// The size is in pixels, the dimension is the width and height of each
+    /// texture, and the padding is the number of empty pixels kept around each
+    /// glyph. Characters the font has no glyphs for are skipped.
+    pub fn new<T>(
+        font: &mut Font<T>,
+        characters: &[Character],
+        size: Number,
+        dimension: usize,
+        padding: usize,
+    ) -> Result<Self>
+    where
+        T: crate::Read,
+    {
+        let metrics = font.metrics()?;
+        let scale = size / metrics.granularity;
+        let mut items = vec![];
+        for character in characters.iter().flat_map(expand) {
+            if let Some(glyph) = font.glyph(character)? {
+                let bitmap = raster::rasterize(&glyph, scale, Offset::default());
+                if bitmap.width + 2 * padding > dimension || bitmap.height + 2 * padding > dimension
+                {
+                    raise!(
+                        "found a glyph too large for the texture ({} pixels)",
+                        dimension,
+                    );
+                }
+                items.push((character, glyph.advance_width * scale, bitmap));
+            }
+        }
+        let characters = items.iter().map(|item| item.0).collect::<Vec<_>>();
+        let kerning = font
+            .kerning_pairs(&characters)?
+            .into_iter()
+            .map(|(one, other, value)| (one, other, value * scale))
+            .collect();
+        let mut order = (0..items.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&i| std::cmp::Reverse((items[i].2.height, items[i].2.width)));
+        let mut textures: Vec<Bitmap> = vec![];
+        let mut shelves: Vec<Shelf> = vec![];
+        let mut glyphs = vec![None; items.len()];
+        for i in order {
+            let (character, advance, ref bitmap) = items[i];
+            let (width, height) = (bitmap.width, bitmap.height);
+            let mut position = None;
+            if width > 0 && height > 0 {
+                let (outer_width, outer_height) = (width + padding, height + padding);
+                for shelf in shelves.iter_mut() {
+                    if shelf.height >= outer_height && shelf.right + outer_width <= dimension {
+                        position = Some((shelf.right, shelf.top));
+                        shelf.right += outer_width;
+                        break;
+                    }
+                }
+                if position.is_none() {
+                    let top = shelves
+                        .last()
+                        .map(|shelf| shelf.top + shelf.height)
+                        .unwrap_or(padding);
+                    let top = if textures.is_empty() || top + outer_height > dimension {
+                        textures.push(Bitmap {
+                            width: dimension,
+                            height: dimension,
+                            data: vec![0; dimension * dimension],
+                            ..Default::default()
+                        });
+                        shelves.clear();
+                        padding
+                    } else {
+                        top
+                    };
+                    shelves.push(Shelf {
+                        top,
+                        height: outer_height,
+                        right: padding + outer_width,
+                    });
+                    position = Some((padding, top));
+                }
+            }
+            let (x, y) = position.unwrap_or((0, 0));
+            if position.is_some() {
+                let texture = textures.last_mut().unwrap();
+                for (j, row) in bitmap.data.chunks(width).enumerate() {
+                    let start = (y + j) * dimension + x;
+                    texture.data[start..(start + width)].copy_from_slice(row);
+                }
+            }
+            let dimension = dimension as Number;
+            glyphs[i] = Some(Entry {
+                character,
+                texture: if position.is_some() {
+                    textures.len() - 1
+                } else {
+                    0
+                },
+                rectangle: (x, y, width, height),
+                uv: (
+                    x as Number / dimension,
+                    y as Number / dimension,
+                    (x + width) as Number / dimension,
+                    (y + height) as Number / dimension,
+                ),
+                bearings: (bitmap.left, bitmap.top),
+                advance,
+            });
+        }
+        Ok(Self {
+            size,
+            ascender: metrics.ascender * scale,
+            descender: metrics.descender * scale,
+            line_height: (metrics.ascender - metrics.descender + metrics.line_gap) * scale,
+            textures,
+            glyphs: glyphs.into_iter().flatten().collect(),
+            kerning,
+        })
+    }
+
+    /// Encode a texture as a grayscale PNG image.
+    pub fn to_png(&self, index: usize) -> Option<Vec<u8>> {
+        self.textures.get(index).map(png)
+    }
+
+    /// Describe the atlas in JSON.
+    pub fn to_json(&self) -> String {
+        let mut output = String::new();
+        output.push_str(&format!(
+            "{{\"size\":{},\"ascender\":{},\"descender\":{},\"line_height\":{},",
+            self.size, self.ascender, self.descender, self.line_height,
+        ));
+        let textures = self
+            .textures
+            .iter()
+            .map(|texture| {
+                format!(
+                    "{{\"width\":{},\"height\":{}}}",
+                    texture.width, texture.height
+                )
+            })
+            .collect::<Vec<_>>();
+        output.push_str(&format!("\"textures\":[{}],", textures.join(",")));
+        let glyphs = self
+            .glyphs
+            .iter()
+            .map(|glyph| {
+                format!(
+                    "{{\"character\":{},\"texture\":{},\"x\":{},\"y\":{},\"width\":{},\"height\":{},\
+                     \"uv\":[{},{},{},{}],\"left\":{},\"top\":{},\"advance\":{}}}",
+                    glyph.character as u32,
+                    glyph.texture,
+                    glyph.rectangle.0,
+                    glyph.rectangle.1,
+                    glyph.rectangle.2,
+                    glyph.rectangle.3,
+                    glyph.uv.0,
+                    glyph.uv.1,
+                    glyph.uv.2,
+                    glyph.uv.3,
+                    glyph.bearings.0,
+                    glyph.bearings.1,
+                    glyph.advance,
+                )
+            })
+            .collect::<Vec<_>>();
+        output.push_str(&format!("\"glyphs\":[{}],", glyphs.join(",")));
+        let kerning = self
+            .kerning
+            .iter()
+            .map(|(one, other, value)| format!("[{},{},{}]", *one as u32, *other as u32, value))
+            .collect::<Vec<_>>();
+        output.push_str(&format!("\"kerning\":[{}]}}", kerning.join(",")));
+        output
+    }
+
+    /// Describe the atlas in the text format of BMFont.
+    ///
+    /// The textures are referred to as `{name}_{index}.png`.
+    pub fn to_bmfont(&self, name: &str) -> String {
+        let base = self.ascender.round() as i32;
+        let (width, height) = self
+            .textures
+            .first()
+            .map(|texture| (texture.width, texture.height))
+            .unwrap_or((0, 0));
+        let mut lines = vec![
+            format!(
+                "info face=\"{}\" size={} bold=0 italic=0 charset=\"\" unicode=1 \
+                 stretchH=100 smooth=1 aa=1 padding=0,0,0,0 spacing=0,0",
+                name,
+                self.size.round() as i32,
+            ),
+            format!(
+                "common lineHeight={} base={} scaleW={} scaleH={} pages={} packed=0",
+                self.line_height.round() as i32,
+                base,
+                width,
+                height,
+                self.textures.len(),
+            ),
+        ];
+        for index in 0..self.textures.len() {
+            lines.push(format!("page id={index} file=\"{name}_{index}.png\""));
+        }
+        lines.push(format!("chars count={}", self.glyphs.len()));
+        for glyph in self.glyphs.iter() {
+            lines.push(format!(
+                "char id={} x={} y={} width={} height={} xoffset={} yoffset={} xadvance={} \
+                 page={} chnl=15",
+                glyph.character as u32,
+                glyph.rectangle.0,
+                glyph.rectangle.1,
+                glyph.rectangle.2,
+                glyph.rectangle.3,
+                glyph.bearings.0,
+                base - glyph.bearings.1,
+                glyph.advance.round() as i32,
+                glyph.texture,
+            ));
+        }
+        lines.push(format!("kernings count={}", self.kerning.len()));
+        for (one, other, value) in self.kerning.iter() {
+            lines.push(format!(
+                "kerning first={} second={} amount={}",
+                *one as u32,
+                *other as u32,
+                value.round() as i32,
+            ));
+        }
+        lines.push(String::new());
+        lines.join("\n")
+    }
+}
+
+fn expand(character: &Character) -> Box<dyn Iterator<Item = char>> {
+    match *character {
+        Character::Scalar(value) => Box::new(std::iter::once(value)),
+        Character::Range((start, end)) => Box::new(start..=end),
+    }
+}
+
+fn png(bitmap: &Bitmap) -> Vec<u8> {
+    let mut raw = Vec::with_capacity((bitmap.width + 1) * bitmap.height);
+    for row in bitmap.data.chunks(bitmap.width.max(1)) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    let mut compressed = vec![0x78, 0x01];
+    let mut blocks = raw.chunks(0xFFFF).peekable();
+    if blocks.peek().is_none() {
+        compressed.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        compressed.push(blocks.peek().is_none() as u8);
+        let length = block.len() as u16;
+        compressed.extend_from_slice(&length.to_le_bytes());
+        compressed.extend_from_slice(&(!length).to_le_bytes());
+        compressed.extend_from_slice(block);
+    }
+    compressed.extend_from_slice(&adler32(&raw).to_be_bytes());
+    let mut header = vec![];
+    header.extend_from_slice(&(bitmap.width as u32).to_be_bytes());
+    header.extend_from_slice(&(bitmap.height as u32).to_be_bytes());
+    header.extend_from_slice(&[8, 0, 0, 0, 0]);
+    let mut output = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+    chunk(&mut output, b"IHDR", &header);
+    chunk(&mut output, b"IDAT", &compressed);
+    chunk(&mut output, b"IEND", &[]);
+    output
+}
+
+fn chunk(output: &mut Vec<u8>, tag: &[u8; 4], data: &[u8]) {
+    output.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = output.len();
+    output.extend_from_slice(tag);
+    output.extend_from_slice(data);
+    let checksum = crc32(&output[start..]);
+    output.extend_from_slice(&checksum.to_be_bytes());
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut value = !0u32;
+    for &byte in data {
+        value ^= byte as u32;
+        for _ in 0..8 {
+            value = if value & 1 != 0 {
+                0xEDB88320 ^ (value >> 1)
+            } else {
+                value >> 1
+            };
+        }
+    }
+    !value
+}
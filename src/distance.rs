@@ -0,0 +1,425 @@
+//! Distance fields.
+
+use crate::glyph::geometry::{cross, dot, length, Curve};
+use crate::glyph::Glyph;
+use crate::{Number, Offset};
+
+const RED: u8 = 1;
+const GREEN: u8 = 2;
+const BLUE: u8 = 4;
+const CYAN: u8 = GREEN | BLUE;
+const MAGENTA: u8 = RED | BLUE;
+const YELLOW: u8 = RED | GREEN;
+const WHITE: u8 = RED | GREEN | BLUE;
+
+const CORNER: Number = 0.14112;
+const EPSILON: Number = 0.05;
+const TOLERANCE: Number = 0.01;
+
+/// A distance field.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Field {
+    /// The number of columns.
+    pub width: usize,
+    /// The number of rows.
+    pub height: usize,
+    /// The horizontal position of the left edge relative to the origin.
+    pub left: i32,
+    /// The vertical position of the top edge relative to the baseline.
+    pub top: i32,
+    /// The number of channels per pixel.
+    pub channels: usize,
+    /// The distances row by row from top to bottom with interleaved channels.
+    pub data: Vec<u8>,
+}
+
+impl Field {
+    /// Return the channels of a pixel.
+    #[inline]
+    pub fn get(&self, x: usize, y: usize) -> Option<&[u8]> {
+        if x < self.width && y < self.height {
+            let start = (y * self.width + x) * self.channels;
+            Some(&self.data[start..(start + self.channels)])
+        } else {
+            None
+        }
+    }
+}
+
+/// Generate a single-channel signed distance field.
+///
+/// The scale converts font units into pixels and is typically the size in
+/// pixels divided by the granularity of the font. The range is the span of
+/// distances in pixels the values cover, with 128 corresponding to the
+/// outline and larger values to the inside, and the padding is the number of
+/// pixels added around the bounding box of the outline.
+pub fn single(glyph: &Glyph, scale: Number, range: Number, padding: usize) -> Field {
+    generate(glyph, scale, range, padding, false)
+}
+
+/// Generate a multi-channel signed distance field.
+///
+/// The parameters are the same as for `single`. The edges of the outline are
+/// colored so that the median of the three channels preserves sharp corners.
+pub fn multiple(glyph: &Glyph, scale: Number, range: Number, padding: usize) -> Field {
+    generate(glyph, scale, range, padding, true)
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Edge {
+    curve: Curve,
+    color: u8,
+}
+
+struct Shape {
+    edges: Vec<Edge>,
+    polygons: Vec<Vec<Offset>>,
+    orientation: Number,
+}
+
+fn generate(glyph: &Glyph, scale: Number, range: Number, padding: usize, colored: bool) -> Field {
+    let mut contours = vec![];
+    let mut minimum = Offset::undefined();
+    let mut maximum = Offset::undefined();
+    for contour in glyph.iter() {
+        let mut edges = contour
+            .curves()
+            .map(|curve| match curve {
+                Curve::Linear(a, b) => Curve::Linear(a * scale, b * scale),
+                Curve::Quadratic(a, b, c) => Curve::Quadratic(a * scale, b * scale, c * scale),
+                Curve::Cubic(a, b, c, d) => {
+                    Curve::Cubic(a * scale, b * scale, c * scale, d * scale)
+                }
+            })
+            .map(|curve| Edge {
+                curve,
+                color: WHITE,
+            })
+            .collect::<Vec<_>>();
+        let (start, end) = match (edges.first(), edges.last()) {
+            (Some(first), Some(last)) => (first.curve.start(), last.curve.end()),
+            _ => continue,
+        };
+        if end != start {
+            edges.push(Edge {
+                curve: Curve::Linear(end, start),
+                color: WHITE,
+            });
+        }
+        edges.retain(|edge| {
+            let points = edge.curve.points();
+            points.iter().any(|&a| a != points[0])
+        });
+        for edge in edges.iter() {
+            for point in edge.curve.points() {
+                minimum = minimum.min(point);
+                maximum = maximum.max(point);
+            }
+        }
+        if !edges.is_empty() {
+            contours.push(edges);
+        }
+    }
+    if contours.is_empty() || !minimum.0.is_finite() || !minimum.1.is_finite() {
+        return Field {
+            channels: if colored { 3 } else { 1 },
+            ..Default::default()
+        };
+    }
+    if colored {
+        for edges in contours.iter_mut() {
+            color(edges);
+        }
+    }
+    let polygons = contours
+        .iter()
+        .map(|edges| {
+            let mut points = vec![edges[0].curve.start()];
+            for edge in edges.iter() {
+                flatten(edge, &mut points);
+            }
+            points
+        })
+        .collect::<Vec<_>>();
+    let orientation = polygons
+        .iter()
+        .map(|points| area(points))
+        .fold(
+            0.0,
+            |sum: Number, value| if value.abs() > sum.abs() { value } else { sum },
+        )
+        .signum();
+    let shape = Shape {
+        edges: contours.into_iter().flatten().collect(),
+        polygons,
+        orientation,
+    };
+    let padding = padding as Number;
+    let left = minimum.0.floor() - padding;
+    let top = maximum.1.ceil() + padding;
+    let width = (maximum.0.ceil() + padding - left) as usize;
+    let height = (top - minimum.1.floor() + padding) as usize;
+    let channels = if colored { 3 } else { 1 };
+    let mut data = Vec::with_capacity(width * height * channels);
+    let encode =
+        |distance: Number| ((0.5 + distance / range).clamp(0.0, 1.0) * 255.0).round() as u8;
+    for j in 0..height {
+        for i in 0..width {
+            let point = Offset(left + i as Number + 0.5, top - j as Number - 0.5);
+            let inside = shape.winding(point) != 0;
+            if !colored {
+                data.push(encode(shape.distance(point, inside)));
+                continue;
+            }
+            let mut values = [RED, GREEN, BLUE].map(|channel| shape.pseudo(point, channel));
+            if (median(values) > 0.0) != inside {
+                values = [shape.distance(point, inside); 3];
+            }
+            data.extend(values.map(encode));
+        }
+    }
+    Field {
+        width,
+        height,
+        left: left as i32,
+        top: top as i32,
+        channels,
+        data,
+    }
+}
+
+impl Shape {
+    fn winding(&self, point: Offset) -> i32 {
+        let mut winding = 0;
+        for points in self.polygons.iter() {
+            for pair in points.windows(2) {
+                let (a, b) = (pair[0], pair[1]);
+                if a.1 <= point.1 {
+                    if b.1 > point.1 && cross(b - a, point - a) > 0.0 {
+                        winding += 1;
+                    }
+                } else if b.1 <= point.1 && cross(b - a, point - a) < 0.0 {
+                    winding -= 1;
+                }
+            }
+        }
+        winding
+    }
+
+    // An edge point belongs to the outline only if it separates the inside
+    // from the outside, which is not the case for the parts of overlapping
+    // contours lying within other contours. The probes are kept away from the
+    // ends of the edge, where they would fall onto the adjacent edges.
+    fn is_boundary(&self, edge: &Edge, t: Number) -> bool {
+        let margin = (2.0 * EPSILON / length(edge.curve.end() - edge.curve.start())).min(0.5);
+        let t = t.clamp(margin, 1.0 - margin);
+        let normal = normal(edge, t) * EPSILON;
+        let point = edge.curve.evaluate(t);
+        (self.winding(point + normal) != 0) != (self.winding(point - normal) != 0)
+    }
+
+    fn candidates(&self, point: Offset, channel: u8) -> Vec<(Number, Number, usize)> {
+        let mut candidates = self
+            .edges
+            .iter()
+            .enumerate()
+            .filter(|(_, edge)| edge.color & channel != 0)
+            .map(|(i, edge)| {
+                let t = closest(edge, point);
+                let direction = point - edge.curve.evaluate(t);
+                let distance = length(direction);
+                let orthogonality = if distance > 0.0 {
+                    cross(tangent(edge, t), direction / distance).abs()
+                } else {
+                    0.0
+                };
+                (distance, -orthogonality, t, i)
+            })
+            .collect::<Vec<_>>();
+        candidates.sort_by(|one, other| {
+            (one.0, one.1)
+                .partial_cmp(&(other.0, other.1))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        candidates
+            .into_iter()
+            .map(|(distance, _, t, i)| (distance, t, i))
+            .collect()
+    }
+
+    fn distance(&self, point: Offset, inside: bool) -> Number {
+        let sign = if inside { 1.0 } else { -1.0 };
+        for (distance, t, i) in self.candidates(point, WHITE) {
+            if self.is_boundary(&self.edges[i], t) {
+                return sign * distance;
+            }
+        }
+        sign * Number::INFINITY
+    }
+
+    fn pseudo(&self, point: Offset, channel: u8) -> Number {
+        for (distance, t, i) in self.candidates(point, channel) {
+            let edge = &self.edges[i];
+            if !self.is_boundary(edge, t) {
+                continue;
+            }
+            let side = cross(tangent(edge, t), point - edge.curve.evaluate(t));
+            let sign = if side * self.orientation > 0.0 {
+                1.0
+            } else {
+                -1.0
+            };
+            let end = if t <= 0.0 {
+                Some(0.0)
+            } else if t >= 1.0 {
+                Some(1.0)
+            } else {
+                None
+            };
+            if let Some(t) = end {
+                let direction = tangent(edge, t);
+                let offset = point - edge.curve.evaluate(t);
+                let along = dot(offset, direction);
+                if (t == 0.0 && along < 0.0) || (t == 1.0 && along > 0.0) {
+                    let side = cross(direction, offset);
+                    let sign = if side * self.orientation > 0.0 {
+                        1.0
+                    } else {
+                        -1.0
+                    };
+                    return sign * side.abs().min(distance);
+                }
+            }
+            return sign * distance;
+        }
+        -Number::INFINITY
+    }
+}
+
+fn color(edges: &mut Vec<Edge>) {
+    let count = edges.len();
+    let corners = (0..count)
+        .filter(|&i| {
+            let one = tangent(&edges[(i + count - 1) % count], 1.0);
+            let other = tangent(&edges[i], 0.0);
+            dot(one, other) <= 0.0 || cross(one, other).abs() > CORNER
+        })
+        .collect::<Vec<_>>();
+    match corners.len() {
+        0 => {}
+        1 => {
+            edges.rotate_left(corners[0]);
+            while edges.len() < 3 {
+                *edges = edges.iter().flat_map(split).collect();
+            }
+            let count = edges.len();
+            for (i, edge) in edges.iter_mut().enumerate() {
+                let position = 3.0 + 2.875 * i as Number / (count - 1) as Number - 1.4375 + 0.5;
+                edge.color = [MAGENTA, WHITE, YELLOW][(position as usize).clamp(2, 4) - 2];
+            }
+        }
+        splines => {
+            let palette = [CYAN, MAGENTA, YELLOW];
+            let mut colors = (0..splines).map(|i| palette[i % 3]).collect::<Vec<_>>();
+            if colors[splines - 1] == colors[0] {
+                let (previous, first) = (colors[splines - 2], colors[0]);
+                colors[splines - 1] = palette
+                    .into_iter()
+                    .find(|&color| color != previous && color != first)
+                    .unwrap_or(WHITE);
+            }
+            for (k, &start) in corners.iter().enumerate() {
+                let end = corners.get(k + 1).cloned().unwrap_or(corners[0] + count);
+                for i in start..end {
+                    edges[i % count].color = colors[k];
+                }
+            }
+        }
+    }
+}
+
+fn split(edge: &Edge) -> [Edge; 2] {
+    [
+        Edge {
+            curve: edge.curve.split(0.0, 0.5),
+            ..*edge
+        },
+        Edge {
+            curve: edge.curve.split(0.5, 1.0),
+            ..*edge
+        },
+    ]
+}
+
+fn flatten(edge: &Edge, points: &mut Vec<Offset>) {
+    let count = ((edge.curve.deviation() / TOLERANCE).sqrt().ceil() as usize).clamp(1, 100);
+    for i in 1..=count {
+        points.push(edge.curve.evaluate(i as Number / count as Number));
+    }
+}
+
+fn closest(edge: &Edge, point: Offset) -> Number {
+    if let Curve::Linear(a, b) = edge.curve {
+        let direction = b - a;
+        return (dot(point - a, direction) / dot(direction, direction)).clamp(0.0, 1.0);
+    }
+    const STEPS: usize = 16;
+    let mut best = (Number::INFINITY, 0.0);
+    for i in 0..=STEPS {
+        let t = i as Number / STEPS as Number;
+        let distance = length(edge.curve.evaluate(t) - point);
+        if distance < best.0 {
+            best = (distance, t);
+        }
+    }
+    let mut t = best.1;
+    for _ in 0..4 {
+        let offset = edge.curve.evaluate(t) - point;
+        let (first, second) = (edge.curve.derivative(t), edge.curve.second_derivative(t));
+        let numerator = dot(offset, first);
+        let denominator = dot(first, first) + dot(offset, second);
+        if denominator.abs() < Number::EPSILON {
+            break;
+        }
+        let next = (t - numerator / denominator).clamp(0.0, 1.0);
+        if length(edge.curve.evaluate(next) - point) > length(edge.curve.evaluate(t) - point) {
+            break;
+        }
+        t = next;
+    }
+    t
+}
+
+fn tangent(edge: &Edge, t: Number) -> Offset {
+    let mut value = edge.curve.derivative(t);
+    if length(value) < Number::EPSILON {
+        value = if t < 0.5 {
+            edge.curve.evaluate(0.01) - edge.curve.start()
+        } else {
+            edge.curve.end() - edge.curve.evaluate(0.99)
+        };
+    }
+    let norm = length(value);
+    if norm > 0.0 {
+        value / norm
+    } else {
+        value
+    }
+}
+
+fn normal(edge: &Edge, t: Number) -> Offset {
+    let value = tangent(edge, t);
+    Offset(-value.1, value.0)
+}
+
+fn area(points: &[Offset]) -> Number {
+    points
+        .windows(2)
+        .map(|pair| cross(pair[0], pair[1]))
+        .sum::<Number>()
+        / 2.0
+}
+
+fn median([a, b, c]: [Number; 3]) -> Number {
+    a.min(b).max(a.max(b).min(c))
+}
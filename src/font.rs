@@ -1,6 +1,13 @@
 use std::io::Result;
+use std::rc::Rc;
 
-use crate::{Axes, Characters, Features, Glyph, Metrics, Names, Palettes, Tables, Timestamps};
+use opentype::truetype::GlyphID;
+
+use crate::glyph::{Alignment, Pen};
+use crate::{
+    Axes, Characters, DeviceWidths, EmbeddedBitmap, Features, Glyph, GridFitting, LinearThresholds,
+    Metrics, Names, Number, Palettes, Tables, Timestamps, VerticalDeviceMetrics,
+};
 
 /// A font.
 pub struct Font<T> {
@@ -56,6 +63,9 @@ implement! {
     fn features() -> Features;
     /// Return the metrics.
     fn metrics() -> Metrics;
+    /// Return the left, bottom, right, and top edges of the union of all
+    /// glyphs.
+    fn bounding_box() -> (Number, Number, Number, Number);
     /// Return the names.
     fn names() -> Names;
     /// Return the palettes.
@@ -64,8 +74,38 @@ implement! {
     fn tables() -> Tables;
     /// Return the timestamps.
     fn timestamps() -> Timestamps;
+    /// Return the rasterization behavior for sizes if the font has it.
+    fn grid_fitting() -> Option<Rc<GridFitting>>;
+    /// Return the advance widths in pixels for sizes if the font has them.
+    fn device_widths() -> Option<Rc<DeviceWidths>>;
+    /// Return the sizes from which advance widths scale linearly if the font
+    /// has them.
+    fn linear_thresholds() -> Option<Rc<LinearThresholds>>;
+    /// Return the vertical extents in pixels for sizes if the font has them.
+    fn vertical_device_metrics() -> Option<Rc<VerticalDeviceMetrics>>;
     /// Return the glyph of a character.
     fn glyph(character: char) -> Option<Glyph>;
+    /// Return the glyph identifier of a character.
+    fn glyph_id(character: char) -> Option<GlyphID>;
+    /// Return the glyph with an identifier, flattening its components or
+    /// leaving them out of the contours.
+    fn glyph_by_id(glyph_id: GlyphID, flatten: bool) -> Glyph;
+    /// Return the alignment zones and standard stem widths if the font has
+    /// them.
+    fn alignment() -> Option<Alignment>;
+    /// Draw the glyph of a character and return whether there is one.
+    fn draw(character: char, pen: &mut dyn Pen) -> bool;
+    /// Return the horizontal kerning of a pair of characters.
+    fn kerning(one: char, other: char) -> Number;
+    /// Return the pairs of characters from a set that have nonzero horizontal
+    /// kerning along with the kerning.
+    fn kerning_pairs(characters: &[char]) -> Vec<(char, char, Number)>;
+    /// Return the glyph of a character grid-fitted by the instructions of the
+    /// font at a size in pixels per em with coordinates in pixels.
+    fn hinted_glyph(character: char, ppem: u16) -> Option<Glyph>;
+    /// Return the embedded bitmap of a character from the strike closest to a
+    /// size in pixels per em.
+    fn bitmap(character: char, ppem: u16) -> Option<EmbeddedBitmap>;
 }
 
 pub fn read<T: crate::Read>(mut tape: T) -> Result<Vec<Font<T>>> {
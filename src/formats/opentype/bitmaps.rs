@@ -0,0 +1,387 @@
+//! Embedded bitmaps.
+
+use std::io::Result;
+use std::ops::DerefMut;
+
+use opentype::truetype::GlyphID;
+
+use crate::formats::opentype::cache::Cache;
+use crate::formats::opentype::tables::{self, get_u16, get_u32};
+
+const DEPTH_LIMIT: usize = 16;
+
+/// A bitmap of a glyph.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bitmap {
+    /// The horizontal and vertical sizes in pixels per em of the strike.
+    pub ppem: (u8, u8),
+    /// The width in pixels.
+    pub width: u8,
+    /// The height in pixels.
+    pub height: u8,
+    /// The horizontal and vertical distances from the origin to the top-left
+    /// corner in pixels.
+    pub bearings: (i8, i8),
+    /// The advance width in pixels.
+    pub advance_width: u8,
+    /// The image.
+    pub image: Image,
+}
+
+/// An image of a bitmap.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Image {
+    /// Gray levels of pixels with one byte per pixel in rows from the top.
+    ///
+    /// The levels range up to two to the power of the bit depth minus one, so
+    /// a bit depth of one makes a monochrome image.
+    Gray {
+        /// The bit depth.
+        bit_depth: u8,
+        /// The levels.
+        pixels: Vec<u8>,
+    },
+    /// Data in the PNG format.
+    Png(Vec<u8>),
+}
+
+/// The strikes of a font with their images left in the data table.
+pub(crate) struct Bitmaps {
+    strikes: Vec<Strike>,
+    offset: u64,
+    size: u64,
+}
+
+struct Strike {
+    ppem: (u8, u8),
+    bit_depth: u8,
+    ranges: Vec<Range>,
+}
+
+struct Range {
+    first: GlyphID,
+    last: GlyphID,
+    image_format: u16,
+    image_offset: u64,
+    index: Index,
+}
+
+enum Index {
+    Offsets(Vec<u64>),
+    Constant(u64, Metrics),
+    Sparse(Vec<(GlyphID, u64)>),
+    SparseConstant(u64, Metrics, Vec<GlyphID>),
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Metrics {
+    width: u8,
+    height: u8,
+    bearings: (i8, i8),
+    advance_width: u8,
+}
+
+impl Bitmaps {
+    /// Read the color tables or, if there are none, the monochrome and
+    /// grayscale ones.
+    pub fn read<T: crate::Read>(cache: &mut Cache<T>) -> Result<Option<Self>> {
+        let records = cache.backend.offsets.records.clone();
+        let mut tape = cache.tape.borrow_mut();
+        let tape = tape.deref_mut();
+        for (location, data) in [(b"CBLC", b"CBDT"), (b"EBLC", b"EBDT")] {
+            let record = match records.iter().find(|record| record.tag == data) {
+                Some(record) => record,
+                _ => continue,
+            };
+            if let Some(table) = tables::take(tape, &records, location)? {
+                return match parse(&table) {
+                    Some(strikes) => Ok(Some(Self {
+                        strikes,
+                        offset: record.offset as u64,
+                        size: record.size as u64,
+                    })),
+                    _ => tables::malformed(location),
+                };
+            }
+        }
+        Ok(None)
+    }
+
+    /// Return the bitmap of a glyph from the strike closest to a size in
+    /// pixels per em preferring larger strikes to smaller ones.
+    pub fn get<T: crate::Read>(
+        &self,
+        tape: &mut T,
+        glyph_id: GlyphID,
+        ppem: u16,
+    ) -> Result<Option<Bitmap>> {
+        let strike = self
+            .strikes
+            .iter()
+            .filter(|strike| strike.locate(glyph_id).is_some())
+            .min_by_key(|strike| {
+                let value = strike.ppem.1 as u16;
+                (value < ppem, value.abs_diff(ppem))
+            });
+        match strike {
+            Some(strike) => self.decode(tape, strike, glyph_id, 0).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    fn decode<T: crate::Read>(
+        &self,
+        tape: &mut T,
+        strike: &Strike,
+        glyph_id: GlyphID,
+        depth: usize,
+    ) -> Result<Bitmap> {
+        if depth > DEPTH_LIMIT {
+            raise!("found too deeply nested components in bitmap {}", glyph_id);
+        }
+        let (format, offset, size, metrics) = match strike.locate(glyph_id) {
+            Some(value) => value,
+            _ => raise!("found no bitmap for glyph {}", glyph_id),
+        };
+        if offset + size > self.size {
+            raise!("found a bitmap for glyph {} out of bounds", glyph_id);
+        }
+        tape.jump(self.offset + offset)?;
+        let data = tape.take_bytes(size as usize)?;
+        let result = match format {
+            1 | 2 | 8 | 17 => small(&data).map(|metrics| (metrics, 5)),
+            6 | 7 | 9 | 18 => big(&data).map(|metrics| (metrics, 8)),
+            5 | 19 => metrics.map(|metrics| (metrics, 0)),
+            _ => raise!(
+                "found an unknown format of bitmap {} ({})",
+                glyph_id,
+                format,
+            ),
+        };
+        let (metrics, start) = match result {
+            Some(value) => value,
+            _ => raise!("found malformed metrics of bitmap {}", glyph_id),
+        };
+        let gray = |aligned: bool| {
+            unpack(&data[start..], metrics, strike.bit_depth, aligned).map(|pixels| Image::Gray {
+                bit_depth: strike.bit_depth,
+                pixels,
+            })
+        };
+        let image = match format {
+            1 | 6 => gray(true),
+            2 | 5 | 7 => gray(false),
+            8 | 9 => {
+                let start = if format == 8 { start + 1 } else { start };
+                let components = match components(&data[start.min(data.len())..]) {
+                    Some(value) => value,
+                    _ => raise!("found malformed components of bitmap {}", glyph_id),
+                };
+                let mut pixels = vec![0; metrics.width as usize * metrics.height as usize];
+                for (component, x, y) in components {
+                    let bitmap = self.decode(tape, strike, component, depth + 1)?;
+                    compose(&mut pixels, metrics, &bitmap, (x, y));
+                }
+                Some(Image::Gray {
+                    bit_depth: strike.bit_depth,
+                    pixels,
+                })
+            }
+            _ => get_u32(&data, start).and_then(|size| {
+                data.get((start + 4)..(start + 4 + size as usize))
+                    .map(|data| Image::Png(data.to_vec()))
+            }),
+        };
+        match image {
+            Some(image) => Ok(Bitmap {
+                ppem: strike.ppem,
+                width: metrics.width,
+                height: metrics.height,
+                bearings: metrics.bearings,
+                advance_width: metrics.advance_width,
+                image,
+            }),
+            _ => raise!("found a malformed bitmap for glyph {}", glyph_id),
+        }
+    }
+}
+
+impl Strike {
+    // Return the image format, offset, and size of the image of a glyph and
+    // its metrics if the index gives them.
+    fn locate(&self, glyph_id: GlyphID) -> Option<(u16, u64, u64, Option<Metrics>)> {
+        for range in self.ranges.iter() {
+            if glyph_id < range.first || glyph_id > range.last {
+                continue;
+            }
+            let i = (glyph_id - range.first) as usize;
+            let (offset, size, metrics) = match &range.index {
+                Index::Offsets(offsets) => {
+                    let (start, end) = (*offsets.get(i)?, *offsets.get(i + 1)?);
+                    (start, end.checked_sub(start)?, None)
+                }
+                Index::Constant(size, metrics) => (*size * i as u64, *size, Some(*metrics)),
+                Index::Sparse(glyphs) => {
+                    let j = glyphs.iter().position(|glyph| glyph.0 == glyph_id)?;
+                    let (start, end) = (glyphs[j].1, glyphs.get(j + 1)?.1);
+                    (start, end.checked_sub(start)?, None)
+                }
+                Index::SparseConstant(size, metrics, glyphs) => {
+                    let j = glyphs.iter().position(|&glyph| glyph == glyph_id)?;
+                    (*size * j as u64, *size, Some(*metrics))
+                }
+            };
+            if size == 0 {
+                continue;
+            }
+            return Some((
+                range.image_format,
+                range.image_offset + offset,
+                size,
+                metrics,
+            ));
+        }
+        None
+    }
+}
+
+fn parse(data: &[u8]) -> Option<Vec<Strike>> {
+    let mut strikes = vec![];
+    for i in 0..get_u32(data, 4)? as usize {
+        let record = data.get((8 + 48 * i)..(8 + 48 * i + 48))?;
+        let array = get_u32(record, 0)? as usize;
+        let mut ranges = vec![];
+        for j in 0..get_u32(record, 8)? as usize {
+            let entry = array + 8 * j;
+            let (first, last) = (get_u16(data, entry)?, get_u16(data, entry + 2)?);
+            if first > last {
+                return None;
+            }
+            let position = array + get_u32(data, entry + 4)? as usize;
+            let count = (last - first) as usize + 1;
+            let body = position + 8;
+            let index = match get_u16(data, position)? {
+                1 => Index::Offsets(
+                    (0..=count)
+                        .map(|k| get_u32(data, body + 4 * k).map(u64::from))
+                        .collect::<Option<_>>()?,
+                ),
+                2 => Index::Constant(get_u32(data, body)?.into(), big(data.get(body + 4..)?)?),
+                3 => Index::Offsets(
+                    (0..=count)
+                        .map(|k| get_u16(data, body + 2 * k).map(u64::from))
+                        .collect::<Option<_>>()?,
+                ),
+                4 => Index::Sparse(
+                    (0..=get_u32(data, body)? as usize)
+                        .map(|k| {
+                            let position = body + 4 + 4 * k;
+                            Some((
+                                get_u16(data, position)?,
+                                get_u16(data, position + 2)?.into(),
+                            ))
+                        })
+                        .collect::<Option<_>>()?,
+                ),
+                5 => Index::SparseConstant(
+                    get_u32(data, body)?.into(),
+                    big(data.get(body + 4..)?)?,
+                    (0..get_u32(data, body + 12)? as usize)
+                        .map(|k| get_u16(data, body + 16 + 2 * k))
+                        .collect::<Option<_>>()?,
+                ),
+                _ => continue,
+            };
+            ranges.push(Range {
+                first,
+                last,
+                image_format: get_u16(data, position + 2)?,
+                image_offset: get_u32(data, position + 4)?.into(),
+                index,
+            });
+        }
+        strikes.push(Strike {
+            ppem: (record[44], record[45]),
+            bit_depth: record[46],
+            ranges,
+        });
+    }
+    Some(strikes)
+}
+
+fn small(data: &[u8]) -> Option<Metrics> {
+    let data = data.get(..5)?;
+    Some(Metrics {
+        height: data[0],
+        width: data[1],
+        bearings: (data[2] as i8, data[3] as i8),
+        advance_width: data[4],
+    })
+}
+
+fn big(data: &[u8]) -> Option<Metrics> {
+    let data = data.get(..8)?;
+    small(data)
+}
+
+fn components(data: &[u8]) -> Option<Vec<(GlyphID, i8, i8)>> {
+    (0..get_u16(data, 0)? as usize)
+        .map(|i| {
+            let data = data.get((2 + 4 * i)..(6 + 4 * i))?;
+            Some((
+                u16::from_be_bytes([data[0], data[1]]),
+                data[2] as i8,
+                data[3] as i8,
+            ))
+        })
+        .collect()
+}
+
+// Read pixels with the most significant bits first, padding rows to whole
+// bytes if they are byte-aligned.
+fn unpack(data: &[u8], metrics: Metrics, bit_depth: u8, aligned: bool) -> Option<Vec<u8>> {
+    if !matches!(bit_depth, 1 | 2 | 4 | 8) {
+        return None;
+    }
+    let (width, height, depth) = (
+        metrics.width as usize,
+        metrics.height as usize,
+        bit_depth as usize,
+    );
+    let stride = match aligned {
+        true => (width * depth + 7) & !7,
+        _ => width * depth,
+    };
+    if data.len() * 8 < stride * height {
+        return None;
+    }
+    let mask = ((1u16 << depth) - 1) as u8;
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let bit = y * stride + x * depth;
+            pixels.push((data[bit / 8] >> (8 - depth - bit % 8)) & mask);
+        }
+    }
+    Some(pixels)
+}
+
+// Place a component with its top-left corner at an offset from the top-left
+// corner of a composite bitmap.
+fn compose(pixels: &mut [u8], metrics: Metrics, bitmap: &Bitmap, offset: (i8, i8)) {
+    let other = match &bitmap.image {
+        Image::Gray { pixels, .. } => pixels,
+        _ => return,
+    };
+    let (width, height) = (metrics.width as isize, metrics.height as isize);
+    for y in 0..bitmap.height as isize {
+        for x in 0..bitmap.width as isize {
+            let (i, j) = (x + offset.0 as isize, y + offset.1 as isize);
+            if (0..width).contains(&i) && (0..height).contains(&j) {
+                let value = other[(y * bitmap.width as isize + x) as usize];
+                let pixel = &mut pixels[(j * width + i) as usize];
+                *pixel = (*pixel).max(value);
+            }
+        }
+    }
+}
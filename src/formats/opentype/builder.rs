@@ -0,0 +1,558 @@
+use std::collections::BTreeMap;
+use std::io::Result;
+
+use opentype::truetype::tables::names::language::Windows;
+use opentype::truetype::tables::names::{LanguageID, NameID, Names, PlatformID};
+use opentype::truetype::{GlyphID, Tag};
+
+use crate::formats::opentype::writing::{self, compact, outline, type2};
+use crate::glyph::geometry::Curve;
+use crate::glyph::{conversion, Glyph, Point};
+use crate::{Metrics, Number, Offset, Timestamps};
+
+const NOTDEF: &str = ".notdef";
+const TOLERANCE: Number = 0.5;
+
+/// A flavor of outlines.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Outlines {
+    /// Quadratic curves stored in glyph data, that is, TrueType outlines.
+    Quadratic,
+    /// Cubic curves stored in a font set, that is, PostScript outlines.
+    Cubic,
+}
+
+/// A builder of fonts.
+///
+/// Glyphs are identified by their names, and the glyph named `.notdef` is
+/// placed first, being added empty unless given. The other glyphs follow in
+/// the order they were added. Only the advance widths and contours of glyphs
+/// are taken into account, and contours are closed implicitly.
+#[derive(Clone, Debug, Default)]
+pub struct FontBuilder {
+    metrics: Metrics,
+    glyphs: Vec<(String, Glyph)>,
+    characters: BTreeMap<char, String>,
+    names: Vec<(NameID, String)>,
+    timestamps: Timestamps,
+}
+
+enum Step {
+    Linear(Offset),
+    Quadratic(Offset, Offset),
+    Cubic(Offset, Offset, Offset),
+}
+
+struct Outline {
+    advance_width: u16,
+    bounding_box: Option<[i16; 4]>,
+    data: Vec<u8>,
+    point_count: usize,
+    contour_count: usize,
+}
+
+impl FontBuilder {
+    /// Create a builder.
+    pub fn new(metrics: Metrics) -> Self {
+        Self {
+            metrics,
+            ..Default::default()
+        }
+    }
+
+    /// Add a glyph replacing the one with the same name, if any.
+    pub fn glyph(&mut self, name: &str, glyph: Glyph) -> &mut Self {
+        match self.glyphs.iter_mut().find(|(other, _)| other == name) {
+            Some((_, other)) => *other = glyph,
+            _ => self.glyphs.push((name.to_string(), glyph)),
+        }
+        self
+    }
+
+    /// Map a character to the glyph with the given name.
+    pub fn character(&mut self, character: char, name: &str) -> &mut Self {
+        self.characters.insert(character, name.to_string());
+        self
+    }
+
+    /// Set a name.
+    pub fn name(&mut self, id: NameID, value: &str) -> &mut Self {
+        match self.names.iter_mut().find(|(other, _)| *other == id) {
+            Some((_, other)) => *other = value.to_string(),
+            _ => self.names.push((id, value.to_string())),
+        }
+        self
+    }
+
+    /// Set the timestamps.
+    pub fn timestamps(&mut self, value: Timestamps) -> &mut Self {
+        self.timestamps = value;
+        self
+    }
+
+    /// Build the font with the given flavor of outlines.
+    ///
+    /// Quadratic outlines approximate cubic curves, and cubic outlines
+    /// represent quadratic curves exactly.
+    pub fn build(&self, outlines: Outlines) -> Result<Vec<u8>> {
+        let granularity = self.metrics.granularity;
+        if !(16.0..=16384.0).contains(&granularity) {
+            raise!("found an invalid granularity {}", granularity);
+        }
+        let granularity = granularity as u16;
+
+        let empty = Glyph::default();
+        let mut glyphs = vec![(NOTDEF, &empty)];
+        for (name, glyph) in self.glyphs.iter() {
+            if name.is_empty() || name.len() > 63 || !name.is_ascii() {
+                raise!("found an invalid glyph name {:?}", name);
+            }
+            if name == NOTDEF {
+                glyphs[0].1 = glyph;
+            } else {
+                glyphs.push((name, glyph));
+            }
+        }
+        let glyph_indices = glyphs
+            .iter()
+            .enumerate()
+            .map(|(i, (name, _))| (*name, i as GlyphID))
+            .collect::<BTreeMap<_, _>>();
+        let mut character_mapping = BTreeMap::default();
+        for (&character, name) in self.characters.iter() {
+            match glyph_indices.get(name.as_str()) {
+                Some(&glyph_id) => character_mapping.insert(character as u32, glyph_id),
+                _ => raise!("found no glyph named {:?}", name),
+            };
+        }
+
+        let shapes = glyphs
+            .iter()
+            .map(|(_, glyph)| match outlines {
+                Outlines::Quadratic => quadratic(glyph),
+                Outlines::Cubic => cubic(glyph),
+            })
+            .collect::<Vec<_>>();
+        let bounding_box = shapes
+            .iter()
+            .filter_map(|outline| outline.bounding_box)
+            .reduce(|one, other| {
+                [
+                    one[0].min(other[0]),
+                    one[1].min(other[1]),
+                    one[2].max(other[2]),
+                    one[3].max(other[3]),
+                ]
+            })
+            .unwrap_or_default();
+
+        let mut tables = vec![];
+        let format = match outlines {
+            Outlines::Quadratic => {
+                let data = shapes
+                    .iter()
+                    .map(|outline| outline.data.clone())
+                    .collect::<Vec<_>>();
+                let (data, mapping, format) = writing::locate(&data);
+                tables.push((Tag(*b"glyf"), data));
+                tables.push((Tag(*b"loca"), mapping));
+                format
+            }
+            Outlines::Cubic => {
+                let name = self.postscript_name();
+                let names = glyphs.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+                let strings = shapes
+                    .iter()
+                    .map(|outline| outline.data.clone())
+                    .collect::<Vec<_>>();
+                tables.push((
+                    Tag(*b"CFF "),
+                    compact::compose(&name, &names, &strings, bounding_box, granularity)?,
+                ));
+                0
+            }
+        };
+
+        let round = |value: Number| -> i16 {
+            match value.is_finite() {
+                true => value.round() as i16,
+                _ => 0,
+            }
+        };
+        let fraction = |value: Number| -> i16 { round(value * granularity as Number) };
+
+        let mut data = vec![];
+        data.extend(0x0001_0000u32.to_be_bytes());
+        data.extend(0x0001_0000u32.to_be_bytes());
+        data.extend(0u32.to_be_bytes());
+        data.extend(0x5F0F_3CF5u32.to_be_bytes());
+        data.extend(0b11u16.to_be_bytes());
+        data.extend(granularity.to_be_bytes());
+        data.extend(self.timestamps.creation.to_be_bytes());
+        data.extend(self.timestamps.modification.to_be_bytes());
+        bounding_box
+            .iter()
+            .for_each(|value| data.extend(value.to_be_bytes()));
+        data.extend(0u16.to_be_bytes());
+        data.extend(8u16.to_be_bytes());
+        data.extend(2i16.to_be_bytes());
+        data.extend(format.to_be_bytes());
+        data.extend(0i16.to_be_bytes());
+        tables.push((Tag(*b"head"), data));
+
+        let advances = shapes
+            .iter()
+            .map(|outline| {
+                let left = outline.bounding_box.map(|value| value[0]).unwrap_or(0);
+                (outline.advance_width, left)
+            })
+            .collect::<Vec<_>>();
+        let (data, count) = writing::horizontal_metrics(&advances);
+        tables.push((Tag(*b"hmtx"), data));
+
+        let drawn = || {
+            shapes.iter().filter_map(|outline| {
+                outline
+                    .bounding_box
+                    .map(|value| (outline.advance_width as i16, value))
+            })
+        };
+        let mut data = vec![];
+        data.extend(0x0001_0000u32.to_be_bytes());
+        for value in [
+            round(self.metrics.ascender),
+            round(self.metrics.descender),
+            round(self.metrics.line_gap),
+        ] {
+            data.extend(value.to_be_bytes());
+        }
+        let advance_width = advances.iter().map(|value| value.0).max().unwrap_or(0);
+        data.extend(advance_width.to_be_bytes());
+        for value in [
+            drawn().map(|(_, value)| value[0]).min().unwrap_or(0),
+            drawn()
+                .map(|(advance, value)| advance.saturating_sub(value[2]))
+                .min()
+                .unwrap_or(0),
+            drawn().map(|(_, value)| value[2]).max().unwrap_or(0),
+            1,
+        ] {
+            data.extend(value.to_be_bytes());
+        }
+        data.extend([0; 14]);
+        data.extend(count.to_be_bytes());
+        tables.push((Tag(*b"hhea"), data));
+
+        let mut data = vec![];
+        match outlines {
+            Outlines::Quadratic => {
+                data.extend(0x0001_0000u32.to_be_bytes());
+                data.extend((glyphs.len() as u16).to_be_bytes());
+                let points = shapes.iter().map(|outline| outline.point_count).max();
+                let contours = shapes.iter().map(|outline| outline.contour_count).max();
+                data.extend((points.unwrap_or(0) as u16).to_be_bytes());
+                data.extend((contours.unwrap_or(0) as u16).to_be_bytes());
+                data.extend([0; 4]);
+                data.extend(2u16.to_be_bytes());
+                data.extend([0; 16]);
+            }
+            Outlines::Cubic => {
+                data.extend(0x0000_5000u32.to_be_bytes());
+                data.extend((glyphs.len() as u16).to_be_bytes());
+            }
+        }
+        tables.push((Tag(*b"maxp"), data));
+
+        let mut data = vec![];
+        let widths = advances
+            .iter()
+            .map(|value| value.0 as i32)
+            .filter(|&value| value > 0)
+            .collect::<Vec<_>>();
+        let average = widths.iter().sum::<i32>() / (widths.len() as i32).max(1);
+        for value in [4, average as i16, 400, 5, 0] {
+            data.extend(value.to_be_bytes());
+        }
+        for value in [0.65, 0.6, 0.0, 0.075, 0.65, 0.6, 0.0, 0.35, 0.05] {
+            data.extend(fraction(value).to_be_bytes());
+        }
+        let strikeout = match self.metrics.x_height.is_finite() {
+            true => round(self.metrics.x_height / 2.0),
+            _ => fraction(0.25),
+        };
+        data.extend(strikeout.to_be_bytes());
+        data.extend([0; 2 + 10 + 16]);
+        data.extend(b"NONE");
+        data.extend(0x00C0u16.to_be_bytes());
+        data.extend([0; 4]);
+        let ascender = match self.metrics.clipping_ascender.is_finite() {
+            true => round(self.metrics.clipping_ascender),
+            _ => bounding_box[3].max(round(self.metrics.ascender)),
+        };
+        let descender = match self.metrics.clipping_descender.is_finite() {
+            true => round(self.metrics.clipping_descender),
+            _ => bounding_box[1].min(round(self.metrics.descender)),
+        };
+        for value in [
+            round(self.metrics.ascender),
+            round(self.metrics.descender),
+            round(self.metrics.line_gap),
+            ascender.max(0),
+            descender.min(0).saturating_neg(),
+        ] {
+            data.extend(value.to_be_bytes());
+        }
+        data.extend(1u32.to_be_bytes());
+        data.extend(0u32.to_be_bytes());
+        for value in [
+            round(self.metrics.x_height),
+            round(self.metrics.cap_height),
+            0,
+            32,
+            0,
+        ] {
+            data.extend(value.to_be_bytes());
+        }
+        writing::windows_metrics(&mut data, &character_mapping);
+        tables.push((Tag(*b"OS/2"), data));
+
+        let mut data = vec![];
+        let version: u32 = match outlines {
+            Outlines::Quadratic => 0x0002_0000,
+            Outlines::Cubic => 0x0003_0000,
+        };
+        data.extend(version.to_be_bytes());
+        data.extend(0u32.to_be_bytes());
+        data.extend(fraction(-0.075).to_be_bytes());
+        data.extend(fraction(0.05).to_be_bytes());
+        data.extend([0; 20]);
+        if outlines == Outlines::Quadratic {
+            data.extend((glyphs.len() as u16).to_be_bytes());
+            let mut names = vec![];
+            for (name, _) in glyphs.iter() {
+                if *name == NOTDEF {
+                    data.extend(0u16.to_be_bytes());
+                } else {
+                    data.extend((258 + names.len() as u16).to_be_bytes());
+                    names.push(*name);
+                }
+            }
+            for name in names {
+                data.push(name.len() as u8);
+                data.extend(name.as_bytes());
+            }
+        }
+        tables.push((Tag(*b"post"), data));
+
+        tables.push((
+            Tag(*b"cmap"),
+            writing::character_mapping(&character_mapping)?,
+        ));
+
+        let records = self.names.iter().map(|(id, value)| {
+            (
+                (
+                    PlatformID::Windows,
+                    1,
+                    LanguageID::Windows(Windows::EnglishUnitedStates),
+                    *id,
+                ),
+                value,
+            )
+        });
+        let names = Names::from_iter(records, Vec::<String>::new(), &mut Default::default())?;
+        let mut data = vec![];
+        crate::Write::give(&mut data, &names)?;
+        tables.push((Tag(*b"name"), data));
+
+        let version = match outlines {
+            Outlines::Quadratic => 0x0001_0000,
+            Outlines::Cubic => u32::from_be_bytes(*b"OTTO"),
+        };
+        writing::assemble(version, tables)
+    }
+
+    fn postscript_name(&self) -> String {
+        let find = |id: NameID| {
+            self.names
+                .iter()
+                .find(|(other, _)| *other == id)
+                .map(|(_, value)| value)
+        };
+        let name = find(NameID::PostScriptFontName)
+            .or_else(|| find(NameID::FullFontName))
+            .or_else(|| find(NameID::FontFamilyName))
+            .map(|value| {
+                value
+                    .chars()
+                    .filter(|character| character.is_ascii_graphic())
+                    .filter(|character| !"[](){}<>/%".contains(*character))
+                    .take(63)
+                    .collect::<String>()
+            })
+            .unwrap_or_default();
+        match name.is_empty() {
+            true => "Untitled".into(),
+            _ => name,
+        }
+    }
+}
+
+fn steps(glyph: &Glyph) -> Vec<(Offset, Vec<Step>)> {
+    let mut contours = vec![];
+    for contour in glyph.contours.iter() {
+        let mut points = contour.points();
+        let start = match points.next() {
+            Some(value) => value.offset(),
+            _ => continue,
+        };
+        let mut steps = Vec::with_capacity(contour.segments.len());
+        let mut controls = vec![];
+        for point in points {
+            match point {
+                Point::Off(value) => controls.push(value),
+                Point::On(value) => {
+                    steps.push(match *controls {
+                        [] => Step::Linear(value),
+                        [a] => Step::Quadratic(a, value),
+                        _ => Step::Cubic(controls[0], controls[1], value),
+                    });
+                    controls.clear();
+                }
+            }
+        }
+        contours.push((start, steps));
+    }
+    contours
+}
+
+fn quadratic(glyph: &Glyph) -> Outline {
+    let point = |value: Offset, on: bool| (round(value.0), round(value.1), on);
+    let mut contours = vec![];
+    for (start, steps) in steps(glyph) {
+        let mut points = vec![point(start, true)];
+        let mut current = start;
+        for step in steps {
+            match step {
+                Step::Linear(a) => {
+                    points.push(point(a, true));
+                    current = a;
+                }
+                Step::Quadratic(a, b) => {
+                    points.push(point(a, false));
+                    points.push(point(b, true));
+                    current = b;
+                }
+                Step::Cubic(a, b, c) => {
+                    for (control, end) in conversion::approximate(current, a, b, c, TOLERANCE) {
+                        points.push(point(control, false));
+                        points.push(point(end, true));
+                    }
+                    current = c;
+                }
+            }
+        }
+        if points.len() > 1 && points.last() == points.first() {
+            points.pop();
+        }
+        if points.len() > 1 {
+            contours.push(points);
+        }
+    }
+    let data = outline::encode(&contours);
+    let points = contours.iter().flatten().collect::<Vec<_>>();
+    let bounding_box = match points.is_empty() {
+        true => None,
+        _ => Some([
+            points.iter().map(|point| point.0).min().unwrap(),
+            points.iter().map(|point| point.1).min().unwrap(),
+            points.iter().map(|point| point.0).max().unwrap(),
+            points.iter().map(|point| point.1).max().unwrap(),
+        ]),
+    };
+    Outline {
+        advance_width: width(glyph),
+        bounding_box,
+        data,
+        point_count: points.len(),
+        contour_count: contours.len(),
+    }
+}
+
+fn cubic(glyph: &Glyph) -> Outline {
+    let point = |value: Offset| (round(value.0) as i32, round(value.1) as i32);
+    let offset = |(x, y): (i32, i32)| Offset(x as Number, y as Number);
+    let mut contours = vec![];
+    let mut bounding_box: Option<[i16; 4]> = None;
+    let mut include = |(x, y): (i32, i32)| {
+        let (x, y) = (x as i16, y as i16);
+        bounding_box = Some(match bounding_box {
+            Some(value) => [
+                value[0].min(x),
+                value[1].min(y),
+                value[2].max(x),
+                value[3].max(y),
+            ],
+            _ => [x, y, x, y],
+        });
+    };
+    for (start, steps) in steps(glyph) {
+        let mut current = point(start);
+        let mut points = vec![vec![current]];
+        for step in steps {
+            let step = match step {
+                Step::Linear(a) => vec![point(a)],
+                Step::Quadratic(a, b) => {
+                    let one = Offset(current.0 as Number, current.1 as Number);
+                    let (a, b, c) = conversion::elevate(one, a, b);
+                    vec![point(a), point(b), point(c)]
+                }
+                Step::Cubic(a, b, c) => vec![point(a), point(b), point(c)],
+            };
+            current = *step.last().unwrap();
+            points.push(step);
+        }
+        if points.len() > 1 {
+            include(points[0][0]);
+            let mut last = points[0][0];
+            for step in points.iter().skip(1) {
+                match step.len() {
+                    1 => include(step[0]),
+                    _ => {
+                        let curve = Curve::Cubic(
+                            offset(last),
+                            offset(step[0]),
+                            offset(step[1]),
+                            offset(step[2]),
+                        );
+                        for value in curve.extrema() {
+                            include(point(value));
+                        }
+                    }
+                }
+                last = *step.last().unwrap();
+            }
+            contours.push(points);
+        }
+    }
+    Outline {
+        advance_width: width(glyph),
+        bounding_box,
+        data: type2::encode(width(glyph) as i32, &contours),
+        point_count: 0,
+        contour_count: contours.len(),
+    }
+}
+
+#[inline]
+fn round(value: Number) -> i16 {
+    value.round().clamp(i16::MIN as Number, i16::MAX as Number) as i16
+}
+
+#[inline]
+fn width(glyph: &Glyph) -> u16 {
+    match glyph.advance_width.is_finite() {
+        true => glyph.advance_width.round().clamp(0.0, u16::MAX as Number) as u16,
+        _ => 0,
+    }
+}
@@ -5,6 +5,12 @@ use std::rc::Rc;
 
 use opentype;
 
+use crate::formats::opentype::bitmaps::Bitmaps;
+use crate::formats::opentype::devices::{
+    self, DeviceWidths, GridFitting, LinearThresholds, VerticalDeviceMetrics,
+};
+use crate::formats::opentype::hinting::Hinting;
+use crate::formats::opentype::kerning::Kerning;
 use crate::formats::opentype::mapping;
 use crate::formats::opentype::metrics::Metrics;
 
@@ -24,6 +30,13 @@ macro_rules! cache(
                     forward_mapping: Default::default(),
                     reverse_mapping: Default::default(),
                     metrics: Default::default(),
+                    kerning: Default::default(),
+                    hinting: Default::default(),
+                    grid_fitting: Default::default(),
+                    device_widths: Default::default(),
+                    linear_thresholds: Default::default(),
+                    vertical_device_metrics: Default::default(),
+                    bitmaps: Default::default(),
 
                     $($field: Default::default(),)+
                 }
@@ -40,6 +53,13 @@ macro_rules! cache(
             forward_mapping: Option<Rc<mapping::Forward>>,
             reverse_mapping: Option<Rc<mapping::Reverse>>,
             metrics: Option<Rc<Metrics>>,
+            kerning: Option<Rc<Kerning>>,
+            hinting: Option<Reference<Hinting>>,
+            grid_fitting: Option<Option<Rc<GridFitting>>>,
+            device_widths: Option<Option<Rc<DeviceWidths>>>,
+            linear_thresholds: Option<Option<Rc<LinearThresholds>>>,
+            vertical_device_metrics: Option<Option<Rc<VerticalDeviceMetrics>>>,
+            bitmaps: Option<Option<Rc<Bitmaps>>>,
 
             $(pub $field: Option<Reference<$type>>,)+
         }
@@ -201,4 +221,60 @@ impl<T: crate::Read> Cache<T> {
         }
         Ok(self.metrics.as_ref().unwrap())
     }
+
+    pub fn kerning(&mut self) -> Result<&Rc<Kerning>> {
+        if self.kerning.is_none() {
+            let value = Kerning::read(self)?;
+            self.kerning = Some(Rc::new(value));
+        }
+        Ok(self.kerning.as_ref().unwrap())
+    }
+
+    pub fn hinting(&mut self) -> Result<&Reference<Hinting>> {
+        if self.hinting.is_none() {
+            let value = Hinting::read(self)?;
+            self.hinting = Some(Rc::new(RefCell::new(value)));
+        }
+        Ok(self.hinting.as_ref().unwrap())
+    }
+
+    pub fn grid_fitting(&mut self) -> Result<Option<&Rc<GridFitting>>> {
+        if self.grid_fitting.is_none() {
+            let value = devices::read_grid_fitting(self)?;
+            self.grid_fitting = Some(value.map(Rc::new));
+        }
+        Ok(self.grid_fitting.as_ref().unwrap().as_ref())
+    }
+
+    pub fn device_widths(&mut self) -> Result<Option<&Rc<DeviceWidths>>> {
+        if self.device_widths.is_none() {
+            let value = devices::read_device_widths(self)?;
+            self.device_widths = Some(value.map(Rc::new));
+        }
+        Ok(self.device_widths.as_ref().unwrap().as_ref())
+    }
+
+    pub fn linear_thresholds(&mut self) -> Result<Option<&Rc<LinearThresholds>>> {
+        if self.linear_thresholds.is_none() {
+            let value = devices::read_linear_thresholds(self)?;
+            self.linear_thresholds = Some(value.map(Rc::new));
+        }
+        Ok(self.linear_thresholds.as_ref().unwrap().as_ref())
+    }
+
+    pub fn vertical_device_metrics(&mut self) -> Result<Option<&Rc<VerticalDeviceMetrics>>> {
+        if self.vertical_device_metrics.is_none() {
+            let value = devices::read_vertical_device_metrics(self)?;
+            self.vertical_device_metrics = Some(value.map(Rc::new));
+        }
+        Ok(self.vertical_device_metrics.as_ref().unwrap().as_ref())
+    }
+
+    pub fn bitmaps(&mut self) -> Result<Option<&Rc<Bitmaps>>> {
+        if self.bitmaps.is_none() {
+            let value = Bitmaps::read(self)?;
+            self.bitmaps = Some(value.map(Rc::new));
+        }
+        Ok(self.bitmaps.as_ref().unwrap().as_ref())
+    }
 }
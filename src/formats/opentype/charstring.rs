@@ -0,0 +1,337 @@
+use std::io::Result;
+use std::ops::Range;
+
+const CALLGSUBR: u8 = 29;
+const CALLSUBR: u8 = 10;
+const CNTRMASK: u8 = 20;
+const ENDCHAR: u8 = 14;
+const ESCAPE: u8 = 12;
+const HINTMASK: u8 = 19;
+const HSTEM: u8 = 1;
+const HSTEMHM: u8 = 18;
+const RETURN: u8 = 11;
+const VSTEM: u8 = 3;
+const VSTEMHM: u8 = 23;
+
+const DEPTH: usize = 10;
+
+/// A hint mask of a character string.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mask {
+    /// The flag of counter masks.
+    pub counter: bool,
+    /// The vertical stems implied by the mask as pairs of relative edges.
+    pub stems: Vec<f32>,
+    /// The bytes.
+    pub data: Vec<u8>,
+}
+
+enum Token<'l> {
+    Operand(&'l [u8]),
+    Operation(&'l [u8]),
+    Call,
+}
+
+struct State<'l> {
+    global: &'l [&'l [u8]],
+    local: &'l [&'l [u8]],
+    operands: Vec<Option<i32>>,
+    stems: usize,
+}
+
+/// Expand the subroutine calls of a character string.
+///
+/// The expansion follows the same rules as `Program` does when interpreting
+/// the string, but the string is rewritten at the level of bytes so that hint
+/// masks are retained as is.
+pub fn flatten(code: &[u8], global: &[&[u8]], local: &[&[u8]]) -> Result<Vec<u8>> {
+    let mut output = Vec::with_capacity(code.len());
+    let mut positions = vec![];
+    walk(code, global, local, |token| match token {
+        Token::Operand(data) => {
+            positions.push(output.len());
+            output.extend_from_slice(data);
+        }
+        Token::Operation(data) => {
+            output.extend_from_slice(data);
+            positions.clear();
+        }
+        Token::Call => {
+            if let Some(position) = positions.pop() {
+                output.truncate(position);
+            }
+        }
+    })?;
+    Ok(output)
+}
+
+/// Return the offset of the accent and the standard codes of the base and
+/// accent characters if a character string ends with the deprecated form of
+/// the end operator composing an accented character.
+pub fn accent(
+    code: &[u8],
+    global: &[&[u8]],
+    local: &[&[u8]],
+) -> Result<Option<(i32, i32, u8, u8)>> {
+    let mut operands = vec![];
+    let mut value = None;
+    walk(code, global, local, |token| match token {
+        Token::Operand(data) => operands.push(integer(data)),
+        Token::Operation(data) => {
+            let count = operands.len();
+            if data[0] == ENDCHAR && (count == 4 || count == 5) {
+                if let [Some(x), Some(y), Some(base), Some(accent)] = operands[(count - 4)..] {
+                    if let (Ok(base), Ok(accent)) = (u8::try_from(base), u8::try_from(accent)) {
+                        value = Some((x, y, base, accent));
+                    }
+                }
+            }
+            operands.clear();
+        }
+        Token::Call => {
+            operands.pop();
+        }
+    })?;
+    Ok(value)
+}
+
+/// Return the hint masks of a character string in order.
+///
+/// The subroutines are followed without expanding them, and the advance width
+/// is left out of the implied stems.
+pub fn masks(code: &[u8], global: &[&[u8]], local: &[&[u8]]) -> Result<Vec<Mask>> {
+    let mut operands = vec![];
+    let mut masks = vec![];
+    walk(code, global, local, |token| match token {
+        Token::Operand(data) => operands.push(real(data)),
+        Token::Operation(data) => {
+            if matches!(data[0], HINTMASK | CNTRMASK) {
+                if operands.len() % 2 == 1 {
+                    operands.remove(0);
+                }
+                masks.push(Mask {
+                    counter: data[0] == CNTRMASK,
+                    stems: std::mem::take(&mut operands),
+                    data: data[1..].to_vec(),
+                });
+            }
+            operands.clear();
+        }
+        Token::Call => {
+            operands.pop();
+        }
+    })?;
+    Ok(masks)
+}
+
+impl<'l> State<'l> {
+    fn walk<F>(&mut self, code: &'l [u8], depth: usize, visit: &mut F) -> Result<bool>
+    where
+        F: FnMut(Token<'l>),
+    {
+        if depth > DEPTH {
+            raise!("found too deeply nested subroutines");
+        }
+        let mut i = 0;
+        while i < code.len() {
+            let size = operand(code, i)?;
+            if size > 0 {
+                self.operands.push(integer(&code[i..(i + size)]));
+                visit(Token::Operand(&code[i..(i + size)]));
+                i += size;
+                continue;
+            }
+            let operator = code[i];
+            match operator {
+                CALLSUBR | CALLGSUBR => {
+                    let value = match self.operands.pop() {
+                        Some(Some(value)) => value,
+                        _ => raise!("found a subroutine call with a computed index"),
+                    };
+                    visit(Token::Call);
+                    let subroutines = if operator == CALLSUBR {
+                        self.local
+                    } else {
+                        self.global
+                    };
+                    let index = value + bias(subroutines.len());
+                    let subroutine = match usize::try_from(index)
+                        .ok()
+                        .and_then(|index| subroutines.get(index))
+                    {
+                        Some(subroutine) => *subroutine,
+                        _ => raise!("found no subroutine with index {}", value),
+                    };
+                    if self.walk(subroutine, depth + 1, visit)? {
+                        return Ok(true);
+                    }
+                    i += 1;
+                }
+                RETURN => return Ok(false),
+                _ => {
+                    let size = operation(code, i, &mut self.stems, self.operands.len())?;
+                    visit(Token::Operation(&code[i..(i + size)]));
+                    self.operands.clear();
+                    if operator == ENDCHAR {
+                        return Ok(true);
+                    }
+                    i += size;
+                }
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Return the bias of subroutine indices given the number of subroutines.
+pub fn bias(count: usize) -> i32 {
+    if count < 1240 {
+        107
+    } else if count < 33900 {
+        1131
+    } else {
+        32768
+    }
+}
+
+fn integer(data: &[u8]) -> Option<i32> {
+    let value = data[0] as i32;
+    match value {
+        28 => Some(i16::from_be_bytes([data[1], data[2]]) as i32),
+        32..=246 => Some(value - 139),
+        247..=250 => Some((value - 247) * 256 + data[1] as i32 + 108),
+        251..=254 => Some(-(value - 251) * 256 - data[1] as i32 - 108),
+        _ => None,
+    }
+}
+
+fn operand(code: &[u8], i: usize) -> Result<usize> {
+    let size = match code[i] {
+        28 => 3,
+        32..=246 => 1,
+        247..=254 => 2,
+        255 => 5,
+        _ => return Ok(0),
+    };
+    if i + size > code.len() {
+        raise!("found a malformed character string");
+    }
+    Ok(size)
+}
+
+fn operation(code: &[u8], i: usize, stems: &mut usize, operands: usize) -> Result<usize> {
+    let size = match code[i] {
+        ESCAPE => 2,
+        HSTEM | VSTEM | HSTEMHM | VSTEMHM => {
+            *stems += operands / 2;
+            1
+        }
+        HINTMASK | CNTRMASK => {
+            *stems += operands / 2;
+            1 + (*stems).div_ceil(8)
+        }
+        _ => 1,
+    };
+    if i + size > code.len() {
+        raise!("found a malformed character string");
+    }
+    Ok(size)
+}
+
+fn real(data: &[u8]) -> f32 {
+    match integer(data) {
+        Some(value) => value as f32,
+        _ => i32::from_be_bytes([data[1], data[2], data[3], data[4]]) as f32 / 65536.0,
+    }
+}
+
+/// Split a character string without subroutine calls into operations with
+/// their operands.
+pub fn split(code: &[u8]) -> Result<Vec<Range<usize>>> {
+    let mut values = vec![];
+    let mut stems = 0;
+    let mut operands = 0;
+    let mut start = 0;
+    let mut i = 0;
+    while i < code.len() {
+        let size = operand(code, i)?;
+        if size > 0 {
+            operands += 1;
+            i += size;
+            continue;
+        }
+        if matches!(code[i], CALLSUBR | CALLGSUBR | RETURN) {
+            raise!("found an unexpected subroutine operator");
+        }
+        i += operation(code, i, &mut stems, operands)?;
+        values.push(start..i);
+        operands = 0;
+        start = i;
+    }
+    if start != code.len() {
+        raise!("found a malformed character string");
+    }
+    Ok(values)
+}
+
+// Follow a character string through the subroutines it calls up to the end
+// operator.
+fn walk<'l, F>(
+    code: &'l [u8],
+    global: &'l [&'l [u8]],
+    local: &'l [&'l [u8]],
+    mut visit: F,
+) -> Result<()>
+where
+    F: FnMut(Token<'l>),
+{
+    let mut state = State {
+        global,
+        local,
+        operands: vec![],
+        stems: 0,
+    };
+    state.walk(code, 0, &mut visit)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    macro_rules! ok(($result:expr) => ($result.unwrap()));
+
+    #[test]
+    fn accent() {
+        let code = [149, 159, 204, 247, 86, 14];
+        assert_eq!(ok!(super::accent(&code, &[], &[])), Some((10, 20, 65, 194)));
+
+        let code = [239, 149, 159, 204, 247, 86, 14];
+        assert_eq!(ok!(super::accent(&code, &[], &[])), Some((10, 20, 65, 194)));
+
+        let local: &[u8] = &[204, 247, 86, 11];
+        let code = [149, 159, 32, 10, 14];
+        assert_eq!(
+            ok!(super::accent(&code, &[], &[local])),
+            Some((10, 20, 65, 194))
+        );
+
+        let code = [149, 159, 21, 149, 5, 14];
+        assert_eq!(ok!(super::accent(&code, &[], &[])), None);
+
+        let code = [239, 14];
+        assert_eq!(ok!(super::accent(&code, &[], &[])), None);
+    }
+
+    #[test]
+    fn masks() {
+        let local: &[u8] = &[149, 159, 1, 11];
+        let code = [32, 10, 239, 159, 19, 0b1100_0000, 14];
+        assert_eq!(
+            ok!(super::masks(&code, &[], &[local])),
+            [super::Mask {
+                counter: false,
+                stems: vec![100.0, 20.0],
+                data: vec![0b1100_0000],
+            }],
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io::Result;
+use std::ops::DerefMut;
+
+use opentype::truetype::tables::offsets::Offsets;
+use opentype::truetype::Tag;
+
+use crate::formats::opentype::Font;
+
+table! {
+    @write
+    /// The header of a collection.
+    pub Header {
+        tag           (Tag) = { Tag(*b"ttcf") }, // ttcTag
+        major_version (u16), // majorVersion
+        minor_version (u16) = { 0 }, // minorVersion
+        font_count    (u32), // numFonts
+
+        offsets (Vec<u32>) |this, tape| { // tableDirectoryOffsets
+            tape.take_given(this.font_count as usize)
+        },
+    }
+}
+
+/// Write a collection of fonts.
+///
+/// Tables that are byte-identical across the fonts are stored only once. The
+/// version should be either 1 or 2; in the latter case, the file carries no
+/// digital signature.
+pub fn write_collection<T, U>(fonts: Vec<Font<T>>, tape: &mut U, version: u16) -> Result<()>
+where
+    T: crate::Read + 'static,
+    U: crate::Read + crate::Write,
+{
+    if !matches!(version, 1 | 2) {
+        raise!("found an unknown version of a collection ({version})");
+    }
+
+    let mut directories = Vec::with_capacity(fonts.len());
+    let mut blobs: Vec<Vec<u8>> = vec![];
+    let mut indices = vec![];
+    let mut hashes = HashMap::<u64, Vec<usize>>::default();
+    for font in fonts.iter() {
+        let cache = font.cache.borrow();
+        let mut other = cache.tape.borrow_mut();
+        let mut font_indices = Vec::with_capacity(cache.backend.offsets.records.len());
+        for record in cache.backend.offsets.records.iter() {
+            other.jump(record.offset as u64)?;
+            let data = other.deref_mut().take_bytes(record.size as usize)?;
+            let mut hasher = DefaultHasher::new();
+            data.hash(&mut hasher);
+            let candidates = hashes.entry(hasher.finish()).or_default();
+            let index = match candidates.iter().find(|&&index| blobs[index] == data) {
+                Some(&index) => index,
+                _ => {
+                    candidates.push(blobs.len());
+                    blobs.push(data);
+                    blobs.len() - 1
+                }
+            };
+            font_indices.push(index);
+        }
+        directories.push(cache.backend.offsets.clone());
+        indices.push(font_indices);
+    }
+
+    let start = tape.position()?;
+    let mut header = Header {
+        tag: Tag(*b"ttcf"),
+        major_version: version,
+        minor_version: 0,
+        font_count: directories.len() as u32,
+        offsets: vec![0; directories.len()],
+    };
+    let mut size = 12 + 4 * directories.len() as u64;
+    if version == 2 {
+        size += 12;
+    }
+    for (directory, offset) in directories.iter().zip(header.offsets.iter_mut()) {
+        *offset = size as u32;
+        size += 12 + 16 * directory.records.len() as u64;
+    }
+
+    tape.jump(start + size)?;
+    let mut positions = Vec::with_capacity(blobs.len());
+    for blob in blobs.iter() {
+        positions.push(tape.position()?);
+        tape.give_bytes(blob)?;
+        super::font::pad(tape, blob.len())?;
+    }
+    let end = tape.position()?;
+
+    tape.jump(start)?;
+    tape.give(&header)?;
+    if version == 2 {
+        tape.give(&[0u32; 3][..])?;
+    }
+    for (directory, indices) in directories.iter_mut().zip(indices.iter()) {
+        for (record, &index) in directory.records.iter_mut().zip(indices.iter()) {
+            record.offset = positions[index] as u32;
+        }
+        tape.give::<Offsets>(directory)?;
+    }
+    tape.jump(end)?;
+    Ok(())
+}
+
+pub(super) fn read<T: crate::Read>(tape: &mut T) -> Result<Vec<opentype::Font>> {
+    let start = tape.position()?;
+    let header = tape.take::<Header>()?;
+    let mut fonts = Vec::with_capacity(header.offsets.len());
+    for offset in header.offsets.iter() {
+        tape.jump(start + *offset as u64)?;
+        fonts.push(opentype::Font::read(tape)?);
+    }
+    Ok(fonts)
+}
@@ -0,0 +1,255 @@
+//! Device tables.
+
+use std::io::Result;
+use std::ops::DerefMut;
+
+use opentype::truetype::GlyphID;
+
+use crate::formats::opentype::cache::Cache;
+use crate::formats::opentype::tables::{self, get_u16};
+
+/// Rasterization behavior for ranges of sizes given by the grid-fitting and
+/// scan-conversion procedure table (`gasp`).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GridFitting {
+    /// The ranges given by their upper sizes in pixels per em, which are
+    /// inclusive, in increasing order.
+    pub ranges: Vec<(u16, Behavior)>,
+}
+
+/// A rasterization behavior.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Behavior {
+    /// The flag of grid-fitting.
+    pub grid_fit: bool,
+    /// The flag of grayscale rendering.
+    pub grayscale: bool,
+    /// The flag of grid-fitting with symmetric smoothing.
+    pub symmetric_grid_fit: bool,
+    /// The flag of smoothing along multiple axes with ClearType.
+    pub symmetric_smoothing: bool,
+}
+
+/// Advance widths in pixels precomputed for sizes given by the horizontal
+/// device-metrics table (`hdmx`).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DeviceWidths {
+    /// The records in increasing order of sizes.
+    pub records: Vec<DeviceRecord>,
+}
+
+/// Advance widths in pixels for a size.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DeviceRecord {
+    /// The size in pixels per em.
+    pub ppem: u8,
+    /// The maximal advance width.
+    pub maximal_width: u8,
+    /// The advance widths indexed by glyph identifiers.
+    pub widths: Vec<u8>,
+}
+
+/// Sizes in pixels per em from which advance widths scale linearly given by
+/// the linear-threshold table (`LTSH`).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LinearThresholds {
+    /// The sizes indexed by glyph identifiers.
+    pub thresholds: Vec<u8>,
+}
+
+/// Vertical extents in pixels for sizes and aspect ratios given by the
+/// vertical device-metrics table (`VDMX`).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VerticalDeviceMetrics {
+    /// The ratios in the order of preference.
+    pub ratios: Vec<Ratio>,
+}
+
+/// Vertical extents for a range of aspect ratios.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Ratio {
+    /// The character set.
+    pub character_set: u8,
+    /// The horizontal part of the ratio, which is zero for any ratio.
+    pub x: u8,
+    /// The lower and upper vertical parts of the ratio.
+    pub y: (u8, u8),
+    /// The records in increasing order of sizes.
+    pub records: Vec<VerticalRecord>,
+}
+
+/// Vertical extents for a size.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VerticalRecord {
+    /// The size in pixels per em.
+    pub ppem: u16,
+    /// The maximal vertical position.
+    pub maximum: i16,
+    /// The minimal vertical position.
+    pub minimum: i16,
+}
+
+impl GridFitting {
+    /// Return the behavior for a size in pixels per em.
+    pub fn get(&self, ppem: u16) -> Option<Behavior> {
+        self.ranges
+            .iter()
+            .find(|(limit, _)| ppem <= *limit)
+            .map(|(_, behavior)| *behavior)
+    }
+
+    fn parse(data: &[u8], _: usize) -> Option<Self> {
+        let version = get_u16(data, 0)?;
+        let mut ranges = vec![];
+        for i in 0..get_u16(data, 2)? as usize {
+            let flags = get_u16(data, 4 + 4 * i + 2)?;
+            let extended = version > 0;
+            let behavior = Behavior {
+                grid_fit: flags & 0x0001 != 0,
+                grayscale: flags & 0x0002 != 0,
+                symmetric_grid_fit: extended && flags & 0x0004 != 0,
+                symmetric_smoothing: extended && flags & 0x0008 != 0,
+            };
+            ranges.push((get_u16(data, 4 + 4 * i)?, behavior));
+        }
+        Some(Self { ranges })
+    }
+}
+
+impl DeviceWidths {
+    /// Return the advance width of a glyph at a size in pixels per em.
+    pub fn get(&self, glyph_id: GlyphID, ppem: u16) -> Option<u8> {
+        self.records
+            .iter()
+            .find(|record| record.ppem as u16 == ppem)
+            .and_then(|record| record.widths.get(glyph_id as usize))
+            .copied()
+    }
+
+    fn parse(data: &[u8], glyph_count: usize) -> Option<Self> {
+        let count = get_u16(data, 2)? as usize;
+        let size = u32::from_be_bytes(data.get(4..8)?.try_into().ok()?) as usize;
+        if size < 2 + glyph_count {
+            return None;
+        }
+        let mut records = vec![];
+        for i in 0..count {
+            let record = data.get((8 + i * size)..(8 + i * size + 2 + glyph_count))?;
+            records.push(DeviceRecord {
+                ppem: record[0],
+                maximal_width: record[1],
+                widths: record[2..].to_vec(),
+            });
+        }
+        Some(Self { records })
+    }
+}
+
+impl LinearThresholds {
+    /// Return the size in pixels per em from which the advance width of a
+    /// glyph scales linearly.
+    pub fn get(&self, glyph_id: GlyphID) -> Option<u8> {
+        self.thresholds.get(glyph_id as usize).copied()
+    }
+
+    fn parse(data: &[u8], _: usize) -> Option<Self> {
+        let count = get_u16(data, 2)? as usize;
+        Some(Self {
+            thresholds: data.get(4..(4 + count))?.to_vec(),
+        })
+    }
+}
+
+impl VerticalDeviceMetrics {
+    /// Return the maximal and minimal vertical positions at a size in pixels
+    /// per em on a device with an aspect ratio given by its horizontal and
+    /// vertical resolutions.
+    pub fn get(&self, ppem: u16, aspect: (u16, u16)) -> Option<(i16, i16)> {
+        let (x, y) = (aspect.0 as u32, aspect.1 as u32);
+        self.ratios
+            .iter()
+            .find(|ratio| {
+                ratio.x == 0
+                    || (ratio.y.0 as u32 * x <= ratio.x as u32 * y
+                        && ratio.x as u32 * y <= ratio.y.1 as u32 * x)
+            })
+            .and_then(|ratio| ratio.records.iter().find(|record| record.ppem == ppem))
+            .map(|record| (record.maximum, record.minimum))
+    }
+
+    fn parse(data: &[u8], _: usize) -> Option<Self> {
+        let count = get_u16(data, 4)? as usize;
+        let mut ratios = vec![];
+        for i in 0..count {
+            let ratio = data.get((6 + 4 * i)..(6 + 4 * i + 4))?;
+            let mut position = get_u16(data, 6 + 4 * count + 2 * i)? as usize;
+            let mut records = vec![];
+            for _ in 0..get_u16(data, position)? {
+                position += if records.is_empty() { 4 } else { 6 };
+                records.push(VerticalRecord {
+                    ppem: get_u16(data, position)?,
+                    maximum: get_u16(data, position + 2)? as i16,
+                    minimum: get_u16(data, position + 4)? as i16,
+                });
+            }
+            ratios.push(Ratio {
+                character_set: ratio[0],
+                x: ratio[1],
+                y: (ratio[2], ratio[3]),
+                records,
+            });
+        }
+        Some(Self { ratios })
+    }
+}
+
+pub(crate) fn read_grid_fitting<T>(cache: &mut Cache<T>) -> Result<Option<GridFitting>>
+where
+    T: crate::Read,
+{
+    read(cache, b"gasp", GridFitting::parse)
+}
+
+pub(crate) fn read_device_widths<T>(cache: &mut Cache<T>) -> Result<Option<DeviceWidths>>
+where
+    T: crate::Read,
+{
+    read(cache, b"hdmx", DeviceWidths::parse)
+}
+
+pub(crate) fn read_linear_thresholds<T>(cache: &mut Cache<T>) -> Result<Option<LinearThresholds>>
+where
+    T: crate::Read,
+{
+    read(cache, b"LTSH", LinearThresholds::parse)
+}
+
+pub(crate) fn read_vertical_device_metrics<T>(
+    cache: &mut Cache<T>,
+) -> Result<Option<VerticalDeviceMetrics>>
+where
+    T: crate::Read,
+{
+    read(cache, b"VDMX", VerticalDeviceMetrics::parse)
+}
+
+fn read<T, U>(
+    cache: &mut Cache<T>,
+    tag: &[u8; 4],
+    parse: fn(&[u8], usize) -> Option<U>,
+) -> Result<Option<U>>
+where
+    T: crate::Read,
+{
+    let records = cache.backend.offsets.records.clone();
+    let data = tables::take(cache.tape.borrow_mut().deref_mut(), &records, tag)?;
+    let data = match data {
+        Some(data) => data,
+        _ => return Ok(None),
+    };
+    let glyph_count = cache.maximum_profile()?.borrow().glyph_count();
+    match parse(&data, glyph_count) {
+        Some(value) => Ok(Some(value)),
+        _ => tables::malformed(tag),
+    }
+}
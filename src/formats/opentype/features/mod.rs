@@ -1,6 +1,6 @@
 //! Layout features.
 
-mod rules;
+pub(crate) mod rules;
 mod sample;
 mod transform;
 
@@ -3,25 +3,121 @@ use std::io::Result;
 use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
 
+use opentype::truetype::tables::offsets::{Offsets, Record};
 use opentype::truetype::tables::FontHeader;
-use opentype::truetype::Tag;
+use opentype::truetype::{GlyphID, Tag};
 
 use crate::formats::opentype::cache::{Cache, Reference};
 use crate::formats::opentype::{
     axes, characters, features, metrics, names, palettes, tables, timestamps,
 };
+use crate::Number;
 
 /// A font.
 pub struct Font<T> {
-    cache: Reference<Cache<T>>,
-    index: (bool, usize),
+    pub(super) cache: Reference<Cache<T>>,
+    pub(super) index: (bool, usize),
 }
 
 /// A disposition.
-#[derive(Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Disposition {
+    /// Copy the table as is.
     Retain,
+    /// Write the table from the cache.
     Update,
+    /// Leave the table out.
+    Drop,
+    /// Copy the table as is under another tag.
+    Rename(Tag),
+}
+
+/// A table to insert.
+#[derive(Clone, Debug)]
+pub struct Insertion {
+    /// The tag.
+    pub tag: Tag,
+    /// The data.
+    pub data: Vec<u8>,
+}
+
+impl Insertion {
+    /// Create an instance from raw data.
+    #[inline]
+    pub fn raw(tag: Tag, data: Vec<u8>) -> Self {
+        Self { tag, data }
+    }
+
+    /// Create an instance from a typed value.
+    pub fn typed<T>(tag: Tag, value: &T) -> Result<Self>
+    where
+        T: opentype::value::Write,
+    {
+        let mut data = vec![];
+        crate::Write::give(&mut data, value)?;
+        Ok(Self { tag, data })
+    }
+}
+
+/// A drawn glyph.
+#[derive(Default)]
+pub(super) struct Drawing {
+    pub horizontal_metrics: (Number, Number),
+    pub bounding_box: Option<(Number, Number, Number, Number)>,
+    pub components: Vec<crate::glyph::Component>,
+    pub hints: Option<crate::glyph::Hints>,
+}
+
+impl<T: crate::Read> Font<T> {
+    fn trace(
+        &mut self,
+        character: char,
+        pen: &mut dyn crate::glyph::Pen,
+    ) -> Result<Option<Drawing>> {
+        let glyph_id = self.cache.borrow_mut().forward_mapping()?.get(character);
+        match glyph_id {
+            Some(glyph_id) => self.trace_id(glyph_id, true, pen).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    fn trace_id(
+        &mut self,
+        glyph_id: GlyphID,
+        flatten: bool,
+        pen: &mut dyn crate::glyph::Pen,
+    ) -> Result<Drawing> {
+        let mut cache = self.cache.borrow_mut();
+        let metrics = cache.metrics()?.clone();
+        match self.index {
+            (true, _) => super::truetype::draw(
+                &cache.glyph_data()?.borrow(),
+                &metrics,
+                glyph_id,
+                flatten,
+                pen,
+            ),
+            (false, id) => super::postscript::draw(
+                &cache.font_set()?.borrow(),
+                &metrics,
+                id,
+                glyph_id,
+                flatten,
+                pen,
+            ),
+        }
+    }
+}
+
+fn build(drawing: Drawing, mut builder: crate::glyph::Builder) -> crate::Glyph {
+    builder.set_horizontal_metrics(drawing.horizontal_metrics);
+    if let Some(value) = drawing.bounding_box {
+        builder.set_bounding_box(value);
+    }
+    let mut glyph = crate::Glyph::from(builder);
+    glyph.components = drawing.components;
+    glyph.hints = drawing.hints;
+    glyph
 }
 
 impl<T: crate::Read> crate::font::Case for Font<T> {
@@ -45,6 +141,13 @@ impl<T: crate::Read> crate::font::Case for Font<T> {
         metrics::read(&mut self.cache.borrow_mut())
     }
 
+    #[inline]
+    fn bounding_box(
+        &mut self,
+    ) -> Result<(crate::Number, crate::Number, crate::Number, crate::Number)> {
+        metrics::bounding_box(&mut self.cache.borrow_mut())
+    }
+
     #[inline]
     fn names(&mut self) -> Result<crate::Names> {
         names::read(&mut self.cache.borrow_mut())
@@ -65,22 +168,144 @@ impl<T: crate::Read> crate::font::Case for Font<T> {
         timestamps::read(&mut self.cache.borrow_mut())
     }
 
+    #[inline]
+    fn grid_fitting(&mut self) -> Result<Option<Rc<crate::GridFitting>>> {
+        Ok(self.cache.borrow_mut().grid_fitting()?.cloned())
+    }
+
+    #[inline]
+    fn device_widths(&mut self) -> Result<Option<Rc<crate::DeviceWidths>>> {
+        Ok(self.cache.borrow_mut().device_widths()?.cloned())
+    }
+
+    #[inline]
+    fn linear_thresholds(&mut self) -> Result<Option<Rc<crate::LinearThresholds>>> {
+        Ok(self.cache.borrow_mut().linear_thresholds()?.cloned())
+    }
+
+    #[inline]
+    fn vertical_device_metrics(&mut self) -> Result<Option<Rc<crate::VerticalDeviceMetrics>>> {
+        Ok(self.cache.borrow_mut().vertical_device_metrics()?.cloned())
+    }
+
     fn glyph(&mut self, character: char) -> Result<Option<crate::Glyph>> {
+        let mut builder = crate::glyph::Builder::default();
+        Ok(self
+            .trace(character, &mut builder)?
+            .map(|drawing| build(drawing, builder)))
+    }
+
+    #[inline]
+    fn glyph_id(&mut self, character: char) -> Result<Option<GlyphID>> {
+        Ok(self.cache.borrow_mut().forward_mapping()?.get(character))
+    }
+
+    fn glyph_by_id(&mut self, glyph_id: GlyphID, flatten: bool) -> Result<crate::Glyph> {
+        let mut builder = crate::glyph::Builder::default();
+        let drawing = self.trace_id(glyph_id, flatten, &mut builder)?;
+        Ok(build(drawing, builder))
+    }
+
+    fn alignment(&mut self) -> Result<Option<crate::glyph::Alignment>> {
+        match self.index {
+            (true, _) => Ok(None),
+            (false, id) => {
+                let mut cache = self.cache.borrow_mut();
+                let font_set = cache.font_set()?.clone();
+                let font_set = font_set.borrow();
+                super::postscript::alignment(&font_set, id).map(Some)
+            }
+        }
+    }
+
+    #[inline]
+    fn draw(&mut self, character: char, pen: &mut dyn crate::glyph::Pen) -> Result<bool> {
+        Ok(self.trace(character, pen)?.is_some())
+    }
+
+    fn kerning(&mut self, one: char, other: char) -> Result<Number> {
         let mut cache = self.cache.borrow_mut();
         let mapping = cache.forward_mapping()?.clone();
-        let metrics = cache.metrics()?.clone();
-        match self.index {
-            (true, _) => {
-                super::truetype::draw(&cache.glyph_data()?.borrow(), &mapping, &metrics, character)
+        match (mapping.get(one), mapping.get(other)) {
+            (Some(one), Some(other)) => Ok(cache.kerning()?.get(one, other).into()),
+            _ => Ok(0.0),
+        }
+    }
+
+    fn kerning_pairs(&mut self, characters: &[char]) -> Result<Vec<(char, char, Number)>> {
+        use std::collections::HashMap;
+
+        let mut cache = self.cache.borrow_mut();
+        let mapping = cache.forward_mapping()?.clone();
+        let mut positions = HashMap::new();
+        let mut glyph_ids = vec![];
+        let mut groups = HashMap::<GlyphID, Vec<char>>::new();
+        for &character in characters.iter() {
+            if positions.contains_key(&character) {
+                continue;
+            }
+            positions.insert(character, positions.len());
+            if let Some(glyph_id) = mapping.get(character) {
+                let group = groups.entry(glyph_id).or_default();
+                if group.is_empty() {
+                    glyph_ids.push(glyph_id);
+                }
+                group.push(character);
             }
-            (false, id) => super::postscript::draw(
-                &cache.font_set()?.borrow(),
-                &mapping,
-                &metrics,
-                id,
-                character,
-            ),
         }
+        let mut values = vec![];
+        for ((one, other), value) in cache.kerning()?.pairs(&glyph_ids) {
+            for &one in groups[&one].iter() {
+                for &other in groups[&other].iter() {
+                    values.push((one, other, value.into()));
+                }
+            }
+        }
+        values.sort_by_key(|&(one, other, _)| (positions[&one], positions[&other]));
+        Ok(values)
+    }
+
+    fn hinted_glyph(&mut self, character: char, ppem: u16) -> Result<Option<crate::Glyph>> {
+        if !self.index.0 {
+            raise!("hinting PostScript outlines is not supported");
+        }
+        let mut cache = self.cache.borrow_mut();
+        let glyph_id = match cache.forward_mapping()?.get(character) {
+            Some(glyph_id) => glyph_id,
+            _ => return Ok(None),
+        };
+        let metrics = cache.metrics()?.clone();
+        let glyph_data = cache.glyph_data()?.clone();
+        let hinting = cache.hinting()?.clone();
+        let mut builder = crate::glyph::Builder::default();
+        let drawing = hinting.borrow_mut().draw(
+            &glyph_data.borrow(),
+            &metrics,
+            glyph_id,
+            ppem,
+            &mut builder,
+        )?;
+        let mut glyph = build(drawing, builder);
+        if !glyph.contours.is_empty() {
+            let (left, bottom, right, top) = glyph.bounds();
+            glyph.bounding_box = (left, bottom, right, top);
+            glyph.side_bearings = (left, glyph.advance_width - right);
+        }
+        Ok(Some(glyph))
+    }
+
+    fn bitmap(&mut self, character: char, ppem: u16) -> Result<Option<crate::EmbeddedBitmap>> {
+        let mut cache = self.cache.borrow_mut();
+        let glyph_id = match cache.forward_mapping()?.get(character) {
+            Some(glyph_id) => glyph_id,
+            _ => return Ok(None),
+        };
+        let bitmaps = match cache.bitmaps()? {
+            Some(bitmaps) => bitmaps.clone(),
+            _ => return Ok(None),
+        };
+        let mut tape = cache.tape.borrow_mut();
+        bitmaps.get(tape.deref_mut(), glyph_id, ppem)
     }
 }
 
@@ -116,17 +341,91 @@ pub fn read<T: crate::Read>(tape: Reference<T>, backend: opentype::Font) -> Resu
 }
 
 /// Write a font.
+#[inline]
 pub fn write<T, U, F>(font: Font<T>, tape: &mut U, dispose: F) -> Result<()>
 where
     T: crate::Read + 'static,
     U: crate::Read + crate::Write,
     F: Fn(&Tag) -> Disposition,
 {
+    write_with(font, tape, dispose, Vec::new())
+}
+
+/// Write a font inserting additional tables.
+///
+/// An inserted table replaces any existing table with the same tag. The table
+/// directory is sorted by tag.
+pub fn write_with<T, U, F, I>(font: Font<T>, tape: &mut U, dispose: F, insertions: I) -> Result<()>
+where
+    T: crate::Read + 'static,
+    U: crate::Read + crate::Write,
+    F: Fn(&Tag) -> Disposition,
+    I: IntoIterator<Item = Insertion>,
+{
+    enum Source {
+        Copy(u64, u64),
+        Update(Tag),
+        Insert(Vec<u8>),
+    }
+
     let mut cache = font.cache.borrow_mut();
 
-    let offsets_position = tape.position()?;
-    let mut offsets = cache.backend.offsets.clone();
+    let mut entries = Vec::with_capacity(cache.backend.offsets.records.len());
+    for record in cache.backend.offsets.records.iter() {
+        let disposition = if record.tag == b"head" {
+            Disposition::Update
+        } else {
+            dispose(&record.tag)
+        };
+        match disposition {
+            Disposition::Retain => entries.push((
+                *record,
+                Source::Copy(record.offset as u64, record.size as u64),
+            )),
+            Disposition::Update => entries.push((*record, Source::Update(record.tag))),
+            Disposition::Drop => {}
+            Disposition::Rename(tag) => {
+                if tag == b"head" {
+                    raise!("renaming {:?} into {:?} is not supported", record.tag, tag);
+                }
+                let mut record = *record;
+                record.tag = tag;
+                entries.push((
+                    record,
+                    Source::Copy(record.offset as u64, record.size as u64),
+                ));
+            }
+        }
+    }
+    for Insertion { tag, data } in insertions {
+        if tag == b"head" {
+            raise!("inserting {:?} is not supported", tag);
+        }
+        entries.retain(|(record, _)| record.tag != tag);
+        let record = Record {
+            tag,
+            ..Default::default()
+        };
+        entries.push((record, Source::Insert(data)));
+    }
+    entries.sort_by_key(|(record, _)| record.tag);
+    for pair in entries.windows(2) {
+        if pair[0].0.tag == pair[1].0.tag {
+            raise!("found duplicate tables with {:?}", pair[0].0.tag);
+        }
+    }
+
+    let mut offsets = Offsets {
+        header: cache.backend.offsets.header,
+        records: entries.iter().map(|(record, _)| *record).collect(),
+    };
+    let (search_range, entry_selector, range_shift) = search(offsets.records.len());
+    offsets.header.table_count = offsets.records.len() as _;
+    offsets.header.search_range = search_range;
+    offsets.header.entry_selector = entry_selector;
+    offsets.header.range_shift = range_shift;
 
+    let offsets_position = tape.position()?;
     let mut font_header_position = None;
     let mut font_header = *cache.font_header()?.borrow();
     font_header.checksum_adjustment = 0;
@@ -136,32 +435,30 @@ where
     pad(tape, size as usize)?;
 
     let mut other = cache.tape.borrow_mut();
-    for record in offsets.records.iter_mut() {
+    for ((_, source), record) in entries.iter().zip(offsets.records.iter_mut()) {
         let position = tape.position()?;
-        let disposition = if record.tag == b"head" {
-            font_header_position = Some(position);
-            Disposition::Update
-        } else {
-            dispose(&record.tag)
-        };
-        match disposition {
-            Disposition::Retain => {
-                other.jump(record.offset as u64)?;
-                copy(other.deref_mut(), tape, record.size as u64)?;
+        match source {
+            &Source::Copy(offset, size) => {
+                other.jump(offset)?;
+                copy(other.deref_mut(), tape, size)?;
             }
-            Disposition::Update => match &*record.tag {
-                b"head" => tape.give(&font_header)?,
+            Source::Update(tag) => match &**tag {
+                b"head" => {
+                    font_header_position = Some(position);
+                    tape.give(&font_header)?;
+                }
                 b"name" => match cache.names.as_ref() {
                     Some(table) => tape.give(table.borrow().deref())?,
-                    _ => raise!("found no update for {:?}", record.tag),
+                    _ => raise!("found no update for {:?}", tag),
                 },
-                _ => raise!("updating {:?} is not supported yet", record.tag),
+                _ => raise!("updating {:?} is not supported yet", tag),
             },
+            Source::Insert(data) => tape.give_bytes(data)?,
         }
         record.offset = position as _;
         record.size = (tape.position()? - position) as _;
         pad(tape, record.size as usize)?;
-        if disposition == Disposition::Update {
+        if !matches!(source, Source::Copy(..)) {
             record.checksum = record.checksum(tape)?;
         }
     }
@@ -175,8 +472,7 @@ where
     };
 
     tape.jump(offsets_position)?;
-    let checksum = FontHeader::checksum(tape)?;
-    font_header.checksum_adjustment = FontHeader::CHECKSUM_ADJUSTMENT.wrapping_sub(checksum);
+    font_header.checksum_adjustment = FontHeader::checksum(tape)?;
     tape.jump(font_header_position)?;
     tape.give(&font_header)?;
 
@@ -193,7 +489,22 @@ where
     Ok(())
 }
 
-fn pad<T: crate::Write>(tape: &mut T, size: usize) -> Result<()> {
+pub(super) fn search(count: usize) -> (u16, u16, u16) {
+    let mut power = 1;
+    let mut selector = 0;
+    while 2 * power <= count {
+        power *= 2;
+        selector += 1;
+    }
+    let range = 16 * power;
+    (
+        range as u16,
+        selector,
+        (16 * count).saturating_sub(range) as u16,
+    )
+}
+
+pub(super) fn pad<T: crate::Write>(tape: &mut T, size: usize) -> Result<()> {
     match size % 4 {
         1 => tape.give_bytes(&[0, 0, 0])?,
         2 => tape.give_bytes(&[0, 0])?,
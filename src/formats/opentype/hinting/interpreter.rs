@@ -0,0 +1,1764 @@
+//! The interpreter of TrueType instructions.
+//!
+//! The interpreter follows the one of FreeType in its classic mode, which is
+//! the one of the Windows rasterizer before ClearType. Coordinates are in 26.6
+//! fixed-point pixels, and vectors are in 2.14 fixed-point units.
+
+use std::collections::HashMap;
+use std::io::Result;
+
+/// The flag of points on the curve.
+pub const ON_CURVE: u8 = 0x01;
+
+const TOUCHED_X: u8 = 0x08;
+const TOUCHED_Y: u8 = 0x10;
+
+const CALL_LIMIT: usize = 32;
+const STEP_LIMIT: usize = 1_000_000;
+const UNIT: i32 = 0x4000;
+
+/// A point.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// A zone of points.
+#[derive(Clone, Debug, Default)]
+pub struct Zone {
+    /// The points in font units, which are the ones of the components for
+    /// composite glyphs.
+    pub original: Vec<Point>,
+    /// The scaled points.
+    pub scaled: Vec<Point>,
+    /// The current points.
+    pub current: Vec<Point>,
+    /// The flags.
+    pub flags: Vec<u8>,
+    /// The indices of the last points of the contours.
+    pub end_points: Vec<usize>,
+}
+
+/// The limits given in the maximum profile.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Limits {
+    pub functions: usize,
+    pub instructions: usize,
+    pub stack: usize,
+    pub storage: usize,
+    pub twilight: usize,
+}
+
+/// An interpreter set up for a size.
+pub struct Interpreter {
+    programs: [Vec<u8>; 3],
+    control_values: Vec<i16>,
+    functions: HashMap<u32, Definition>,
+    instructions: HashMap<u8, Definition>,
+    limits: Limits,
+
+    cvt: Vec<i32>,
+    storage: Vec<i32>,
+    twilight: Zone,
+    glyph: Zone,
+    default: State,
+    state: State,
+    stack: Vec<i32>,
+    calls: Vec<Call>,
+
+    ppem: i32,
+    scale: i32,
+    unit: i32,
+    range: usize,
+    ip: usize,
+    free_dot_projection: i32,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct State {
+    projection: (i32, i32),
+    freedom: (i32, i32),
+    dual: (i32, i32),
+    references: [usize; 3],
+    zones: [u8; 3],
+    round: Round,
+    repetition: i32,
+    minimum_distance: i32,
+    control_value_cutin: i32,
+    single_width_cutin: i32,
+    single_width_value: i32,
+    delta_base: i32,
+    delta_shift: i32,
+    auto_flip: bool,
+    instruct_control: u8,
+    scan_control: bool,
+    scan_type: i32,
+    period: i32,
+    phase: i32,
+    threshold: i32,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Round {
+    HalfGrid,
+    Grid,
+    DoubleGrid,
+    DownToGrid,
+    UpToGrid,
+    Off,
+    Super,
+    Super45,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Definition {
+    range: usize,
+    start: usize,
+    end: usize,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Call {
+    range: usize,
+    ip: usize,
+    count: i32,
+    definition: Definition,
+}
+
+const FONT: usize = 0;
+const CONTROL: usize = 1;
+const GLYPH: usize = 2;
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            projection: (UNIT, 0),
+            freedom: (UNIT, 0),
+            dual: (UNIT, 0),
+            references: [0; 3],
+            zones: [1; 3],
+            round: Round::Grid,
+            repetition: 1,
+            minimum_distance: 64,
+            control_value_cutin: 68,
+            single_width_cutin: 0,
+            single_width_value: 0,
+            delta_base: 9,
+            delta_shift: 3,
+            auto_flip: true,
+            instruct_control: 0,
+            scan_control: false,
+            scan_type: 0,
+            period: 64,
+            phase: 0,
+            threshold: 0,
+        }
+    }
+}
+
+impl Point {
+    #[inline]
+    fn get(&self, x: bool) -> i32 {
+        if x {
+            self.x
+        } else {
+            self.y
+        }
+    }
+
+    #[inline]
+    fn set(&mut self, x: bool, value: i32) {
+        if x {
+            self.x = value;
+        } else {
+            self.y = value;
+        }
+    }
+}
+
+impl Zone {
+    fn new(count: usize) -> Self {
+        Self {
+            original: vec![Point::default(); count],
+            scaled: vec![Point::default(); count],
+            current: vec![Point::default(); count],
+            flags: vec![0; count],
+            end_points: vec![],
+        }
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.current.len()
+    }
+}
+
+macro_rules! skip(
+    ($condition:expr) => (
+        if $condition {
+            return Ok(());
+        }
+    )
+);
+
+impl Interpreter {
+    /// Create an instance and execute the font program.
+    pub fn new(
+        font_program: Vec<u8>,
+        control_program: Vec<u8>,
+        control_values: Vec<i16>,
+        limits: Limits,
+    ) -> Result<Self> {
+        let mut limits = limits;
+        limits.functions = limits.functions.max(64);
+        limits.stack += 32;
+        let mut interpreter = Self {
+            programs: [font_program, control_program, vec![]],
+            cvt: vec![0; control_values.len()],
+            control_values,
+            functions: HashMap::new(),
+            instructions: HashMap::new(),
+            limits,
+
+            storage: vec![0; limits.storage],
+            twilight: Zone::new(limits.twilight + 4),
+            glyph: Zone::default(),
+            default: State::default(),
+            state: State::default(),
+            stack: Vec::with_capacity(limits.stack),
+            calls: Vec::with_capacity(CALL_LIMIT),
+
+            ppem: 0,
+            scale: 0,
+            unit: 0,
+            range: FONT,
+            ip: 0,
+            free_dot_projection: UNIT,
+        };
+        interpreter.execute(FONT)?;
+        Ok(interpreter)
+    }
+
+    /// Set up for a size by scaling the control values and executing the
+    /// control-value program.
+    ///
+    /// The scale is given in 16.16 fixed-point pixels per font unit times 64.
+    pub fn prepare(&mut self, ppem: i32, scale: i32) -> Result<()> {
+        self.ppem = ppem;
+        self.scale = scale;
+        self.unit = scale;
+        // The scaling loses the lower bits of the scale as in FreeType, which
+        // matters to programs comparing control values with rounded ones.
+        self.cvt = self
+            .control_values
+            .iter()
+            .map(|&value| mul_fix(value as i32 * 64, scale >> 6))
+            .collect();
+        self.storage.iter_mut().for_each(|value| *value = 0);
+        self.twilight = Zone::new(self.limits.twilight + 4);
+        self.state = State::default();
+        let result = self.execute(CONTROL);
+        // The control-value program cannot alter these variables.
+        self.state.projection = (UNIT, 0);
+        self.state.freedom = (UNIT, 0);
+        self.state.dual = (UNIT, 0);
+        self.state.references = [0; 3];
+        self.state.zones = [1; 3];
+        self.state.repetition = 1;
+        self.default = self.state;
+        result
+    }
+
+    /// Check if the control-value program has left the glyph instructions
+    /// enabled.
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.default.instruct_control & 1 == 0
+    }
+
+    /// Execute the instructions of a glyph.
+    ///
+    /// The points of components of a composite glyph are taken as they are
+    /// for original distances, which is what the glyph instructions of
+    /// composite glyphs expect.
+    pub fn hint(&mut self, zone: &mut Zone, instructions: &[u8], composite: bool) -> Result<()> {
+        self.glyph = std::mem::take(zone);
+        self.programs[GLYPH] = instructions.to_vec();
+        self.unit = if composite { 0x10000 } else { self.scale };
+        self.state = if self.default.instruct_control & 2 != 0 {
+            State::default()
+        } else {
+            self.default
+        };
+        self.state.projection = (UNIT, 0);
+        self.state.freedom = (UNIT, 0);
+        self.state.dual = (UNIT, 0);
+        self.state.zones = [1; 3];
+        self.state.round = Round::Grid;
+        self.state.repetition = 1;
+        let result = self.execute(GLYPH);
+        *zone = std::mem::take(&mut self.glyph);
+        result
+    }
+
+    fn execute(&mut self, range: usize) -> Result<()> {
+        self.range = range;
+        self.ip = 0;
+        self.stack.clear();
+        self.calls.clear();
+        self.compute();
+        let mut steps = 0;
+        loop {
+            if self.ip >= self.programs[self.range].len() {
+                if !self.calls.is_empty() {
+                    raise!("found a function without an end");
+                }
+                return Ok(());
+            }
+            let opcode = self.programs[self.range][self.ip];
+            let length = match length(&self.programs[self.range], self.ip) {
+                Some(value) => value,
+                _ => raise!("found truncated instructions"),
+            };
+            self.ip += length;
+            self.step(opcode, self.ip - length)?;
+            steps += 1;
+            if steps > STEP_LIMIT {
+                raise!("found instructions running for too long");
+            }
+        }
+    }
+
+    fn step(&mut self, opcode: u8, start: usize) -> Result<()> {
+        match opcode {
+            0x00..=0x05 => {
+                let value = if opcode & 1 == 1 {
+                    (UNIT, 0)
+                } else {
+                    (0, UNIT)
+                };
+                if opcode < 4 {
+                    self.state.projection = value;
+                    self.state.dual = value;
+                }
+                if opcode & 2 == 0 {
+                    self.state.freedom = value;
+                }
+                self.compute();
+            }
+            0x06..=0x09 => {
+                let [one, other] = self.pop::<2>();
+                let (one, other) = (one as u16 as usize, other as u16 as usize);
+                skip!(one >= self.zone(1).len() || other >= self.zone(2).len());
+                let a = self.zone(1).current[one];
+                let b = self.zone(2).current[other];
+                let value = line(a.x.wrapping_sub(b.x), a.y.wrapping_sub(b.y), opcode & 1);
+                if opcode < 0x08 {
+                    self.state.projection = value;
+                    self.state.dual = value;
+                } else {
+                    self.state.freedom = value;
+                }
+                self.compute();
+            }
+            0x0A | 0x0B => {
+                let [x, y] = self.pop::<2>();
+                let value = normalize(x as i16 as i32, y as i16 as i32);
+                if opcode == 0x0A {
+                    self.state.projection = value;
+                    self.state.dual = value;
+                } else {
+                    self.state.freedom = value;
+                }
+                self.compute();
+            }
+            0x0C => {
+                let (x, y) = self.state.projection;
+                self.push(x)?;
+                self.push(y)?;
+            }
+            0x0D => {
+                let (x, y) = self.state.freedom;
+                self.push(x)?;
+                self.push(y)?;
+            }
+            0x0E => {
+                self.state.freedom = self.state.projection;
+                self.compute();
+            }
+            0x0F => self.intersect()?,
+            0x10..=0x12 => {
+                let [value] = self.pop::<1>();
+                self.state.references[(opcode - 0x10) as usize] = value as u16 as usize;
+            }
+            0x13..=0x16 => {
+                let [value] = self.pop::<1>();
+                skip!(!(0..=1).contains(&value));
+                match opcode {
+                    0x16 => self.state.zones = [value as u8; 3],
+                    _ => self.state.zones[(opcode - 0x13) as usize] = value as u8,
+                }
+            }
+            0x17 => {
+                let [value] = self.pop::<1>();
+                if value < 0 {
+                    raise!("found a negative loop count");
+                }
+                self.state.repetition = value.min(0xFFFF);
+            }
+            0x18 => self.state.round = Round::Grid,
+            0x19 => self.state.round = Round::HalfGrid,
+            0x1A => self.state.minimum_distance = self.pop::<1>()[0],
+            0x1B => self.skip(false)?,
+            0x1C => {
+                let [offset] = self.pop::<1>();
+                self.jump(start, offset)?;
+            }
+            0x1D => self.state.control_value_cutin = self.pop::<1>()[0],
+            0x1E => self.state.single_width_cutin = self.pop::<1>()[0],
+            0x1F => self.state.single_width_value = mul_fix(self.pop::<1>()[0], self.scale),
+            0x20 => {
+                let [value] = self.pop::<1>();
+                self.push(value)?;
+                self.push(value)?;
+            }
+            0x21 => {
+                self.pop::<1>();
+            }
+            0x22 => self.stack.clear(),
+            0x23 => {
+                let [one, other] = self.pop::<2>();
+                self.push(other)?;
+                self.push(one)?;
+            }
+            0x24 => self.push(self.stack.len() as i32)?,
+            0x25 | 0x26 => {
+                let [index] = self.pop::<1>();
+                let count = self.stack.len();
+                if index <= 0 || index as usize > count {
+                    if opcode == 0x25 {
+                        self.push(0)?;
+                    }
+                    return Ok(());
+                }
+                let position = count - index as usize;
+                if opcode == 0x25 {
+                    self.push(self.stack[position])?;
+                } else {
+                    let value = self.stack.remove(position);
+                    self.stack.push(value);
+                }
+            }
+            0x27 => {
+                let [one, other] = self.pop::<2>();
+                let (one, other) = (one as u16 as usize, other as u16 as usize);
+                skip!(one >= self.zone(1).len() || other >= self.zone(0).len());
+                let a = self.zone(0).current[other];
+                let b = self.zone(1).current[one];
+                let distance = self.project(a, b) / 2;
+                self.shift(1, one, distance);
+                self.shift(0, other, distance.wrapping_neg());
+            }
+            0x29 => {
+                let [point] = self.pop::<1>();
+                let point = point as u16 as usize;
+                skip!(point >= self.zone(0).len());
+                let mut mask = 0xFF;
+                if self.state.freedom.0 != 0 {
+                    mask &= !TOUCHED_X;
+                }
+                if self.state.freedom.1 != 0 {
+                    mask &= !TOUCHED_Y;
+                }
+                self.zone_mut(0).flags[point] &= mask;
+            }
+            0x2A | 0x2B => {
+                let (function, count) = if opcode == 0x2B {
+                    (self.pop::<1>()[0], 1)
+                } else {
+                    let [count, function] = self.pop::<2>();
+                    (function, count)
+                };
+                let definition = match self.functions.get(&(function as u32)) {
+                    Some(value) => *value,
+                    _ => raise!("found an undefined function ({})", function),
+                };
+                if count > 0 {
+                    self.call(definition, count)?;
+                }
+            }
+            0x2C => {
+                let [function] = self.pop::<1>();
+                let function = function as u32;
+                if self.range == GLYPH {
+                    raise!("found a function definition in glyph instructions");
+                }
+                if function > 0xFFFF
+                    || !self.functions.contains_key(&function)
+                        && self.functions.len() >= self.limits.functions
+                {
+                    raise!("found too many function definitions");
+                }
+                let definition = self.define()?;
+                self.functions.insert(function, definition);
+            }
+            0x2D => {
+                let mut call = match self.calls.pop() {
+                    Some(value) => value,
+                    _ => raise!("found an unexpected end of a function"),
+                };
+                call.count -= 1;
+                if call.count > 0 {
+                    self.ip = call.definition.start;
+                    self.calls.push(call);
+                } else {
+                    self.range = call.range;
+                    self.ip = call.ip;
+                }
+            }
+            0x2E | 0x2F => {
+                let [point] = self.pop::<1>();
+                let point = point as u16 as usize;
+                skip!(point >= self.zone(0).len());
+                let distance = if opcode & 1 == 1 {
+                    let value = self.project(self.zone(0).current[point], Point::default());
+                    self.round(value).wrapping_sub(value)
+                } else {
+                    0
+                };
+                self.shift(0, point, distance);
+                self.state.references[0] = point;
+                self.state.references[1] = point;
+            }
+            0x30 | 0x31 => self.interpolate_untouched(opcode & 1 == 1),
+            0x32 | 0x33 => {
+                if self.stack.len() < self.state.repetition as usize {
+                    self.state.repetition = 1;
+                    return Ok(());
+                }
+                let (dx, dy, _, _) = match self.displacement(opcode) {
+                    Some(value) => value,
+                    _ => return Ok(()),
+                };
+                for _ in 0..self.state.repetition {
+                    let point = self.stack.pop().unwrap_or(0) as u16 as usize;
+                    if point < self.zone(2).len() {
+                        self.displace(point, dx, dy, true);
+                    }
+                }
+                self.state.repetition = 1;
+            }
+            0x34 | 0x35 => {
+                let [contour] = self.pop::<1>();
+                let contour = contour as u16 as usize;
+                let twilight = self.state.zones[2] == 0;
+                let count = if twilight {
+                    1
+                } else {
+                    self.glyph.end_points.len()
+                };
+                skip!(contour >= count);
+                let (dx, dy, zone, reference) = match self.displacement(opcode) {
+                    Some(value) => value,
+                    _ => return Ok(()),
+                };
+                let (first, last) = if twilight {
+                    (0, self.twilight.len())
+                } else {
+                    let end_points = &self.glyph.end_points;
+                    let first = if contour == 0 {
+                        0
+                    } else {
+                        end_points[contour - 1] + 1
+                    };
+                    (first, end_points[contour] + 1)
+                };
+                for point in first..last {
+                    if zone != self.state.zones[2] || reference != point {
+                        self.displace(point, dx, dy, true);
+                    }
+                }
+            }
+            0x36 | 0x37 => {
+                let [value] = self.pop::<1>();
+                skip!(!(0..2).contains(&value));
+                let (dx, dy, zone, reference) = match self.displacement(opcode) {
+                    Some(value) => value,
+                    _ => return Ok(()),
+                };
+                // The phantom points are left intact.
+                let last = if self.state.zones[2] == 0 {
+                    self.twilight.len()
+                } else {
+                    self.glyph
+                        .end_points
+                        .last()
+                        .map(|&value| value + 1)
+                        .unwrap_or(0)
+                };
+                for point in 0..last {
+                    if zone != self.state.zones[2] || reference != point {
+                        self.displace(point, dx, dy, false);
+                    }
+                }
+            }
+            0x38 => {
+                let [amount] = self.pop::<1>();
+                if self.stack.len() < self.state.repetition as usize {
+                    self.state.repetition = 1;
+                    return Ok(());
+                }
+                let dx = mul_fix14(amount, self.state.freedom.0);
+                let dy = mul_fix14(amount, self.state.freedom.1);
+                for _ in 0..self.state.repetition {
+                    let point = self.stack.pop().unwrap_or(0) as u16 as usize;
+                    if point < self.zone(2).len() {
+                        self.displace(point, dx, dy, true);
+                    }
+                }
+                self.state.repetition = 1;
+            }
+            0x39 => self.interpolate(),
+            0x3A | 0x3B => {
+                let [point, distance] = self.pop::<2>();
+                let point = point as u16 as usize;
+                let reference = self.state.references[0];
+                skip!(point >= self.zone(1).len() || reference >= self.zone(0).len());
+                if self.state.zones[1] == 0 {
+                    let value = self.zone(0).scaled[reference];
+                    self.twilight.scaled[point] = value;
+                    self.shift_scaled(1, point, distance);
+                    self.twilight.current[point] = self.twilight.scaled[point];
+                }
+                let value =
+                    self.project(self.zone(1).current[point], self.zone(0).current[reference]);
+                self.shift(1, point, distance.wrapping_sub(value));
+                self.state.references[1] = reference;
+                self.state.references[2] = point;
+                if opcode & 1 == 1 {
+                    self.state.references[0] = point;
+                }
+            }
+            0x3C => {
+                let reference = self.state.references[0];
+                if self.stack.len() < self.state.repetition as usize
+                    || reference >= self.zone(0).len()
+                {
+                    self.state.repetition = 1;
+                    return Ok(());
+                }
+                for _ in 0..self.state.repetition {
+                    let point = self.stack.pop().unwrap_or(0) as u16 as usize;
+                    if point < self.zone(1).len() {
+                        let value = self
+                            .project(self.zone(1).current[point], self.zone(0).current[reference]);
+                        self.shift(1, point, value.wrapping_neg());
+                    }
+                }
+                self.state.repetition = 1;
+            }
+            0x3D => self.state.round = Round::DoubleGrid,
+            0x3E | 0x3F => {
+                let [point, entry] = self.pop::<2>();
+                let (point, entry) = (point as u16 as usize, entry as u32 as usize);
+                if point < self.zone(0).len() && entry < self.cvt.len() {
+                    let mut distance = self.cvt[entry];
+                    if self.state.zones[0] == 0 {
+                        let value = Point {
+                            x: mul_fix14(distance, self.state.freedom.0),
+                            y: mul_fix14(distance, self.state.freedom.1),
+                        };
+                        self.twilight.scaled[point] = value;
+                        self.twilight.current[point] = value;
+                    }
+                    let value = self.project(self.zone(0).current[point], Point::default());
+                    if opcode & 1 == 1 {
+                        if distance.wrapping_sub(value).wrapping_abs()
+                            > self.state.control_value_cutin
+                        {
+                            distance = value;
+                        }
+                        distance = self.round(distance);
+                    }
+                    self.shift(0, point, distance.wrapping_sub(value));
+                }
+                self.state.references[0] = point;
+                self.state.references[1] = point;
+            }
+            0x40 | 0x41 | 0xB0..=0xBF => {
+                let code = &self.programs[self.range];
+                let (count, word, first) = match opcode {
+                    0x40 | 0x41 => (code[start + 1] as usize, opcode == 0x41, start + 2),
+                    _ => ((opcode & 7) as usize + 1, opcode >= 0xB8, start + 1),
+                };
+                if self.stack.len() + count > self.limits.stack {
+                    raise!("found a stack overflow");
+                }
+                for i in 0..count {
+                    let value = if word {
+                        i16::from_be_bytes([code[first + 2 * i], code[first + 2 * i + 1]]) as i32
+                    } else {
+                        code[first + i] as i32
+                    };
+                    self.stack.push(value);
+                }
+            }
+            0x42 => {
+                let [index, value] = self.pop::<2>();
+                if let Some(entry) = self.storage.get_mut(index as u32 as usize) {
+                    *entry = value;
+                }
+            }
+            0x43 => {
+                let [index] = self.pop::<1>();
+                let value = self.storage.get(index as u32 as usize).copied();
+                self.push(value.unwrap_or(0))?;
+            }
+            0x44 | 0x70 => {
+                let [index, value] = self.pop::<2>();
+                let value = if opcode == 0x70 {
+                    mul_fix(value, self.scale)
+                } else {
+                    value
+                };
+                if let Some(entry) = self.cvt.get_mut(index as u32 as usize) {
+                    *entry = value;
+                }
+            }
+            0x45 => {
+                let [index] = self.pop::<1>();
+                let value = self.cvt.get(index as u32 as usize).copied();
+                self.push(value.unwrap_or(0))?;
+            }
+            0x46 | 0x47 => {
+                let [point] = self.pop::<1>();
+                let point = point as u32 as usize;
+                let value = if point >= self.zone(2).len() {
+                    0
+                } else if opcode & 1 == 1 {
+                    dual_project(&self.state, self.zone(2).scaled[point], Point::default())
+                } else {
+                    self.project(self.zone(2).current[point], Point::default())
+                };
+                self.push(value)?;
+            }
+            0x48 => {
+                let [point, value] = self.pop::<2>();
+                let point = point as u16 as usize;
+                skip!(point >= self.zone(2).len());
+                let current = self.project(self.zone(2).current[point], Point::default());
+                self.shift(2, point, value.wrapping_sub(current));
+                if self.state.zones[2] == 0 {
+                    self.twilight.scaled[point] = self.twilight.current[point];
+                }
+            }
+            0x49 | 0x4A => {
+                let [one, other] = self.pop::<2>();
+                let (one, other) = (one as u16 as usize, other as u16 as usize);
+                let value = if one >= self.zone(0).len() || other >= self.zone(1).len() {
+                    0
+                } else if opcode & 1 == 1 {
+                    self.project(self.zone(0).current[one], self.zone(1).current[other])
+                } else {
+                    self.measure(0, one, 1, other)
+                };
+                self.push(value)?;
+            }
+            0x4B | 0x4C => self.push(self.ppem)?,
+            0x4D => self.state.auto_flip = true,
+            0x4E => self.state.auto_flip = false,
+            0x4F => raise!("found a debugging instruction"),
+            0x50..=0x55 => {
+                let [one, other] = self.pop::<2>();
+                let value = match opcode {
+                    0x50 => one < other,
+                    0x51 => one <= other,
+                    0x52 => one > other,
+                    0x53 => one >= other,
+                    0x54 => one == other,
+                    _ => one != other,
+                };
+                self.push(value as i32)?;
+            }
+            0x56 | 0x57 => {
+                let [value] = self.pop::<1>();
+                let value = self.round(value) & 127;
+                self.push((value == if opcode == 0x56 { 64 } else { 0 }) as i32)?;
+            }
+            0x58 => {
+                let [value] = self.pop::<1>();
+                if value == 0 {
+                    self.skip(true)?;
+                }
+            }
+            0x59 => {}
+            0x5A | 0x5B => {
+                let [one, other] = self.pop::<2>();
+                let value = match opcode {
+                    0x5A => one != 0 && other != 0,
+                    _ => one != 0 || other != 0,
+                };
+                self.push(value as i32)?;
+            }
+            0x5C => {
+                let [value] = self.pop::<1>();
+                self.push((value == 0) as i32)?;
+            }
+            0x5D | 0x71 | 0x72 => self.delta_point(opcode),
+            0x73..=0x75 => self.delta_cvt(opcode),
+            0x5E => self.state.delta_base = self.pop::<1>()[0] as u16 as i32,
+            0x5F => {
+                let [value] = self.pop::<1>();
+                if !(0..=6).contains(&value) {
+                    raise!("found an invalid delta shift");
+                }
+                self.state.delta_shift = value;
+            }
+            0x60..=0x63 | 0x8B | 0x8C => {
+                let [one, other] = self.pop::<2>();
+                let value = match opcode {
+                    0x60 => one.wrapping_add(other),
+                    0x61 => one.wrapping_sub(other),
+                    0x62 => {
+                        if other == 0 {
+                            raise!("found a division by zero");
+                        }
+                        mul_div_no_round(one, 64, other)
+                    }
+                    0x63 => mul_div(one, other, 64),
+                    0x8B => one.max(other),
+                    _ => one.min(other),
+                };
+                self.push(value)?;
+            }
+            0x64..=0x67 => {
+                let [value] = self.pop::<1>();
+                let value = match opcode {
+                    0x64 => value.wrapping_abs(),
+                    0x65 => value.wrapping_neg(),
+                    0x66 => value & !63,
+                    _ => value.wrapping_add(63) & !63,
+                };
+                self.push(value)?;
+            }
+            0x68..=0x6B => {
+                let [value] = self.pop::<1>();
+                let value = self.round(value);
+                self.push(value)?;
+            }
+            0x6C..=0x6F => {}
+            0x76 | 0x77 => {
+                let [value] = self.pop::<1>();
+                let period = if opcode == 0x76 { 0x4000 } else { 0x2D41 };
+                self.round_super(period, value);
+                self.state.round = if opcode == 0x76 {
+                    Round::Super
+                } else {
+                    Round::Super45
+                };
+            }
+            0x78 | 0x79 => {
+                let [offset, value] = self.pop::<2>();
+                if (value != 0) == (opcode == 0x78) {
+                    self.jump(start, offset)?;
+                }
+            }
+            0x7A => self.state.round = Round::Off,
+            0x7C => self.state.round = Round::UpToGrid,
+            0x7D => self.state.round = Round::DownToGrid,
+            0x7E => {
+                self.pop::<1>();
+            }
+            0x7F => {
+                self.pop::<1>();
+            }
+            0x80 => {
+                if self.stack.len() < self.state.repetition as usize {
+                    self.state.repetition = 1;
+                    return Ok(());
+                }
+                for _ in 0..self.state.repetition {
+                    let point = self.stack.pop().unwrap_or(0) as u16 as usize;
+                    if let Some(flags) = self.glyph.flags.get_mut(point) {
+                        *flags ^= ON_CURVE;
+                    }
+                }
+                self.state.repetition = 1;
+            }
+            0x81 | 0x82 => {
+                let [first, last] = self.pop::<2>();
+                let (first, last) = (first as u16 as usize, last as u16 as usize);
+                skip!(first > last || last >= self.glyph.len());
+                for flags in self.glyph.flags[first..=last].iter_mut() {
+                    if opcode == 0x81 {
+                        *flags |= ON_CURVE;
+                    } else {
+                        *flags &= !ON_CURVE;
+                    }
+                }
+            }
+            0x85 => {
+                let [value] = self.pop::<1>();
+                let threshold = value & 0xFF;
+                if threshold == 0xFF {
+                    self.state.scan_control = true;
+                } else if threshold == 0 {
+                    self.state.scan_control = false;
+                } else {
+                    if value & 0x100 != 0 && self.ppem <= threshold {
+                        self.state.scan_control = true;
+                    }
+                    if value & 0x800 != 0 && self.ppem > threshold {
+                        self.state.scan_control = false;
+                    }
+                }
+            }
+            0x86 | 0x87 => {
+                let [one, other] = self.pop::<2>();
+                let (one, other) = (one as u16 as usize, other as u16 as usize);
+                skip!(one >= self.zone(1).len() || other >= self.zone(2).len());
+                let (a, b) = (self.zone(1).scaled[one], self.zone(2).scaled[other]);
+                self.state.dual = line(a.x.wrapping_sub(b.x), a.y.wrapping_sub(b.y), opcode & 1);
+                let (a, b) = (self.zone(1).current[one], self.zone(2).current[other]);
+                self.state.projection =
+                    line(a.x.wrapping_sub(b.x), a.y.wrapping_sub(b.y), opcode & 1);
+                self.compute();
+            }
+            0x88 => {
+                let [selector] = self.pop::<1>();
+                // The version of the classic interpreter with no rotation,
+                // stretching, variations, or grayscale.
+                self.push(if selector & 1 != 0 { 35 } else { 0 })?;
+            }
+            0x89 => {
+                let [instruction] = self.pop::<1>();
+                if self.range == GLYPH {
+                    raise!("found an instruction definition in glyph instructions");
+                }
+                if !(0..=0xFF).contains(&instruction)
+                    || !self.instructions.contains_key(&(instruction as u8))
+                        && self.instructions.len() >= self.limits.instructions
+                {
+                    raise!("found too many instruction definitions");
+                }
+                let definition = self.define()?;
+                self.instructions.insert(instruction as u8, definition);
+            }
+            0x8A => {
+                let [a, b, c] = self.pop::<3>();
+                self.push(b)?;
+                self.push(c)?;
+                self.push(a)?;
+            }
+            0x8D => self.state.scan_type = self.pop::<1>()[0],
+            0x8E => {
+                let [value, selector] = self.pop::<2>();
+                skip!(!(1..=3).contains(&selector));
+                let mask = 1 << (selector - 1);
+                self.state.instruct_control &= !mask;
+                if value != 0 {
+                    self.state.instruct_control |= mask;
+                }
+            }
+            0xC0..=0xDF => self.move_direct(opcode),
+            0xE0..=0xFF => self.move_indirect(opcode),
+            _ => match self.instructions.get(&opcode) {
+                Some(&definition) => self.call(definition, 1)?,
+                _ => raise!("found an unknown instruction ({:#04x})", opcode),
+            },
+        }
+        Ok(())
+    }
+
+    fn pop<const N: usize>(&mut self) -> [i32; N] {
+        let mut values = [0; N];
+        // Missing arguments are taken as zeros, and the stack is cleared.
+        if self.stack.len() < N {
+            self.stack.clear();
+            return values;
+        }
+        let count = self.stack.len();
+        values.copy_from_slice(&self.stack[count - N..]);
+        self.stack.truncate(count - N);
+        values
+    }
+
+    #[inline]
+    fn push(&mut self, value: i32) -> Result<()> {
+        if self.stack.len() >= self.limits.stack {
+            raise!("found a stack overflow");
+        }
+        self.stack.push(value);
+        Ok(())
+    }
+
+    fn call(&mut self, definition: Definition, count: i32) -> Result<()> {
+        if self.calls.len() >= CALL_LIMIT {
+            raise!("found too many nested calls");
+        }
+        self.calls.push(Call {
+            range: self.range,
+            ip: self.ip,
+            count,
+            definition,
+        });
+        self.range = definition.range;
+        self.ip = definition.start;
+        Ok(())
+    }
+
+    fn define(&mut self) -> Result<Definition> {
+        let start = self.ip;
+        let code = &self.programs[self.range];
+        let mut ip = start;
+        while ip < code.len() {
+            let opcode = code[ip];
+            match opcode {
+                0x2C | 0x89 => raise!("found nested definitions"),
+                0x2D => {
+                    self.ip = ip + 1;
+                    return Ok(Definition {
+                        range: self.range,
+                        start,
+                        end: ip,
+                    });
+                }
+                _ => {}
+            }
+            ip += match length(code, ip) {
+                Some(value) => value,
+                _ => break,
+            };
+        }
+        raise!("found a definition without an end")
+    }
+
+    fn jump(&mut self, start: usize, offset: i32) -> Result<()> {
+        let ip = start as i64 + offset as i64;
+        let end = self.calls.last().map(|call| call.definition.end as i64);
+        if ip < 0 || matches!(end, Some(end) if ip > end) {
+            raise!("found an invalid jump");
+        }
+        self.ip = ip as usize;
+        Ok(())
+    }
+
+    // Skip to the matching ELSE or EIF when the condition of IF fails and to
+    // the matching EIF when ELSE is reached.
+    fn skip(&mut self, otherwise: bool) -> Result<()> {
+        let code = &self.programs[self.range];
+        let mut depth = 1;
+        let mut ip = self.ip;
+        while ip < code.len() {
+            let opcode = code[ip];
+            ip += match length(code, ip) {
+                Some(value) => value,
+                _ => break,
+            };
+            match opcode {
+                0x58 => depth += 1,
+                0x1B if otherwise && depth == 1 => {
+                    self.ip = ip;
+                    return Ok(());
+                }
+                0x59 => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.ip = ip;
+                        return Ok(());
+                    }
+                }
+                _ => {}
+            }
+        }
+        raise!("found a condition without an end")
+    }
+
+    #[inline]
+    fn zone(&self, index: usize) -> &Zone {
+        if self.state.zones[index] == 0 {
+            &self.twilight
+        } else {
+            &self.glyph
+        }
+    }
+
+    #[inline]
+    fn zone_mut(&mut self, index: usize) -> &mut Zone {
+        if self.state.zones[index] == 0 {
+            &mut self.twilight
+        } else {
+            &mut self.glyph
+        }
+    }
+
+    fn compute(&mut self) {
+        let (projection, freedom) = (self.state.projection, self.state.freedom);
+        let mut value = if freedom.0 == UNIT {
+            projection.0
+        } else if freedom.1 == UNIT {
+            projection.1
+        } else {
+            ((projection.0 as i64 * freedom.0 as i64 + projection.1 as i64 * freedom.1 as i64)
+                >> 14) as i32
+        };
+        // Small values lead to spikes at small sizes.
+        if value.abs() < 0x400 {
+            value = UNIT;
+        }
+        self.free_dot_projection = value;
+    }
+
+    #[inline]
+    fn project(&self, a: Point, b: Point) -> i32 {
+        let (x, y) = self.state.projection;
+        dot_fix14(a.x.wrapping_sub(b.x), a.y.wrapping_sub(b.y), x, y)
+    }
+
+    // Measure the original distance between two points.
+    fn measure(&self, one: usize, a: usize, other: usize, b: usize) -> i32 {
+        if self.state.zones[one] == 0 || self.state.zones[other] == 0 {
+            return dual_project(
+                &self.state,
+                self.zone(one).scaled[a],
+                self.zone(other).scaled[b],
+            );
+        }
+        let value = dual_project(
+            &self.state,
+            self.zone(one).original[a],
+            self.zone(other).original[b],
+        );
+        mul_fix(value, self.unit)
+    }
+
+    fn shift(&mut self, index: usize, point: usize, distance: i32) {
+        let (x, y) = self.state.freedom;
+        let value = self.free_dot_projection;
+        let zone = self.zone_mut(index);
+        if x != 0 {
+            let current = &mut zone.current[point];
+            current.x = current.x.wrapping_add(mul_div(distance, x, value));
+            zone.flags[point] |= TOUCHED_X;
+        }
+        if y != 0 {
+            let current = &mut zone.current[point];
+            current.y = current.y.wrapping_add(mul_div(distance, y, value));
+            zone.flags[point] |= TOUCHED_Y;
+        }
+    }
+
+    fn shift_scaled(&mut self, index: usize, point: usize, distance: i32) {
+        let (x, y) = self.state.freedom;
+        let value = self.free_dot_projection;
+        let scaled = &mut self.zone_mut(index).scaled[point];
+        if x != 0 {
+            scaled.x = scaled.x.wrapping_add(mul_div(distance, x, value));
+        }
+        if y != 0 {
+            scaled.y = scaled.y.wrapping_add(mul_div(distance, y, value));
+        }
+    }
+
+    fn displacement(&self, opcode: u8) -> Option<(i32, i32, u8, usize)> {
+        let (index, reference) = if opcode & 1 == 1 {
+            (0, self.state.references[1])
+        } else {
+            (1, self.state.references[2])
+        };
+        let zone = self.zone(index);
+        if reference >= zone.len() {
+            return None;
+        }
+        let distance = self.project(zone.current[reference], zone.scaled[reference]);
+        let (x, y) = self.state.freedom;
+        Some((
+            mul_div(distance, x, self.free_dot_projection),
+            mul_div(distance, y, self.free_dot_projection),
+            self.state.zones[index],
+            reference,
+        ))
+    }
+
+    fn displace(&mut self, point: usize, dx: i32, dy: i32, touch: bool) {
+        let (x, y) = self.state.freedom;
+        let zone = self.zone_mut(2);
+        if x != 0 {
+            zone.current[point].x = zone.current[point].x.wrapping_add(dx);
+            if touch {
+                zone.flags[point] |= TOUCHED_X;
+            }
+        }
+        if y != 0 {
+            zone.current[point].y = zone.current[point].y.wrapping_add(dy);
+            if touch {
+                zone.flags[point] |= TOUCHED_Y;
+            }
+        }
+    }
+
+    fn round(&self, distance: i32) -> i32 {
+        let State {
+            period,
+            phase,
+            threshold,
+            ..
+        } = self.state;
+        let positive = distance >= 0;
+        let value = distance.wrapping_abs();
+        let value = match self.state.round {
+            Round::Off => return distance,
+            Round::Grid => value.wrapping_add(32) & !63,
+            Round::HalfGrid => (value & !63) + 32,
+            Round::DoubleGrid => value.wrapping_add(16) & !31,
+            Round::DownToGrid => value & !63,
+            Round::UpToGrid => value.wrapping_add(63) & !63,
+            Round::Super => {
+                let value = (value.wrapping_add(threshold - phase) & -period).wrapping_add(phase);
+                return match positive {
+                    true if value < 0 => phase,
+                    true => value,
+                    _ => {
+                        let value = (distance.wrapping_neg().wrapping_add(threshold - phase)
+                            & -period)
+                            .wrapping_neg()
+                            .wrapping_sub(phase);
+                        if value > 0 {
+                            -phase
+                        } else {
+                            value
+                        }
+                    }
+                };
+            }
+            Round::Super45 => {
+                let value =
+                    (value.wrapping_add(threshold - phase) / period * period).wrapping_add(phase);
+                return match positive {
+                    true if value < 0 => phase,
+                    true => value,
+                    _ => {
+                        let value = ((distance.wrapping_neg().wrapping_add(threshold - phase)
+                            / period)
+                            * period)
+                            .wrapping_neg()
+                            .wrapping_sub(phase);
+                        if value > 0 {
+                            -phase
+                        } else {
+                            value
+                        }
+                    }
+                };
+            }
+        };
+        match (positive, self.state.round) {
+            (true, Round::HalfGrid) if value < 0 => 32,
+            (true, _) if value < 0 => 0,
+            (true, _) => value,
+            (false, Round::HalfGrid) if -value > 0 => -32,
+            (false, _) if -value > 0 => 0,
+            (false, _) => -value,
+        }
+    }
+
+    fn round_super(&mut self, period: i32, selector: i32) {
+        let state = &mut self.state;
+        state.period = match selector & 0xC0 {
+            0x00 => period / 2,
+            0x80 => period * 2,
+            _ => period,
+        };
+        state.phase = match selector & 0x30 {
+            0x00 => 0,
+            0x10 => state.period / 4,
+            0x20 => state.period / 2,
+            _ => state.period * 3 / 4,
+        };
+        state.threshold = if selector & 0x0F == 0 {
+            state.period - 1
+        } else {
+            ((selector & 0x0F) - 4) * state.period / 8
+        };
+        state.period >>= 8;
+        state.phase >>= 8;
+        state.threshold >>= 8;
+    }
+
+    fn intersect(&mut self) -> Result<()> {
+        let [point, a0, a1, b0, b1] = self.pop::<5>();
+        let [point, a0, a1, b0, b1] = [point, a0, a1, b0, b1].map(|value| value as u16 as usize);
+        skip!(
+            b0 >= self.zone(0).len()
+                || b1 >= self.zone(0).len()
+                || a0 >= self.zone(1).len()
+                || a1 >= self.zone(1).len()
+                || point >= self.zone(2).len()
+        );
+        let (b0, b1) = (self.zone(0).current[b0], self.zone(0).current[b1]);
+        let (a0, a1) = (self.zone(1).current[a0], self.zone(1).current[a1]);
+        let (dbx, dby) = (b1.x.wrapping_sub(b0.x), b1.y.wrapping_sub(b0.y));
+        let (dax, day) = (a1.x.wrapping_sub(a0.x), a1.y.wrapping_sub(a0.y));
+        let (dx, dy) = (b0.x.wrapping_sub(a0.x), b0.y.wrapping_sub(a0.y));
+        let discriminant =
+            mul_div(dax, dby.wrapping_neg(), 0x40).wrapping_add(mul_div(day, dbx, 0x40));
+        let product = mul_div(dax, dbx, 0x40).wrapping_add(mul_div(day, dby, 0x40));
+        // Grazing intersections are rejected by thresholding the tangent of
+        // the angle between the lines at 1/19.
+        let value = if 19 * (discriminant as i64).abs() > (product as i64).abs() {
+            let value = mul_div(dx, dby.wrapping_neg(), 0x40).wrapping_add(mul_div(dy, dbx, 0x40));
+            Point {
+                x: a0.x.wrapping_add(mul_div(value, dax, discriminant)),
+                y: a0.y.wrapping_add(mul_div(value, day, discriminant)),
+            }
+        } else {
+            Point {
+                x: a0
+                    .x
+                    .wrapping_add(a1.x)
+                    .wrapping_add(b0.x.wrapping_add(b1.x))
+                    / 4,
+                y: a0
+                    .y
+                    .wrapping_add(a1.y)
+                    .wrapping_add(b0.y.wrapping_add(b1.y))
+                    / 4,
+            }
+        };
+        let zone = self.zone_mut(2);
+        zone.current[point] = value;
+        zone.flags[point] |= TOUCHED_X | TOUCHED_Y;
+        Ok(())
+    }
+
+    fn interpolate(&mut self) {
+        let [one, other] = [self.state.references[1], self.state.references[2]];
+        if self.stack.len() < self.state.repetition as usize || one >= self.zone(0).len() {
+            self.state.repetition = 1;
+            return;
+        }
+        // The original positions in the twilight zone are the scaled ones.
+        let twilight = self.state.zones.contains(&0);
+        let original = |interpreter: &Self, index: usize, point: usize| {
+            let zone = interpreter.zone(index);
+            if twilight {
+                zone.scaled[point]
+            } else {
+                zone.original[point]
+            }
+        };
+        let base = original(self, 0, one);
+        let current_base = self.zone(0).current[one];
+        let (old_range, current_range) = if other < self.zone(1).len() {
+            (
+                dual_project(&self.state, original(self, 1, other), base),
+                self.project(self.zone(1).current[other], current_base),
+            )
+        } else {
+            (0, 0)
+        };
+        for _ in 0..self.state.repetition {
+            let point = self.stack.pop().unwrap_or(0) as u16 as usize;
+            if point >= self.zone(2).len() {
+                continue;
+            }
+            let original_distance = dual_project(&self.state, original(self, 2, point), base);
+            let current_distance = self.project(self.zone(2).current[point], current_base);
+            let distance = match (original_distance, old_range) {
+                (0, _) => 0,
+                (_, 0) => original_distance,
+                _ => mul_div(original_distance, current_range, old_range),
+            };
+            self.shift(2, point, distance.wrapping_sub(current_distance));
+        }
+        self.state.repetition = 1;
+    }
+
+    fn interpolate_untouched(&mut self, x: bool) {
+        let mask = if x { TOUCHED_X } else { TOUCHED_Y };
+        let zone = &mut self.glyph;
+        let count = zone.len();
+        let mut point = 0;
+        for i in 0..zone.end_points.len() {
+            let mut last = zone.end_points[i];
+            if last >= count {
+                last = count - 1;
+            }
+            let first = point;
+            while point <= last && zone.flags[point] & mask == 0 {
+                point += 1;
+            }
+            if point <= last {
+                let first_touched = point;
+                let mut current_touched = point;
+                point += 1;
+                while point <= last {
+                    if zone.flags[point] & mask != 0 {
+                        untouched(
+                            zone,
+                            x,
+                            current_touched + 1,
+                            point - 1,
+                            current_touched,
+                            point,
+                        );
+                        current_touched = point;
+                    }
+                    point += 1;
+                }
+                if current_touched == first_touched {
+                    let delta =
+                        zone.current[current_touched].get(x) - zone.scaled[current_touched].get(x);
+                    if delta != 0 {
+                        for j in (first..=last).filter(|&j| j != current_touched) {
+                            let value = zone.current[j].get(x).wrapping_add(delta);
+                            zone.current[j].set(x, value);
+                        }
+                    }
+                } else {
+                    untouched(
+                        zone,
+                        x,
+                        current_touched + 1,
+                        last,
+                        current_touched,
+                        first_touched,
+                    );
+                    if first_touched > 0 {
+                        untouched(
+                            zone,
+                            x,
+                            first,
+                            first_touched - 1,
+                            current_touched,
+                            first_touched,
+                        );
+                    }
+                }
+            }
+            point = point.max(last + 1);
+        }
+    }
+
+    fn delta_point(&mut self, opcode: u8) {
+        let [count] = self.pop::<1>();
+        let base = match opcode {
+            0x5D => 0,
+            0x71 => 16,
+            _ => 32,
+        };
+        for _ in 0..count.max(0) {
+            if self.stack.len() < 2 {
+                self.stack.clear();
+                return;
+            }
+            let [argument, point] = self.pop::<2>();
+            let point = point as u16 as usize;
+            if point >= self.zone(0).len() {
+                continue;
+            }
+            if let Some(distance) = self.delta(base, argument) {
+                self.shift(0, point, distance);
+            }
+        }
+    }
+
+    fn delta_cvt(&mut self, opcode: u8) {
+        let [count] = self.pop::<1>();
+        let base = match opcode {
+            0x73 => 0,
+            0x74 => 16,
+            _ => 32,
+        };
+        for _ in 0..count.max(0) {
+            if self.stack.len() < 2 {
+                self.stack.clear();
+                return;
+            }
+            let [argument, entry] = self.pop::<2>();
+            let entry = entry as u32 as usize;
+            if entry >= self.cvt.len() {
+                continue;
+            }
+            if let Some(distance) = self.delta(base, argument) {
+                self.cvt[entry] = self.cvt[entry].wrapping_add(distance);
+            }
+        }
+    }
+
+    fn delta(&self, base: i32, argument: i32) -> Option<i32> {
+        let ppem = ((argument as u32 & 0xF0) >> 4) as i32 + base + self.state.delta_base;
+        if ppem != self.ppem {
+            return None;
+        }
+        let mut steps = (argument & 0xF) - 8;
+        if steps >= 0 {
+            steps += 1;
+        }
+        Some(steps * (1 << (6 - self.state.delta_shift)))
+    }
+
+    fn move_direct(&mut self, opcode: u8) {
+        let [point] = self.pop::<1>();
+        let point = point as u16 as usize;
+        let reference = self.state.references[0];
+        if point < self.zone(1).len() && reference < self.zone(0).len() {
+            let mut original = self.measure(1, point, 0, reference);
+            let State {
+                single_width_cutin: cutin,
+                single_width_value: width,
+                ..
+            } = self.state;
+            if cutin > 0 && original < width + cutin && original > width - cutin {
+                original = if original >= 0 { width } else { -width };
+            }
+            let mut distance = if opcode & 4 != 0 {
+                self.round(original)
+            } else {
+                original
+            };
+            if opcode & 8 != 0 {
+                distance = self.limit(original, distance);
+            }
+            let current =
+                self.project(self.zone(1).current[point], self.zone(0).current[reference]);
+            self.shift(1, point, distance.wrapping_sub(current));
+        }
+        self.state.references[1] = reference;
+        self.state.references[2] = point;
+        if opcode & 16 != 0 {
+            self.state.references[0] = point;
+        }
+    }
+
+    fn move_indirect(&mut self, opcode: u8) {
+        let [point, entry] = self.pop::<2>();
+        let point = point as u16 as usize;
+        let entry = (entry as u32).wrapping_add(1) as usize;
+        let reference = self.state.references[0];
+        if point < self.zone(1).len()
+            && entry < self.cvt.len() + 1
+            && reference < self.zone(0).len()
+        {
+            let mut target = if entry == 0 { 0 } else { self.cvt[entry - 1] };
+            let State {
+                single_width_cutin: cutin,
+                single_width_value: width,
+                ..
+            } = self.state;
+            if (target - width).abs() < cutin {
+                target = if target >= 0 { width } else { -width };
+            }
+            if self.state.zones[1] == 0 {
+                let base = self.zone(0).scaled[reference];
+                let value = Point {
+                    x: base.x.wrapping_add(mul_fix14(target, self.state.freedom.0)),
+                    y: base.y.wrapping_add(mul_fix14(target, self.state.freedom.1)),
+                };
+                self.twilight.scaled[point] = value;
+                self.twilight.current[point] = value;
+            }
+            let original = dual_project(
+                &self.state,
+                self.zone(1).scaled[point],
+                self.zone(0).scaled[reference],
+            );
+            let current =
+                self.project(self.zone(1).current[point], self.zone(0).current[reference]);
+            if self.state.auto_flip && (original ^ target) < 0 {
+                target = target.wrapping_neg();
+            }
+            let mut distance = if opcode & 4 != 0 {
+                // The cut-in applies only when both points are in the same
+                // zone.
+                if self.state.zones[0] == self.state.zones[1]
+                    && target.wrapping_sub(original).wrapping_abs() > self.state.control_value_cutin
+                {
+                    target = original;
+                }
+                self.round(target)
+            } else {
+                target
+            };
+            if opcode & 8 != 0 {
+                distance = self.limit(original, distance);
+            }
+            self.shift(1, point, distance.wrapping_sub(current));
+        }
+        self.state.references[1] = reference;
+        if opcode & 16 != 0 {
+            self.state.references[0] = point;
+        }
+        self.state.references[2] = point;
+    }
+
+    fn limit(&self, original: i32, distance: i32) -> i32 {
+        let minimum = self.state.minimum_distance;
+        if original >= 0 {
+            distance.max(minimum)
+        } else {
+            distance.min(minimum.wrapping_neg())
+        }
+    }
+}
+
+fn untouched(zone: &mut Zone, x: bool, first: usize, last: usize, one: usize, other: usize) {
+    if first > last || one >= zone.len() || other >= zone.len() {
+        return;
+    }
+    let (mut one, mut other) = (one, other);
+    if zone.original[one].get(x) > zone.original[other].get(x) {
+        std::mem::swap(&mut one, &mut other);
+    }
+    let (original1, original2) = (zone.original[one].get(x), zone.original[other].get(x));
+    let (scaled1, scaled2) = (zone.scaled[one].get(x), zone.scaled[other].get(x));
+    let (current1, current2) = (zone.current[one].get(x), zone.current[other].get(x));
+    let (delta1, delta2) = (
+        current1.wrapping_sub(scaled1),
+        current2.wrapping_sub(scaled2),
+    );
+    let trivial = current1 == current2 || original1 == original2;
+    let mut scale = None;
+    for i in first..=last {
+        let value = zone.scaled[i].get(x);
+        let value = if value <= scaled1 {
+            value.wrapping_add(delta1)
+        } else if value >= scaled2 {
+            value.wrapping_add(delta2)
+        } else if trivial {
+            current1
+        } else {
+            let scale = *scale.get_or_insert_with(|| {
+                div_fix(
+                    current2.wrapping_sub(current1),
+                    original2.wrapping_sub(original1),
+                )
+            });
+            current1.wrapping_add(mul_fix(
+                zone.original[i].get(x).wrapping_sub(original1),
+                scale,
+            ))
+        };
+        zone.current[i].set(x, value);
+    }
+}
+
+fn dual_project(state: &State, a: Point, b: Point) -> i32 {
+    let (x, y) = state.dual;
+    dot_fix14(a.x.wrapping_sub(b.x), a.y.wrapping_sub(b.y), x, y)
+}
+
+// Return the unit vector along or across a line given by a vector.
+fn line(x: i32, y: i32, perpendicular: u8) -> (i32, i32) {
+    match (x, y) {
+        (0, 0) => (UNIT, 0),
+        _ if perpendicular == 1 => normalize(y.wrapping_neg(), x),
+        _ => normalize(x, y),
+    }
+}
+
+// Normalize a vector into a unit one given in 2.14 fixed-point units using
+// the same integer approximation as FreeType.
+fn normalize(x: i32, y: i32) -> (i32, i32) {
+    let (sx, sy) = (x.signum(), y.signum());
+    let (mut ux, mut uy) = (x.unsigned_abs(), y.unsigned_abs());
+    if ux == 0 {
+        return (0, if uy > 0 { sy * UNIT } else { 0 });
+    } else if uy == 0 {
+        return (sx * UNIT, 0);
+    }
+    let estimate = |x: u32, y: u32| {
+        if x > y {
+            x.wrapping_add(y >> 1)
+        } else {
+            y.wrapping_add(x >> 1)
+        }
+    };
+    let mut length = estimate(ux, uy);
+    let mut shift = 31 - (31 - length.leading_zeros() as i32);
+    shift -= 15 + (length >= (0xAAAA_AAAAu32 >> shift)) as i32;
+    if shift > 0 {
+        ux <<= shift;
+        uy <<= shift;
+        length = estimate(ux, uy);
+    } else {
+        ux >>= -shift;
+        uy >>= -shift;
+        length >>= -shift;
+    }
+    let mut b = 0x10000 - length as i32;
+    let (x, y) = (ux as i32, uy as i32);
+    let (mut u, mut v);
+    loop {
+        u = x.wrapping_add(x.wrapping_mul(b) >> 16) as u32;
+        v = y.wrapping_add(y.wrapping_mul(b) >> 16) as u32;
+        let mut z =
+            (u.wrapping_mul(u).wrapping_add(v.wrapping_mul(v)) as i32).wrapping_neg() / 0x200;
+        z = z.wrapping_mul((0x10000 + b) >> 8) / 0x10000;
+        b += z;
+        if z <= 0 {
+            break;
+        }
+    }
+    (sx * (u as i32) / 4, sy * (v as i32) / 4)
+}
+
+fn length(code: &[u8], ip: usize) -> Option<usize> {
+    let opcode = code[ip];
+    let value = match opcode {
+        0x40 => 2 + *code.get(ip + 1)? as usize,
+        0x41 => 2 + 2 * *code.get(ip + 1)? as usize,
+        0xB0..=0xB7 => 2 + (opcode - 0xB0) as usize,
+        0xB8..=0xBF => 3 + 2 * (opcode - 0xB8) as usize,
+        _ => 1,
+    };
+    if ip + value > code.len() {
+        return None;
+    }
+    Some(value)
+}
+
+fn dot_fix14(ax: i32, ay: i32, bx: i32, by: i32) -> i32 {
+    let value = ax as i64 * bx as i64 + ay as i64 * by as i64;
+    ((value + 0x2000 + (value >> 63)) >> 14) as i32
+}
+
+fn mul_fix14(a: i32, b: i32) -> i32 {
+    let value = a as i64 * b as i64;
+    ((value + 0x2000 + (value >> 63)) >> 14) as i32
+}
+
+pub(super) fn mul_fix(a: i32, b: i32) -> i32 {
+    let value = a as i64 * b as i64;
+    ((value + 0x8000 + (value >> 63)) >> 16) as i32
+}
+
+pub(super) fn div_fix(a: i32, b: i32) -> i32 {
+    let negative = (a < 0) != (b < 0);
+    let (a, b) = ((a as i64).abs(), (b as i64).abs());
+    let value = if b > 0 {
+        ((a << 16) + (b >> 1)) / b
+    } else {
+        0x7FFF_FFFF
+    };
+    (if negative { -value } else { value }) as i32
+}
+
+fn mul_div(a: i32, b: i32, c: i32) -> i32 {
+    let negative = (a < 0) ^ (b < 0) ^ (c < 0);
+    let (a, b, c) = ((a as i64).abs(), (b as i64).abs(), (c as i64).abs());
+    let value = if c > 0 {
+        (a * b + (c >> 1)) / c
+    } else {
+        0x7FFF_FFFF
+    };
+    (if negative { -value } else { value }) as i32
+}
+
+fn mul_div_no_round(a: i32, b: i32, c: i32) -> i32 {
+    let negative = (a < 0) ^ (b < 0) ^ (c < 0);
+    let (a, b, c) = ((a as i64).abs(), (b as i64).abs(), (c as i64).abs());
+    let value = if c > 0 { a * b / c } else { 0x7FFF_FFFF };
+    (if negative { -value } else { value }) as i32
+}
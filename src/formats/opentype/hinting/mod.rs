@@ -0,0 +1,292 @@
+mod interpreter;
+
+use std::io::Result;
+use std::ops::DerefMut;
+
+use opentype::truetype::tables::glyph_data::{
+    Arguments, CompositeDescription, Description, GlyphData,
+};
+use opentype::truetype::tables::MaximumProfile;
+use opentype::truetype::GlyphID;
+
+use crate::formats::opentype::cache::Cache;
+use crate::formats::opentype::font::Drawing;
+use crate::formats::opentype::metrics::{self, Metrics};
+use crate::formats::opentype::{tables, truetype};
+use crate::glyph::Pen;
+use crate::offset::Offset;
+use crate::Number;
+
+use self::interpreter::{div_fix, mul_fix, Interpreter, Limits, Point, Zone, ON_CURVE};
+
+const DEPTH_LIMIT: usize = 16;
+
+/// The state of hinting of a font.
+pub struct Hinting {
+    interpreter: Interpreter,
+    units_per_em: i32,
+    vertical_metrics: (i32, i32),
+    ppem: Option<u16>,
+    scale: i32,
+}
+
+impl Hinting {
+    pub fn read<T: crate::Read>(cache: &mut Cache<T>) -> Result<Self> {
+        let units_per_em = cache.font_header()?.borrow().units_per_em as i32;
+        let limits = match &*cache.maximum_profile()?.borrow() {
+            MaximumProfile::Version1(profile) => Limits {
+                functions: profile.max_function_definitions as usize,
+                instructions: profile.max_instruction_definitions as usize,
+                stack: profile.max_stack_elements as usize,
+                storage: profile.max_storage as usize,
+                twilight: profile.max_twilight_points as usize,
+            },
+            _ => raise!("found no limits for TrueType instructions"),
+        };
+        let metrics = metrics::read(cache)?;
+        let vertical_metrics = (metrics.ascender as i32, metrics.descender as i32);
+        let records = cache.backend.offsets.records.clone();
+        let mut tape = cache.tape.borrow_mut();
+        let tape = tape.deref_mut();
+        let mut take = |tag| tables::take(tape, &records, tag).map(Option::unwrap_or_default);
+        let font_program = take(b"fpgm")?;
+        let control_program = take(b"prep")?;
+        let control_values = take(b"cvt ")?
+            .chunks_exact(2)
+            .map(|chunk| i16::from_be_bytes([chunk[0], chunk[1]]))
+            .collect();
+        Ok(Self {
+            interpreter: Interpreter::new(font_program, control_program, control_values, limits)?,
+            units_per_em,
+            vertical_metrics,
+            ppem: None,
+            scale: 0,
+        })
+    }
+
+    /// Draw a glyph grid-fitted at a size given in pixels per em.
+    ///
+    /// The outline is in pixels with the origin at the hinted left phantom
+    /// point.
+    pub(super) fn draw(
+        &mut self,
+        glyph_data: &GlyphData,
+        metrics: &Metrics,
+        glyph_id: GlyphID,
+        ppem: u16,
+        pen: &mut dyn Pen,
+    ) -> Result<Drawing> {
+        if ppem == 0 {
+            raise!("found a zero size for hinting");
+        }
+        if self.ppem != Some(ppem) {
+            self.ppem = None;
+            self.scale = div_fix(ppem as i32 * 64, self.units_per_em);
+            self.interpreter.prepare(ppem as i32, self.scale)?;
+            self.ppem = Some(ppem);
+        }
+        let zone = self.load(glyph_data, metrics, glyph_id, 0)?;
+        let count = zone.current.len() - 4;
+        let origin = zone.current[count].x;
+        let convert = |point: Point| Offset((point.x - origin) as Number, point.y as Number) / 64.0;
+        let mut first = 0;
+        for &last in zone.end_points.iter() {
+            let points = (first..=last)
+                .map(|i| (convert(zone.current[i]), zone.flags[i] & ON_CURVE != 0))
+                .collect::<Vec<_>>();
+            super::truetype::trace(pen, points);
+            first = last + 1;
+        }
+        let advance_width = (zone.current[count + 1].x - origin + 32) & !63;
+        Ok(Drawing {
+            horizontal_metrics: (advance_width as Number / 64.0, 0.0),
+            ..Default::default()
+        })
+    }
+
+    // Load a glyph with its four phantom points at the end as FreeType does.
+    fn load(
+        &mut self,
+        glyph_data: &GlyphData,
+        metrics: &Metrics,
+        glyph_id: GlyphID,
+        depth: usize,
+    ) -> Result<Zone> {
+        if depth > DEPTH_LIMIT {
+            raise!("found too deeply nested components in glyph {}", glyph_id);
+        }
+        let glyph = match glyph_data.get(glyph_id as usize) {
+            Some(glyph) => glyph,
+            _ => raise!("found no data for glyph {}", glyph_id),
+        };
+        let (advance_width, left_side_bearing) = metrics.get(glyph_id);
+        let (advance_width, left_side_bearing) = (advance_width as i32, left_side_bearing as i32);
+        let min_x = glyph.as_ref().map(|glyph| glyph.min_x as i32).unwrap_or(0);
+        let (ascender, descender) = self.vertical_metrics;
+        let x = min_x - left_side_bearing;
+        let phantoms = [
+            Point { x, y: 0 },
+            Point {
+                x: x + advance_width,
+                y: 0,
+            },
+            Point { x: 0, y: ascender },
+            Point {
+                x: 0,
+                y: ascender - (ascender - descender).abs(),
+            },
+        ];
+        let scale = |point: Point| Point {
+            x: mul_fix(point.x, self.scale),
+            y: mul_fix(point.y, self.scale),
+        };
+        let enabled = self.interpreter.is_enabled();
+        let glyph = match glyph {
+            Some(glyph) => glyph,
+            _ => {
+                return Ok(Zone {
+                    current: phantoms.map(scale).to_vec(),
+                    flags: vec![0; 4],
+                    ..Default::default()
+                });
+            }
+        };
+        match &glyph.description {
+            Description::Simple(description) => {
+                let mut zone = Zone::default();
+                let mut point = Point::default();
+                for (x, y) in description.x.iter().zip(description.y.iter()) {
+                    point.x += *x as i32;
+                    point.y += *y as i32;
+                    zone.original.push(point);
+                }
+                zone.original.extend(phantoms);
+                zone.scaled = zone.original.iter().map(|&point| scale(point)).collect();
+                zone.current = zone.scaled.clone();
+                zone.flags = description
+                    .flags
+                    .iter()
+                    .map(|flags| flags.0 & ON_CURVE)
+                    .collect();
+                zone.flags.extend([0; 4]);
+                zone.end_points = description
+                    .end_points
+                    .iter()
+                    .map(|&value| value as usize)
+                    .collect();
+                if zone.flags.len() != zone.current.len()
+                    || zone.end_points.last().map(|&value| value + 4) >= Some(zone.current.len())
+                {
+                    raise!("found a malformed glyph");
+                }
+                if enabled {
+                    self.hint(&mut zone, &description.instructions, false);
+                }
+                Ok(zone)
+            }
+            Description::Composite(description) => {
+                let phantoms = phantoms.map(scale);
+                self.load_composite(glyph_data, metrics, description, phantoms, depth, enabled)
+            }
+        }
+    }
+
+    fn load_composite(
+        &mut self,
+        glyph_data: &GlyphData,
+        metrics: &Metrics,
+        description: &CompositeDescription,
+        mut phantoms: [Point; 4],
+        depth: usize,
+        enabled: bool,
+    ) -> Result<Zone> {
+        let mut zone = Zone::default();
+        for component in description.components.iter() {
+            let mut child = self.load(glyph_data, metrics, component.glyph_id, depth + 1)?;
+            let count = child.current.len() - 4;
+            if component.flags.should_use_metrics() {
+                phantoms.copy_from_slice(&child.current[count..]);
+            }
+            child.current.truncate(count);
+            child.flags.truncate(count);
+            let matrix = truetype::matrix(component.options)
+                .map(|(xx, xy, yx, yy)| [xx, xy, yx, yy].map(|value| (value.0 as i32) << 2));
+            if let Some([xx, xy, yx, yy]) = matrix {
+                for point in child.current.iter_mut() {
+                    *point = Point {
+                        x: mul_fix(point.x, xx) + mul_fix(point.y, xy),
+                        y: mul_fix(point.x, yx) + mul_fix(point.y, yy),
+                    };
+                }
+            }
+            let offset = match component.arguments {
+                Arguments::Offsets(x, y) => {
+                    let (mut x, mut y) = (x as i32, y as i32);
+                    let flags = component.flags;
+                    if let Some([xx, xy, yx, yy]) = matrix {
+                        if flags.is_offset_scaled() && !flags.is_offset_unscaled() {
+                            let length = |a: i32, b: i32| (a as f64).hypot(b as f64).round() as i32;
+                            x = mul_fix(x, length(xx, xy));
+                            y = mul_fix(y, length(yy, yx));
+                        }
+                    }
+                    x = mul_fix(x, self.scale);
+                    y = mul_fix(y, self.scale);
+                    if enabled && flags.should_round_xy_to_grid() {
+                        x = (x + 32) & !63;
+                        y = (y + 32) & !63;
+                    }
+                    Point { x, y }
+                }
+                Arguments::Indices(one, other) => {
+                    match (
+                        zone.current.get(one as usize),
+                        child.current.get(other as usize),
+                    ) {
+                        (Some(one), Some(other)) => Point {
+                            x: one.x - other.x,
+                            y: one.y - other.y,
+                        },
+                        _ => raise!(
+                            "found a component of glyph {} with unknown points ({} and {})",
+                            component.glyph_id,
+                            one,
+                            other,
+                        ),
+                    }
+                }
+            };
+            let base = zone.current.len();
+            zone.current.extend(child.current.iter().map(|point| Point {
+                x: point.x + offset.x,
+                y: point.y + offset.y,
+            }));
+            zone.flags
+                .extend(child.flags.iter().map(|flags| flags & ON_CURVE));
+            zone.end_points
+                .extend(child.end_points.iter().map(|value| value + base));
+        }
+        let hinted = enabled && !description.instructions.is_empty() && !zone.current.is_empty();
+        zone.current.extend(phantoms);
+        zone.flags.extend([0; 4]);
+        zone.original = zone.current.clone();
+        zone.scaled = zone.current.clone();
+        if hinted {
+            self.hint(&mut zone, &description.instructions, true);
+        }
+        Ok(zone)
+    }
+
+    fn hint(&mut self, zone: &mut Zone, instructions: &[u8], composite: bool) {
+        let count = zone.current.len() - 4;
+        for i in 0..2 {
+            zone.current[count + i].x = (zone.current[count + i].x + 32) & !63;
+            zone.current[count + 2 + i].y = (zone.current[count + 2 + i].y + 32) & !63;
+        }
+        if !instructions.is_empty() {
+            // As in FreeType, a failure leaves the points as they are at the
+            // moment, since malformed instructions are common in the wild.
+            let _ = self.interpreter.hint(zone, instructions, composite);
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Result;
+use std::ops::DerefMut;
+
+use opentype::layout::Class;
+use opentype::tables::glyph_positioning::{PairAdjustment, Type};
+use opentype::truetype::GlyphID;
+
+use crate::formats::opentype::cache::Cache;
+use crate::formats::opentype::tables;
+use crate::formats::opentype::writing::layout;
+
+type Pairs = Vec<(GlyphID, GlyphID, i16)>;
+
+/// Kerning taken from the glyph positioning or, if it has none, from the
+/// legacy kerning table.
+#[derive(Default)]
+pub struct Kerning {
+    lookups: Vec<Vec<Table>>,
+}
+
+enum Table {
+    Pairs(HashMap<(GlyphID, GlyphID), i16>),
+    Classes {
+        coverage: HashSet<GlyphID>,
+        first: HashMap<GlyphID, u16>,
+        second: HashMap<GlyphID, u16>,
+        values: Vec<Vec<i16>>,
+    },
+}
+
+impl Kerning {
+    pub fn read<T: crate::Read>(cache: &mut Cache<T>) -> Result<Self> {
+        let records = cache.backend.offsets.records.clone();
+        let directory = cache
+            .try_glyph_positioning()?
+            .map(|table| table.borrow().clone());
+        let mut tape = cache.tape.borrow_mut();
+        let tape = tape.deref_mut();
+        let mut lookups = vec![];
+        if let Some(mut directory) = directory {
+            if let Some(record) = records.iter().find(|record| record.tag == b"GPOS") {
+                layout::resolve(tape, record.offset as u64, &mut directory)?;
+            }
+            for index in layout::select(&directory, |tag| tag == b"kern") {
+                let record = match directory.lookups.records.get(index as usize) {
+                    Some(record) => record,
+                    _ => continue,
+                };
+                let tables = record
+                    .tables
+                    .iter()
+                    .filter_map(|table| match table {
+                        Type::PairAdjustment(table) => Some(pairs(table)),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                if !tables.is_empty() {
+                    lookups.push(tables);
+                }
+            }
+        }
+        if lookups.is_empty() {
+            if let Some(data) = tables::take(tape, &records, b"kern")? {
+                for (_, pairs) in legacy(&data)? {
+                    let values = pairs
+                        .into_iter()
+                        .map(|(one, other, value)| ((one, other), value))
+                        .collect();
+                    lookups.push(vec![Table::Pairs(values)]);
+                }
+            }
+        }
+        Ok(Self { lookups })
+    }
+
+    pub fn get(&self, one: GlyphID, other: GlyphID) -> i16 {
+        let mut sum = 0i16;
+        for tables in self.lookups.iter() {
+            for table in tables.iter() {
+                let value = match table {
+                    Table::Pairs(values) => values.get(&(one, other)).cloned(),
+                    Table::Classes {
+                        coverage,
+                        first,
+                        second,
+                        values,
+                    } => coverage.contains(&one).then(|| {
+                        let one = first.get(&one).cloned().unwrap_or(0) as usize;
+                        let other = second.get(&other).cloned().unwrap_or(0) as usize;
+                        values
+                            .get(one)
+                            .and_then(|values| values.get(other))
+                            .cloned()
+                            .unwrap_or(0)
+                    }),
+                };
+                if let Some(value) = value {
+                    sum = sum.saturating_add(value);
+                    break;
+                }
+            }
+        }
+        sum
+    }
+
+    /// Return the pairs of the given glyphs with nonzero kerning.
+    ///
+    /// The pairs are enumerated from the tables rather than tried one by one,
+    /// and class-based tables are visited once per class of the second glyph.
+    pub fn pairs(&self, glyph_ids: &[GlyphID]) -> HashMap<(GlyphID, GlyphID), i16> {
+        let set = glyph_ids.iter().cloned().collect::<HashSet<_>>();
+        let mut sums = HashMap::<(GlyphID, GlyphID), i16>::default();
+        for tables in self.lookups.iter() {
+            // The first table covering a pair is the one applied, and a table
+            // of classes covers all pairs starting with a glyph it covers.
+            let mut firsts = HashSet::<GlyphID>::new();
+            let mut seen = HashSet::new();
+            for table in tables.iter() {
+                let mut add = |key: (GlyphID, GlyphID), value: i16| {
+                    if seen.insert(key) && value != 0 {
+                        let sum = sums.entry(key).or_default();
+                        *sum = sum.saturating_add(value);
+                    }
+                };
+                match table {
+                    Table::Pairs(values) => {
+                        for (&(one, other), &value) in values.iter() {
+                            if set.contains(&one) && set.contains(&other) && !firsts.contains(&one)
+                            {
+                                add((one, other), value);
+                            }
+                        }
+                    }
+                    Table::Classes {
+                        coverage,
+                        first,
+                        second,
+                        values,
+                    } => {
+                        let mut groups = HashMap::<u16, Vec<GlyphID>>::default();
+                        for &glyph_id in set.iter() {
+                            let class = second.get(&glyph_id).cloned().unwrap_or(0);
+                            groups.entry(class).or_default().push(glyph_id);
+                        }
+                        for &one in set.iter() {
+                            if !coverage.contains(&one) || firsts.contains(&one) {
+                                continue;
+                            }
+                            let class = first.get(&one).cloned().unwrap_or(0) as usize;
+                            let values = match values.get(class) {
+                                Some(values) => values,
+                                _ => continue,
+                            };
+                            for (&class, others) in groups.iter() {
+                                let value = values.get(class as usize).cloned().unwrap_or(0);
+                                if value != 0 {
+                                    for &other in others.iter() {
+                                        add((one, other), value);
+                                    }
+                                }
+                            }
+                        }
+                        firsts.extend(set.iter().filter(|glyph_id| coverage.contains(glyph_id)));
+                    }
+                }
+            }
+        }
+        sums.retain(|_, value| *value != 0);
+        sums
+    }
+}
+
+fn pairs(table: &PairAdjustment) -> Table {
+    match table {
+        PairAdjustment::Format1(table) => {
+            let mut values = HashMap::default();
+            for (glyph_id, index) in layout::uncover(&table.coverage) {
+                if let Some(records) = table.records.get(index) {
+                    for record in records.records.iter() {
+                        let value = record
+                            .value1
+                            .as_ref()
+                            .and_then(|value| value.x_advance)
+                            .unwrap_or(0);
+                        values.entry((glyph_id, record.glyph2_id)).or_insert(value);
+                    }
+                }
+            }
+            Table::Pairs(values)
+        }
+        PairAdjustment::Format2(table) => Table::Classes {
+            coverage: layout::uncover(&table.coverage)
+                .into_iter()
+                .map(|(glyph_id, _)| glyph_id)
+                .collect(),
+            first: classes(&table.class1),
+            second: classes(&table.class2),
+            values: table
+                .records
+                .iter()
+                .map(|records| {
+                    records
+                        .records
+                        .iter()
+                        .map(|record| {
+                            record
+                                .value1
+                                .as_ref()
+                                .and_then(|value| value.x_advance)
+                                .unwrap_or(0)
+                        })
+                        .collect()
+                })
+                .collect(),
+        },
+    }
+}
+
+fn classes(value: &Class) -> HashMap<GlyphID, u16> {
+    let mut values = HashMap::default();
+    match value {
+        Class::Format1(value) => {
+            for (i, &index) in value.indices.iter().enumerate() {
+                values.insert(value.start_glyph_id + i as GlyphID, index);
+            }
+        }
+        Class::Format2(value) => {
+            for record in value.records.iter() {
+                for glyph_id in record.start_glyph_id..=record.end_glyph_id {
+                    values.insert(glyph_id, record.index);
+                }
+            }
+        }
+    }
+    values
+}
+
+/// Return the horizontal subtables of format 0 of a legacy kerning table as
+/// pairs of their coverage and kerning pairs.
+pub(crate) fn legacy(data: &[u8]) -> Result<Vec<(u16, Pairs)>> {
+    let get = |position: usize| -> Result<u16> {
+        match data.get(position..(position + 2)) {
+            Some(value) => Ok(u16::from_be_bytes([value[0], value[1]])),
+            _ => raise!("found a malformed kerning table"),
+        }
+    };
+    let mut subtables = vec![];
+    if get(0)? != 0 {
+        return Ok(subtables);
+    }
+    let mut position = 4;
+    for _ in 0..get(2)? {
+        let size = get(position + 2)? as usize;
+        let coverage = get(position + 4)?;
+        if coverage >> 8 == 0 && coverage & 0x0007 == 0x0001 {
+            let mut pairs = vec![];
+            for i in 0..get(position + 6)? as usize {
+                let start = position + 14 + 6 * i;
+                pairs.push((get(start)?, get(start + 2)?, get(start + 4)? as i16));
+            }
+            subtables.push((coverage, pairs));
+        }
+        position += size;
+    }
+    Ok(subtables)
+}
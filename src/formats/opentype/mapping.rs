@@ -26,6 +26,13 @@ impl Forward {
     pub fn get(&self, character: char) -> Option<GlyphID> {
         self.0.get(&(character as u32)).copied()
     }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (u32, GlyphID)> + '_ {
+        self.0
+            .iter()
+            .map(|(&character, &glyph_id)| (character, glyph_id))
+    }
 }
 
 impl Reverse {
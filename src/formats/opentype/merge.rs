@@ -0,0 +1,341 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Cursor, Result};
+use std::ops::DerefMut;
+
+use opentype::layout::Directory;
+use opentype::tables::glyph_positioning;
+use opentype::tables::glyph_substitution;
+use opentype::truetype::{GlyphID, Tag};
+
+use crate::formats::opentype::writing::layout::{self, Definition, Shift};
+use crate::formats::opentype::writing::{self, outline};
+use crate::formats::opentype::{metrics, tables, Font};
+use crate::Number;
+
+const RETAINED: [&[u8; 4]; 5] = [b"cvt ", b"fpgm", b"gasp", b"name", b"prep"];
+
+struct Part {
+    glyphs: Vec<Vec<u8>>,
+    advances: Vec<(u16, i16)>,
+    mapping: Vec<(u32, GlyphID)>,
+    substitution: Option<Directory<glyph_substitution::Type>>,
+    positioning: Option<Directory<glyph_positioning::Type>>,
+    definition: Option<Definition>,
+    maximum_profile: Vec<u8>,
+    scale: f32,
+}
+
+/// Merge fonts.
+///
+/// Characters mapped by several fonts are taken from the earliest font mapping
+/// them.
+#[inline]
+pub fn merge<T>(fonts: &[Font<T>]) -> Result<Font<Cursor<Vec<u8>>>>
+where
+    T: crate::Read,
+{
+    merge_with(fonts, |_, indices| indices[0])
+}
+
+/// Merge fonts choosing among fonts mapping the same character.
+///
+/// Glyphs are concatenated in the order of the fonts and scaled to the
+/// granularity of the first font. The font-wide tables and hinting programs are
+/// taken from the first font, and hinting instructions of glyphs from other
+/// fonts are left out. The function given receives a character and the indices
+/// of the fonts mapping it and returns one of the indices. Only fonts with
+/// glyph data are supported.
+pub fn merge_with<T, F>(fonts: &[Font<T>], choose: F) -> Result<Font<Cursor<Vec<u8>>>>
+where
+    T: crate::Read,
+    F: Fn(char, &[usize]) -> usize,
+{
+    let font = match fonts.first() {
+        Some(font) => font,
+        _ => raise!("found no fonts to merge"),
+    };
+    let granularity = metrics::read(&mut font.cache.borrow_mut())?.granularity;
+    if granularity <= 0.0 {
+        raise!("found a malformed font header");
+    }
+    let mut parts = Vec::with_capacity(fonts.len());
+    let mut base = 0;
+    for (i, font) in fonts.iter().enumerate() {
+        let part = read(font, granularity, i == 0, base)?;
+        base += part.glyphs.len();
+        parts.push(part);
+    }
+
+    let mut cache = font.cache.borrow_mut();
+    let mut font_header = *cache.font_header()?.borrow();
+    let version = cache.backend.offsets.header.version;
+    let records = cache.backend.offsets.records.clone();
+    let mut tape = cache.tape.borrow_mut();
+    let tape = tape.deref_mut();
+
+    let mut glyphs = vec![];
+    let mut advances = vec![];
+    let mut glyph_indices = Vec::with_capacity(parts.len());
+    let mut candidates = BTreeMap::<u32, Vec<(usize, GlyphID)>>::new();
+    for (i, part) in parts.iter_mut().enumerate() {
+        let base = glyphs.len();
+        if base + part.glyphs.len() > 0xFFFF {
+            raise!("found too many glyphs to merge");
+        }
+        for mut value in std::mem::take(&mut part.glyphs) {
+            for (position, glyph_id) in writing::components(&value)? {
+                let glyph_id = (base + glyph_id as usize) as GlyphID;
+                value[position..(position + 2)].copy_from_slice(&glyph_id.to_be_bytes());
+            }
+            glyphs.push(value);
+        }
+        advances.extend(part.advances.iter().cloned());
+        glyph_indices.push(
+            (0..(glyphs.len() - base))
+                .map(|glyph_id| (glyph_id as GlyphID, (base + glyph_id) as GlyphID))
+                .collect::<HashMap<_, _>>(),
+        );
+        for &(character, glyph_id) in part.mapping.iter() {
+            candidates
+                .entry(character)
+                .or_default()
+                .push((i, (base + glyph_id as usize) as GlyphID));
+        }
+    }
+    let mut character_mapping = BTreeMap::new();
+    for (character, values) in candidates.into_iter() {
+        let glyph_id = match (values.len(), char::from_u32(character)) {
+            (1, _) | (_, None) => values[0].1,
+            (_, Some(value)) => {
+                let indices = values.iter().map(|(i, _)| *i).collect::<Vec<_>>();
+                let index = choose(value, &indices);
+                match values.iter().find(|(i, _)| *i == index) {
+                    Some((_, glyph_id)) => *glyph_id,
+                    _ => raise!("found no font with index {index} mapping {value:?}"),
+                }
+            }
+        };
+        character_mapping.insert(character, glyph_id);
+    }
+
+    let mut tables = vec![];
+    for tag in RETAINED.iter() {
+        if let Some(data) = tables::take(tape, &records, tag)? {
+            tables.push((Tag(**tag), data));
+        }
+    }
+    if let Some(mut data) = tables::take(tape, &records, b"OS/2")? {
+        writing::windows_metrics(&mut data, &character_mapping);
+        tables.push((Tag(*b"OS/2"), data));
+    }
+
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (i16::MAX, i16::MAX, i16::MIN, i16::MIN);
+    let (mut min_left, mut min_right, mut max_extent) = (i16::MAX, i16::MAX, i16::MIN);
+    for (data, &(advance_width, left_side_bearing)) in glyphs.iter().zip(advances.iter()) {
+        if data.len() < 10 {
+            continue;
+        }
+        let value = |i: usize| i16::from_be_bytes([data[i], data[i + 1]]);
+        let (left, bottom, right, top) = (value(2), value(4), value(6), value(8));
+        min_x = min_x.min(left);
+        min_y = min_y.min(bottom);
+        max_x = max_x.max(right);
+        max_y = max_y.max(top);
+        let extent = left_side_bearing as i32 + (right as i32 - left as i32);
+        min_left = min_left.min(left_side_bearing);
+        min_right = min_right.min((advance_width as i32 - extent) as i16);
+        max_extent = max_extent.max(extent as i16);
+    }
+    if min_x <= max_x {
+        font_header.min_x = min_x;
+        font_header.min_y = min_y;
+        font_header.max_x = max_x;
+        font_header.max_y = max_y;
+    }
+
+    let (data, mapping, format) = writing::locate(&glyphs);
+    font_header.glyph_mapping_format = format;
+    tables.push((Tag(*b"glyf"), data));
+    tables.push((Tag(*b"loca"), mapping));
+    let mut data = vec![];
+    crate::Write::give(&mut data, &font_header)?;
+    tables.push((Tag(*b"head"), data));
+
+    let (data, count) = writing::horizontal_metrics(&advances);
+    tables.push((Tag(*b"hmtx"), data));
+    match tables::take(tape, &records, b"hhea")? {
+        Some(mut data) if data.len() >= 36 => {
+            let advance_width = advances.iter().map(|value| value.0).max().unwrap_or(0);
+            data[10..12].copy_from_slice(&advance_width.to_be_bytes());
+            if min_x <= max_x {
+                data[12..14].copy_from_slice(&min_left.to_be_bytes());
+                data[14..16].copy_from_slice(&min_right.to_be_bytes());
+                data[16..18].copy_from_slice(&max_extent.to_be_bytes());
+            }
+            data[34..36].copy_from_slice(&count.to_be_bytes());
+            tables.push((Tag(*b"hhea"), data));
+        }
+        _ => raise!("cannot find the horizontal header"),
+    }
+    let mut data = parts[0].maximum_profile.clone();
+    if data.len() < 6 {
+        raise!("found a malformed maximum profile");
+    }
+    data[4..6].copy_from_slice(&(glyphs.len() as u16).to_be_bytes());
+    if data.len() >= 32 {
+        for part in parts.iter().skip(1) {
+            for i in (6..32).step_by(2) {
+                let value = |data: &[u8]| match data.get(i..(i + 2)) {
+                    Some(value) => u16::from_be_bytes([value[0], value[1]]),
+                    _ => 0,
+                };
+                let value = value(&data).max(value(&part.maximum_profile));
+                data[i..(i + 2)].copy_from_slice(&value.to_be_bytes());
+            }
+        }
+    }
+    tables.push((Tag(*b"maxp"), data));
+    if let Some(mut data) = tables::take(tape, &records, b"post")? {
+        if data.len() < 32 {
+            raise!("found a malformed PostScript table");
+        }
+        data.truncate(32);
+        data[..4].copy_from_slice(&[0, 3, 0, 0]);
+        tables.push((Tag(*b"post"), data));
+    }
+    tables.push((
+        Tag(*b"cmap"),
+        writing::character_mapping(&character_mapping)?,
+    ));
+
+    let mut definition = Definition::default();
+    let mut shifts = Vec::with_capacity(parts.len());
+    for part in parts.iter_mut() {
+        let (mark_classes, mark_sets) =
+            definition.extend(part.definition.take().unwrap_or_default());
+        shifts.push(Shift {
+            scale: part.scale,
+            mark_classes,
+            mark_sets,
+        });
+    }
+    let mut layout = false;
+    let sources = parts
+        .iter()
+        .zip(glyph_indices.iter())
+        .zip(shifts.iter())
+        .filter_map(|((part, glyphs), shift)| Some((part.substitution.as_ref()?, glyphs, *shift)))
+        .collect::<Vec<_>>();
+    if let Some(data) = layout::merge(&sources)? {
+        tables.push((Tag(*b"GSUB"), data));
+        layout = true;
+    }
+    let sources = parts
+        .iter()
+        .zip(glyph_indices.iter())
+        .zip(shifts.iter())
+        .filter_map(|((part, glyphs), shift)| Some((part.positioning.as_ref()?, glyphs, *shift)))
+        .collect::<Vec<_>>();
+    if let Some(data) = layout::merge(&sources)? {
+        tables.push((Tag(*b"GPOS"), data));
+        layout = true;
+    }
+    if layout {
+        tables.push((Tag(*b"GDEF"), definition.encode()?));
+    }
+
+    let data = writing::assemble(version, tables)?;
+    match crate::formats::opentype::read(Cursor::new(data))?.pop() {
+        Some(font) => Ok(font),
+        _ => raise!("found no font after merging"),
+    }
+}
+
+fn read<T>(font: &Font<T>, granularity: Number, first: bool, base: usize) -> Result<Part>
+where
+    T: crate::Read,
+{
+    if !font.index.0 {
+        raise!("merging fonts without glyph data is not supported yet");
+    }
+    let mut cache = font.cache.borrow_mut();
+    let scale = granularity / metrics::read(&mut cache)?.granularity;
+    let mapping = cache.forward_mapping()?.iter().collect::<Vec<_>>();
+    let metrics = cache.horizontal_metrics()?.clone();
+    let glyph_count = cache.maximum_profile()?.borrow().glyph_count();
+    let offsets = writing::offsets(&cache.glyph_mapping()?.borrow());
+    let substitution = cache
+        .try_glyph_substitution()?
+        .map(|table| table.borrow().clone());
+    let positioning = cache
+        .try_glyph_positioning()?
+        .map(|table| table.borrow().clone());
+    let records = cache.backend.offsets.records.clone();
+    let mut tape = cache.tape.borrow_mut();
+    let tape = tape.deref_mut();
+
+    let data = match tables::take(tape, &records, b"glyf")? {
+        Some(data) => data,
+        _ => raise!("cannot find the glyph data"),
+    };
+    let mut glyphs = Vec::with_capacity(glyph_count);
+    let mut advances = Vec::with_capacity(glyph_count);
+    for glyph_id in 0..glyph_count {
+        let value = match (offsets.get(glyph_id), offsets.get(glyph_id + 1)) {
+            (Some(&start), Some(&end)) if start <= end && end <= data.len() => &data[start..end],
+            _ => raise!("found no data for glyph {}", glyph_id),
+        };
+        let value = match first {
+            true => value.to_vec(),
+            _ => outline::transform(value, scale)?,
+        };
+        let (advance_width, left_side_bearing) = metrics.borrow().get(glyph_id as GlyphID);
+        advances.push((
+            (advance_width as f32 * scale).round() as u16,
+            outline::left(&value).unwrap_or((left_side_bearing as f32 * scale).round() as i16),
+        ));
+        glyphs.push(value);
+    }
+
+    let position = |tag: &[u8; 4]| {
+        records
+            .iter()
+            .find(|record| record.tag == tag)
+            .map(|record| record.offset as u64)
+    };
+    let substitution = match (substitution, position(b"GSUB")) {
+        (Some(mut directory), Some(position)) => {
+            layout::resolve(tape, position, &mut directory)?;
+            Some(directory)
+        }
+        _ => None,
+    };
+    let positioning = match (positioning, position(b"GPOS")) {
+        (Some(mut directory), Some(position)) => {
+            layout::resolve(tape, position, &mut directory)?;
+            Some(directory)
+        }
+        _ => None,
+    };
+    let glyph_indices = (0..glyph_count)
+        .map(|glyph_id| (glyph_id as GlyphID, (base + glyph_id) as GlyphID))
+        .collect::<HashMap<_, _>>();
+    let definition = match position(b"GDEF") {
+        Some(position) => Some(Definition::read(tape, position, &glyph_indices)?),
+        _ => None,
+    };
+    let maximum_profile = match tables::take(tape, &records, b"maxp")? {
+        Some(data) => data,
+        _ => raise!("cannot find the maximum profile"),
+    };
+    Ok(Part {
+        glyphs,
+        advances,
+        mapping,
+        substitution,
+        positioning,
+        definition,
+        maximum_profile,
+        scale,
+    })
+}
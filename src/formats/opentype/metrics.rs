@@ -73,3 +73,15 @@ pub fn read<T: crate::Read>(cache: &mut Cache<T>) -> Result<crate::Metrics> {
         line_gap,
     })
 }
+
+pub fn bounding_box<T: crate::Read>(
+    cache: &mut Cache<T>,
+) -> Result<(Number, Number, Number, Number)> {
+    let font_header = cache.font_header()?.borrow();
+    Ok((
+        font_header.min_x.into(),
+        font_header.min_y.into(),
+        font_header.max_x.into(),
+        font_header.max_y.into(),
+    ))
+}
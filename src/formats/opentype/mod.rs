@@ -1,7 +1,9 @@
 //! The OpenType font format.
 
 pub mod axes;
+pub mod bitmaps;
 pub mod characters;
+pub mod devices;
 pub mod features;
 pub mod names;
 pub mod palettes;
@@ -9,20 +11,34 @@ pub mod tables;
 pub mod timestamps;
 
 pub(crate) mod cache;
+pub(crate) mod hinting;
+pub(crate) mod kerning;
 pub(crate) mod metrics;
 
+mod builder;
+mod charstring;
+mod collection;
 mod font;
 mod mapping;
+mod merge;
 mod postscript;
+mod subset;
 mod truetype;
+mod writing;
 
-pub use self::font::{write, Disposition, Font};
+pub use self::builder::{FontBuilder, Outlines};
+pub use self::collection::write_collection;
+pub use self::font::{write, write_with, Disposition, Font, Insertion};
+pub use self::merge::{merge, merge_with};
+pub use self::subset::{subset, subset_with, Subroutines};
 
 use std::cell::RefCell;
 use std::io::Result;
 use std::ops::DerefMut;
 use std::rc::Rc;
 
+use opentype::truetype::Tag;
+
 /// Read fonts.
 pub fn read<T>(tape: T) -> Result<Vec<Font<T>>>
 where
@@ -30,8 +46,16 @@ where
 {
     let tape = Rc::new(RefCell::new(tape));
     let mut fonts = vec![];
-    let file = opentype::File::read(tape.borrow_mut().deref_mut())?;
-    for font in file.fonts.into_iter() {
+    let backends = {
+        let mut tape = tape.borrow_mut();
+        let tape = tape.deref_mut();
+        if tape.peek::<Tag>()? == b"ttcf" {
+            self::collection::read(tape)?
+        } else {
+            opentype::File::read(tape)?.fonts
+        }
+    };
+    for font in backends.into_iter() {
         fonts.extend(self::font::read(tape.clone(), font)?);
     }
     Ok(fonts)
@@ -1,12 +1,19 @@
 use std::io::Result;
 
-use opentype::postscript::compact1::FontSet;
+use opentype::postscript::compact1::{CharacterSet, Encoding, FontSet};
 use opentype::postscript::type2::Program;
+use opentype::truetype::GlyphID;
 
-use crate::formats::opentype::mapping::Forward as Mapping;
+use crate::formats::opentype::charstring;
+use crate::formats::opentype::font::Drawing;
 use crate::formats::opentype::metrics::Metrics;
-use crate::glyph::{Builder, Glyph};
+use crate::glyph::geometry::Curve;
+use crate::glyph::{Alignment, Component, Hints, Mask, Pen};
 use crate::offset::Offset;
+use crate::Number;
+
+/// A character string with the global and local subroutines.
+type Code<'l> = (&'l [u8], &'l [Vec<u8>], &'l [Vec<u8>]);
 
 macro_rules! expect(
     ($condition:expr) => (
@@ -18,96 +25,302 @@ macro_rules! expect(
 
 pub(super) fn draw(
     font_set: &FontSet,
-    mapping: &Mapping,
     metrics: &Metrics,
     id: usize,
-    character: char,
-) -> Result<Option<Glyph>> {
+    glyph_id: GlyphID,
+    flatten: bool,
+    pen: &mut dyn Pen,
+) -> Result<Drawing> {
+    let mut bounds = (Offset::undefined(), Offset::undefined());
+    let mut components = vec![];
+    let mut hints = Hints::default();
+    let offset = Offset::default();
+    if !draw_glyph(
+        font_set,
+        id,
+        glyph_id,
+        offset,
+        pen,
+        &mut bounds,
+        Some(&mut hints),
+    )? {
+        if let Some((x, y, base, accent)) = accent(font_set, id, glyph_id)? {
+            let offset = Offset(x as Number, y as Number);
+            for (code, offset) in [(base, Offset::default()), (accent, offset)] {
+                let glyph_id = standard(font_set, id, code)?;
+                let pen = if flatten {
+                    &mut *pen
+                } else {
+                    &mut Void as &mut dyn Pen
+                };
+                draw_glyph(font_set, id, glyph_id, offset, pen, &mut bounds, None)?;
+                components.push(Component {
+                    glyph_id,
+                    offset,
+                    scale: (1.0, 0.0, 0.0, 1.0),
+                });
+            }
+        }
+    }
+    let (min, max) = bounds;
+    let bounding_box = match min.0.is_nan() {
+        true => None,
+        _ => Some((min.0, min.1, max.0, max.1)),
+    };
+    Ok(Drawing {
+        horizontal_metrics: metrics.get(glyph_id),
+        bounding_box,
+        components,
+        hints: Some(hints),
+    })
+}
+
+pub(super) fn alignment(font_set: &FontSet, id: usize) -> Result<Alignment> {
     use opentype::postscript::compact1::font_set::Record;
-    use opentype::postscript::type2::Operator::*;
+    use opentype::postscript::compact1::{Number as Operand, Operator};
 
-    let glyph_id = match mapping.get(character) {
-        Some(glyph_id) => glyph_id,
-        _ => return Ok(None),
+    let operations = match &font_set.records[id] {
+        Record::CharacterNameKeyed(ref record) => &record.operations,
+        _ => raise!("found a character-ID-keyed font, which is not supported yet"),
+    };
+    let number = |value: &Operand| match *value {
+        Operand::Integer(value) => value as Number,
+        Operand::Real(value) => value,
     };
-    let mut program = match font_set.character_strings[id].get(glyph_id as usize) {
-        Some(character_string) => Program::new(
-            character_string,
-            &font_set.subroutines,
-            match &font_set.records[id] {
-                Record::CharacterNameKeyed(ref record) => &record.subroutines,
-                _ => raise!("found a character-ID-keyed font, which is not supported yet"),
-            },
-        ),
-        _ => raise!(
-            "found no char string for character {} with glyph {}",
-            character,
-            glyph_id,
-        ),
+    let zones = |operator: Operator| {
+        let mut last = 0.0;
+        let values = operations
+            .get(operator)
+            .unwrap_or_default()
+            .iter()
+            .map(|value| {
+                last += number(value);
+                last
+            })
+            .collect::<Vec<_>>();
+        values
+            .chunks_exact(2)
+            .map(|pair| (pair[0], pair[1]))
+            .collect()
     };
-    let mut builder = Builder::default();
-    let mut position = Offset::default();
-    let (mut max, mut min) = (Offset::undefined(), Offset::undefined());
+    let width = |operator: Operator| operations.get(operator)?.first().map(number);
+    Ok(Alignment {
+        blue_values: zones(Operator::BlueValues),
+        other_blues: zones(Operator::OtherBlues),
+        standard_horizontal_width: width(Operator::StdHW),
+        standard_vertical_width: width(Operator::StdVW),
+    })
+}
+
+/// Return the glyphs of the base and accent characters if a glyph is composed
+/// with the deprecated form of the end operator.
+pub(super) fn components(font_set: &FontSet, id: usize, glyph_id: GlyphID) -> Result<Vec<GlyphID>> {
+    match accent(font_set, id, glyph_id)? {
+        Some((_, _, base, accent)) => Ok(vec![
+            standard(font_set, id, base)?,
+            standard(font_set, id, accent)?,
+        ]),
+        _ => Ok(vec![]),
+    }
+}
+
+struct Void;
+
+impl Pen for Void {
+    fn move_to(&mut self, _: Offset) {}
+
+    fn line_to(&mut self, _: Offset) {}
+
+    fn quad_to(&mut self, _: Offset, _: Offset) {}
+
+    fn curve_to(&mut self, _: Offset, _: Offset, _: Offset) {}
+
+    fn close(&mut self) {}
+}
+
+fn accent(font_set: &FontSet, id: usize, glyph_id: GlyphID) -> Result<Option<(i32, i32, u8, u8)>> {
+    let (code, global, local) = code(font_set, id, glyph_id)?;
+    charstring::accent(code, &slices(global), &slices(local))
+}
+
+fn hint(hints: &mut Hints, mask: charstring::Mask, segment: usize) {
+    stem(&mut hints.vertical, &mask.stems);
+    let active = (0..(hints.horizontal.len() + hints.vertical.len()))
+        .map(|i| {
+            mask.data
+                .get(i / 8)
+                .is_some_and(|byte| byte & (0x80 >> (i % 8)) != 0)
+        })
+        .collect();
+    if mask.counter {
+        hints.counters.push(active);
+    } else {
+        match hints.masks.last_mut() {
+            Some(mask) if mask.segment == segment => mask.active = active,
+            _ => hints.masks.push(Mask { segment, active }),
+        }
+    }
+}
+
+// The first edge of each operator is relative to zero and each other edge to
+// the previous one.
+fn stem(stems: &mut Vec<(Number, Number)>, values: &[f32]) {
+    let mut last = 0.0;
+    for pair in values.chunks_exact(2) {
+        let start = last + pair[0] as Number;
+        last = start + pair[1] as Number;
+        stems.push((start, last));
+    }
+}
+
+// The glyph of a standard code is found by the name the code stands for in the
+// standard encoding.
+fn find(character_set: &CharacterSet, code: u8) -> Option<GlyphID> {
+    let string_id = match Encoding::Standard.get(code as GlyphID) {
+        Some(0) | None => return None,
+        Some(value) => value,
+    };
+    macro_rules! search(
+        ($ranges:expr) => ({
+            let mut glyph_id = 1;
+            for range in $ranges.iter() {
+                let first = range.first_string_id;
+                let count = range.left_count as GlyphID;
+                if string_id >= first && string_id - first <= count {
+                    return Some(glyph_id + string_id - first);
+                }
+                glyph_id += count + 1;
+            }
+            None
+        });
+    );
+    match character_set {
+        CharacterSet::ISOAdobe => (string_id <= 228).then_some(string_id),
+        CharacterSet::Format0(ref value) => value
+            .glyphs
+            .iter()
+            .position(|&other| other == string_id)
+            .map(|i| i as GlyphID + 1),
+        CharacterSet::Format1(ref value) => search!(value.ranges),
+        CharacterSet::Format2(ref value) => search!(value.ranges),
+        _ => None,
+    }
+}
+
+fn standard(font_set: &FontSet, id: usize, code: u8) -> Result<GlyphID> {
+    match find(&font_set.character_sets[id], code) {
+        Some(glyph_id) => Ok(glyph_id),
+        _ => raise!("found no glyph for standard code {}", code),
+    }
+}
+
+fn code(font_set: &FontSet, id: usize, glyph_id: GlyphID) -> Result<Code<'_>> {
+    use opentype::postscript::compact1::font_set::Record;
+
+    let code = match font_set.character_strings[id].get(glyph_id as usize) {
+        Some(character_string) => character_string,
+        _ => raise!("found no char string for glyph {}", glyph_id),
+    };
+    let local = match &font_set.records[id] {
+        Record::CharacterNameKeyed(ref record) => &record.subroutines,
+        _ => raise!("found a character-ID-keyed font, which is not supported yet"),
+    };
+    Ok((code, &font_set.subroutines, local))
+}
+
+fn draw_glyph(
+    font_set: &FontSet,
+    id: usize,
+    glyph_id: GlyphID,
+    offset: Offset,
+    pen: &mut dyn Pen,
+    (min, max): &mut (Offset, Offset),
+    mut hints: Option<&mut Hints>,
+) -> Result<bool> {
+    use opentype::postscript::type2::Operator::*;
+
+    let (code, global, local) = code(font_set, id, glyph_id)?;
+    let mut masks = None;
+    let mut program = Program::new(code, global, local);
+    let mut drawn = false;
+    let mut segments = 0;
+    let mut open = false;
+    let mut position = offset;
     macro_rules! build(
-        ($function:ident($(($x:expr, $y:expr)),+ $(,)?)) => (
-            builder.$function($(($x, $y)),+);
-            build!(@track $function($(($x, $y)),+));
-        );
-        (@track move_relative($(($x:expr, $y:expr)),+)) => (
-            $(position += ($x, $y);)+
-        );
-        (@track $function:ident($(($x:expr, $y:expr)),+)) => (
+        (move_to(($x:expr, $y:expr))) => ({
+            if open {
+                pen.close();
+            }
+            position += ($x, $y);
+            pen.move_to(position);
+            open = true;
+            drawn = true;
+        });
+        (line_to(($x:expr, $y:expr))) => ({
             build!(@update);
-            $(position += ($x, $y);)+
+            position += ($x, $y);
+            pen.line_to(position);
+            segments += 1;
             build!(@update);
-        );
+        });
+        (curve_to(($ax:expr, $ay:expr), ($bx:expr, $by:expr), ($cx:expr, $cy:expr) $(,)?)) => ({
+            build!(@update);
+            let start = position;
+            let a = position + ($ax, $ay);
+            let b = a + ($bx, $by);
+            position = b + ($cx, $cy);
+            pen.curve_to(a, b, position);
+            segments += 1;
+            for point in Curve::Cubic(start, a, b, position).extrema() {
+                build!(@update point);
+            }
+        });
         (@update) => (
-            max = max.max(position);
-            min = min.min(position);
+            build!(@update position)
+        );
+        (@update $point:expr) => (
+            *max = max.max($point);
+            *min = min.min($point);
         );
     );
     let mut clear = false;
     while let Some((operator, operands)) = program.next()? {
         let count = operands.len();
-        match operator {
-            RMoveTo | HMoveTo | VMoveTo => builder.flush(),
-            _ => {}
-        }
         match operator {
             RMoveTo => {
                 expect!(count == 2 || !clear && count == 3);
-                build!(move_relative((operands[0], operands[1])));
+                build!(move_to((operands[0], operands[1])));
             }
             HMoveTo => {
                 expect!(count == 1 || !clear && count == 2);
-                build!(move_relative((operands[0], 0.0)));
+                build!(move_to((operands[0], 0.0)));
             }
             VMoveTo => {
                 expect!(count == 1 || !clear && count == 2);
-                build!(move_relative((0.0, operands[0])));
+                build!(move_to((0.0, operands[0])));
             }
             RLineTo => {
                 expect!(count % 2 == 0);
                 for i in 0..(count / 2) {
                     let j = 2 * i;
-                    build!(add_linear((operands[j], operands[j + 1])));
+                    build!(line_to((operands[j], operands[j + 1])));
                 }
             }
             HLineTo => {
                 for (i, operand) in operands.iter().enumerate().take(count) {
                     if i % 2 == 0 {
-                        build!(add_linear((*operand, 0.0)));
+                        build!(line_to((*operand, 0.0)));
                     } else {
-                        build!(add_linear((0.0, *operand)));
+                        build!(line_to((0.0, *operand)));
                     }
                 }
             }
             VLineTo => {
                 for (i, operand) in operands.iter().enumerate().take(count) {
                     if i % 2 == 1 {
-                        build!(add_linear((*operand, 0.0)));
+                        build!(line_to((*operand, 0.0)));
                     } else {
-                        build!(add_linear((0.0, *operand)));
+                        build!(line_to((0.0, *operand)));
                     }
                 }
             }
@@ -115,7 +328,7 @@ pub(super) fn draw(
                 expect!(count % 6 == 0);
                 for i in 0..(count / 6) {
                     let j = 6 * i;
-                    build!(add_cubic(
+                    build!(curve_to(
                         (operands[j], operands[j + 1]),
                         (operands[j + 2], operands[j + 3]),
                         (operands[j + 4], operands[j + 5]),
@@ -132,7 +345,7 @@ pub(super) fn draw(
                 for i in 0..((count - offset) / 4) {
                     let j = offset + 4 * i;
                     let first = if i == 0 { first } else { 0.0 };
-                    build!(add_cubic(
+                    build!(curve_to(
                         (operands[j], first),
                         (operands[j + 1], operands[j + 2]),
                         (operands[j + 3], 0.0),
@@ -150,13 +363,13 @@ pub(super) fn draw(
                     let j = 4 * i;
                     let last = if i + 1 == steps { last } else { 0.0 };
                     if i % 2 == 0 {
-                        build!(add_cubic(
+                        build!(curve_to(
                             (operands[j], 0.0),
                             (operands[j + 1], operands[j + 2]),
                             (last, operands[j + 3]),
                         ));
                     } else {
-                        build!(add_cubic(
+                        build!(curve_to(
                             (0.0, operands[j]),
                             (operands[j + 1], operands[j + 2]),
                             (operands[j + 3], last),
@@ -175,13 +388,13 @@ pub(super) fn draw(
                     let j = 4 * i;
                     let last = if i + 1 == steps { last } else { 0.0 };
                     if i % 2 == 1 {
-                        build!(add_cubic(
+                        build!(curve_to(
                             (operands[j], 0.0),
                             (operands[j + 1], operands[j + 2]),
                             (last, operands[j + 3]),
                         ));
                     } else {
-                        build!(add_cubic(
+                        build!(curve_to(
                             (0.0, operands[j]),
                             (operands[j + 1], operands[j + 2]),
                             (operands[j + 3], last),
@@ -199,7 +412,7 @@ pub(super) fn draw(
                 for i in 0..((count - offset) / 4) {
                     let j = offset + 4 * i;
                     let first = if i == 0 { first } else { 0.0 };
-                    build!(add_cubic(
+                    build!(curve_to(
                         (first, operands[j]),
                         (operands[j + 1], operands[j + 2]),
                         (0.0, operands[j + 3]),
@@ -210,29 +423,52 @@ pub(super) fn draw(
                 expect!(count >= 2 && (count - 2) % 6 == 0);
                 for i in 0..((count - 2) / 6) {
                     let j = 6 * i;
-                    build!(add_cubic(
+                    build!(curve_to(
                         (operands[j], operands[j + 1]),
                         (operands[j + 2], operands[j + 3]),
                         (operands[j + 4], operands[j + 5]),
                     ));
                 }
                 let j = count - 2;
-                build!(add_linear((operands[j], operands[j + 1])));
+                build!(line_to((operands[j], operands[j + 1])));
             }
             RLineCurve => {
                 expect!(count >= 6 && (count - 6) % 2 == 0);
                 for i in 0..((count - 6) / 2) {
                     let j = 2 * i;
-                    build!(add_linear((operands[j], operands[j + 1])));
+                    build!(line_to((operands[j], operands[j + 1])));
                 }
                 let j = count - 6;
-                build!(add_cubic(
+                build!(curve_to(
                     (operands[j], operands[j + 1]),
                     (operands[j + 2], operands[j + 3]),
                     (operands[j + 4], operands[j + 5]),
                 ));
             }
-            CntrMask | HintMask | HStem | HStemHM | VStem | VStemHM => {}
+            HStem | HStemHM => {
+                if let Some(ref mut hints) = hints {
+                    stem(&mut hints.horizontal, &operands);
+                }
+            }
+            VStem | VStemHM => {
+                if let Some(ref mut hints) = hints {
+                    stem(&mut hints.vertical, &operands);
+                }
+            }
+            CntrMask | HintMask => {
+                if let Some(ref mut hints) = hints {
+                    // The bytes of masks are not exposed by the program, and
+                    // the string is scanned for them when one is reached.
+                    if masks.is_none() {
+                        let values = charstring::masks(code, &slices(global), &slices(local))?;
+                        masks = Some(values.into_iter());
+                    }
+                    match masks.as_mut().and_then(Iterator::next) {
+                        Some(mask) => hint(hints, mask, segments),
+                        _ => raise!("found a malformed glyph"),
+                    }
+                }
+            }
             Flex | Flex1 | HFlex | HFlex1 => {}
             operator => raise!("found an unknown operation with operator {operator:?}"),
         }
@@ -244,8 +480,56 @@ pub(super) fn draw(
             _ => {}
         }
     }
-    builder.flush();
-    builder.set_bounding_box((min.0, min.1, max.0, max.1));
-    builder.set_horizontal_metrics(metrics.get(glyph_id));
-    Ok(Some(builder.into()))
+    if open {
+        pen.close();
+    }
+    Ok(drawn)
+}
+
+fn slices(values: &[Vec<u8>]) -> Vec<&[u8]> {
+    values.iter().map(Vec::as_slice).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use opentype::postscript::compact1::character_set::{CharacterSet0, CharacterSet1, Range1};
+    use opentype::postscript::compact1::CharacterSet;
+
+    #[test]
+    fn find() {
+        let character_set = CharacterSet::Format0(CharacterSet0 {
+            format: 0,
+            glyphs: vec![125, 34],
+        });
+        assert_eq!(super::find(&character_set, 65), Some(2));
+        assert_eq!(super::find(&character_set, 194), Some(1));
+        assert_eq!(super::find(&character_set, 66), None);
+
+        let character_set = CharacterSet::Format1(CharacterSet1 {
+            format: 1,
+            ranges: vec![
+                Range1 {
+                    first_string_id: 1,
+                    left_count: 94,
+                },
+                Range1 {
+                    first_string_id: 125,
+                    left_count: 0,
+                },
+            ],
+        });
+        assert_eq!(super::find(&character_set, 65), Some(34));
+        assert_eq!(super::find(&character_set, 194), Some(96));
+        assert_eq!(super::find(&character_set, 0), None);
+
+        assert_eq!(super::find(&CharacterSet::ISOAdobe, 65), Some(34));
+    }
+
+    #[test]
+    fn stem() {
+        let mut stems = vec![];
+        super::stem(&mut stems, &[10.0, 20.0, 5.0, 10.0]);
+        super::stem(&mut stems, &[100.0, 20.0]);
+        assert_eq!(stems, [(10.0, 30.0), (35.0, 45.0), (100.0, 120.0)]);
+    }
 }
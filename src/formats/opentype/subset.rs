@@ -0,0 +1,306 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::Result;
+use std::ops::DerefMut;
+
+use opentype::truetype::{GlyphID, Tag};
+
+use crate::formats::opentype::features::rules::{Glyph, Rule, Table};
+use crate::formats::opentype::features::Feature;
+use crate::formats::opentype::writing::{self, compact, layout};
+use crate::formats::opentype::{postscript, tables, Font};
+
+const RETAINED: [&[u8; 4]; 6] = [b"OS/2", b"cvt ", b"fpgm", b"gasp", b"name", b"prep"];
+
+/// A treatment of subroutines in font sets.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Subroutines {
+    /// Copy the subroutines as is.
+    Retain,
+    /// Expand the subroutines in place.
+    Flatten,
+    /// Expand the subroutines in place and factor repeated sequences out anew.
+    Factor,
+}
+
+/// Subset a font.
+///
+/// The glyphs needed for the given characters are retained, which includes the
+/// components of composite glyphs, the base and accent glyphs of accented
+/// characters in font sets, and the glyphs the given features can substitute
+/// them with. Glyph identifiers are renumbered, and the tables that depend on
+/// them are rewritten, including the legacy kerning table if kerning is among
+/// the features. Other tables that depend on glyphs are left out. Subroutines
+/// in font sets are flattened.
+#[inline]
+pub fn subset<T>(font: &Font<T>, characters: &[char], features: &[Feature]) -> Result<Vec<u8>>
+where
+    T: crate::Read,
+{
+    subset_with(font, characters, features, Subroutines::Flatten)
+}
+
+/// Subset a font treating subroutines in font sets as specified.
+pub fn subset_with<T>(
+    font: &Font<T>,
+    characters: &[char],
+    features: &[Feature],
+    subroutines: Subroutines,
+) -> Result<Vec<u8>>
+where
+    T: crate::Read,
+{
+    let truetype = match font.index {
+        (truetype, 0) => truetype,
+        _ => raise!("subsetting fonts other than the first one of a font set is not supported yet"),
+    };
+    let wanted = |tag: &Tag| features.contains(&Feature::from_tag(tag));
+
+    let mut cache = font.cache.borrow_mut();
+    let forward_mapping = cache.forward_mapping()?.clone();
+    let metrics = cache.horizontal_metrics()?.clone();
+    let mut font_header = *cache.font_header()?.borrow();
+    let substitution = cache
+        .try_glyph_substitution()?
+        .map(|table| table.borrow().clone());
+    let positioning = cache
+        .try_glyph_positioning()?
+        .map(|table| table.borrow().clone());
+    let glyph_mapping = match truetype {
+        true => Some(cache.glyph_mapping()?.borrow().clone()),
+        _ => None,
+    };
+    let font_set = match truetype {
+        true => None,
+        _ => Some(cache.font_set()?.clone()),
+    };
+    let version = cache.backend.offsets.header.version;
+    let records = cache.backend.offsets.records.clone();
+    let mut tape = cache.tape.borrow_mut();
+    let tape = tape.deref_mut();
+    let mut character_mapping = BTreeMap::default();
+    let mut glyphs = BTreeSet::from([0]);
+    for &character in characters.iter() {
+        if let Some(glyph_id) = forward_mapping.get(character) {
+            character_mapping.insert(character as u32, glyph_id);
+            glyphs.insert(glyph_id);
+        }
+    }
+
+    let glyph_data = match truetype {
+        true => match tables::take(tape, &records, b"glyf")? {
+            Some(data) => Some(data),
+            _ => raise!("cannot find the glyph data"),
+        },
+        _ => None,
+    };
+    let offsets = glyph_mapping
+        .as_ref()
+        .map(writing::offsets)
+        .unwrap_or_default();
+
+    let substitution = match substitution {
+        Some(mut directory) => {
+            let position = records
+                .iter()
+                .find(|record| record.tag == b"GSUB")
+                .map(|record| record.offset as u64)
+                .unwrap_or(0);
+            layout::resolve(tape, position, &mut directory)?;
+            Some(directory)
+        }
+        _ => None,
+    };
+    let positioning = match positioning {
+        Some(mut directory) => {
+            let position = records
+                .iter()
+                .find(|record| record.tag == b"GPOS")
+                .map(|record| record.offset as u64)
+                .unwrap_or(0);
+            layout::resolve(tape, position, &mut directory)?;
+            Some(directory)
+        }
+        _ => None,
+    };
+    let rules: BTreeSet<Rule> = match &substitution {
+        Some(directory) => layout::select(directory, wanted)
+            .into_iter()
+            .filter_map(|index| directory.lookups.records.get(index as usize))
+            .flat_map(|record| record.tables.iter())
+            .filter_map(|table| table.extract(directory))
+            .flatten()
+            .collect(),
+        _ => BTreeSet::default(),
+    };
+
+    loop {
+        let count = glyphs.len();
+        if let Some(data) = &glyph_data {
+            let mut pending = glyphs.iter().cloned().collect::<Vec<_>>();
+            while let Some(glyph_id) = pending.pop() {
+                let start = offsets.get(glyph_id as usize).cloned().unwrap_or(0);
+                let end = offsets.get(glyph_id as usize + 1).cloned().unwrap_or(0);
+                if let Some(data) = data.get(start..end) {
+                    for (_, glyph_id) in writing::components(data)? {
+                        if glyphs.insert(glyph_id) {
+                            pending.push(glyph_id);
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(font_set) = &font_set {
+            let font_set = font_set.borrow();
+            let mut pending = glyphs.iter().cloned().collect::<Vec<_>>();
+            while let Some(glyph_id) = pending.pop() {
+                for glyph_id in postscript::components(&font_set, 0, glyph_id)? {
+                    if glyphs.insert(glyph_id) {
+                        pending.push(glyph_id);
+                    }
+                }
+            }
+        }
+        for rule in rules.iter() {
+            match rule {
+                Rule::Simple((inputs, outputs)) => {
+                    if inputs.iter().all(|input| intersect(input, &glyphs)) {
+                        for output in outputs.iter() {
+                            glyphs.extend(expand(output));
+                        }
+                    }
+                }
+                Rule::Alternate((input, outputs)) => {
+                    if glyphs.contains(input) {
+                        glyphs.extend(outputs.iter().cloned());
+                    }
+                }
+            }
+        }
+        if glyphs.len() == count {
+            break;
+        }
+    }
+
+    let glyph_ids = glyphs.into_iter().collect::<Vec<_>>();
+    let glyph_indices = glyph_ids
+        .iter()
+        .enumerate()
+        .map(|(i, &glyph_id)| (glyph_id, i as GlyphID))
+        .collect::<HashMap<_, _>>();
+    for glyph_id in character_mapping.values_mut() {
+        *glyph_id = glyph_indices[glyph_id];
+    }
+
+    let mut tables = vec![];
+    for tag in RETAINED.iter() {
+        if let Some(mut data) = tables::take(tape, &records, tag)? {
+            if *tag == b"OS/2" {
+                writing::windows_metrics(&mut data, &character_mapping);
+            }
+            tables.push((Tag(**tag), data));
+        }
+    }
+
+    match glyph_data {
+        Some(data) => {
+            let (data, mapping, format) =
+                writing::glyph_data(&data, glyph_mapping.as_ref().unwrap(), &glyph_ids)?;
+            font_header.glyph_mapping_format = format;
+            tables.push((Tag(*b"glyf"), data));
+            tables.push((Tag(*b"loca"), mapping));
+        }
+        _ => match tables::take(tape, &records, b"CFF ")? {
+            Some(data) => tables.push((
+                Tag(*b"CFF "),
+                compact::rewrite(&data, &glyph_ids, subroutines)?,
+            )),
+            _ => raise!("cannot find the font set"),
+        },
+    }
+
+    let mut data = vec![];
+    crate::Write::give(&mut data, &font_header)?;
+    tables.push((Tag(*b"head"), data));
+
+    let advances = glyph_ids
+        .iter()
+        .map(|&glyph_id| metrics.borrow().get(glyph_id))
+        .collect::<Vec<_>>();
+    let (data, count) = writing::horizontal_metrics(&advances);
+    tables.push((Tag(*b"hmtx"), data));
+    match tables::take(tape, &records, b"hhea")? {
+        Some(mut data) if data.len() >= 36 => {
+            let advance_width = advances.iter().map(|value| value.0).max().unwrap_or(0);
+            data[10..12].copy_from_slice(&advance_width.to_be_bytes());
+            data[34..36].copy_from_slice(&count.to_be_bytes());
+            tables.push((Tag(*b"hhea"), data));
+        }
+        _ => raise!("cannot find the horizontal header"),
+    }
+    match tables::take(tape, &records, b"maxp")? {
+        Some(mut data) if data.len() >= 6 => {
+            data[4..6].copy_from_slice(&(glyph_ids.len() as u16).to_be_bytes());
+            tables.push((Tag(*b"maxp"), data));
+        }
+        _ => raise!("cannot find the maximum profile"),
+    }
+    if let Some(data) = tables::take(tape, &records, b"post")? {
+        tables.push((Tag(*b"post"), writing::postscript(&data, &glyph_ids)?));
+    }
+    tables.push((
+        Tag(*b"cmap"),
+        writing::character_mapping(&character_mapping)?,
+    ));
+    if features.contains(&Feature::Kerning) {
+        if let Some(data) = tables::take(tape, &records, b"kern")? {
+            if let Some(data) = writing::kerning(&data, &glyph_indices)? {
+                tables.push((Tag(*b"kern"), data));
+            }
+        }
+    }
+
+    let mut layout = false;
+    if let Some(directory) = &substitution {
+        if let Some(data) = layout::rewrite(directory, &glyph_indices, wanted)? {
+            tables.push((Tag(*b"GSUB"), data));
+            layout = true;
+        }
+    }
+    if let Some(directory) = &positioning {
+        if let Some(data) = layout::rewrite(directory, &glyph_indices, wanted)? {
+            tables.push((Tag(*b"GPOS"), data));
+            layout = true;
+        }
+    }
+    if layout {
+        if let Some(record) = records.iter().find(|record| record.tag == b"GDEF") {
+            let definition = layout::Definition::read(tape, record.offset as u64, &glyph_indices)?;
+            tables.push((Tag(*b"GDEF"), definition.encode()?));
+        }
+    }
+
+    writing::assemble(version, tables)
+}
+
+fn expand(value: &Glyph) -> Vec<GlyphID> {
+    match value {
+        Glyph::Scalar(value) => vec![*value],
+        Glyph::Range((start, end)) => (*start..=*end).collect(),
+        Glyph::Ranges(values) => values
+            .iter()
+            .flat_map(|&(start, end)| start..=end)
+            .collect(),
+        Glyph::List(values) => values.clone(),
+    }
+}
+
+fn intersect(value: &Glyph, glyphs: &BTreeSet<GlyphID>) -> bool {
+    match value {
+        Glyph::Scalar(value) => glyphs.contains(value),
+        Glyph::Range((start, end)) => glyphs.range(*start..=*end).next().is_some(),
+        Glyph::Ranges(values) => values
+            .iter()
+            .any(|&(start, end)| glyphs.range(start..=end).next().is_some()),
+        Glyph::List(values) => values.iter().any(|value| glyphs.contains(value)),
+    }
+}
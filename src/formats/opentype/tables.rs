@@ -2,6 +2,7 @@
 
 use std::io::Result;
 
+use opentype::truetype::tables::offsets::Record;
 use opentype::truetype::Tag;
 
 use crate::formats::opentype::cache::Cache;
@@ -18,3 +19,34 @@ pub(crate) fn read<T: crate::Read>(cache: &mut Cache<T>) -> Result<Tables> {
         .map(|record| record.tag)
         .collect())
 }
+
+/// Read a table as is.
+pub(crate) fn take<T>(tape: &mut T, records: &[Record], tag: &[u8; 4]) -> Result<Option<Vec<u8>>>
+where
+    T: crate::Read,
+{
+    match records.iter().find(|record| record.tag == tag) {
+        Some(record) => {
+            tape.jump(record.offset as u64)?;
+            Ok(Some(tape.take_bytes(record.size as usize)?))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Return an error about a malformed table.
+pub(crate) fn malformed<T>(tag: &[u8; 4]) -> Result<T> {
+    error!("found a malformed {} table", String::from_utf8_lossy(tag))
+}
+
+/// Read a 16-bit big-endian integer at a position.
+pub(crate) fn get_u16(data: &[u8], position: usize) -> Option<u16> {
+    data.get(position..(position + 2))
+        .map(|value| u16::from_be_bytes([value[0], value[1]]))
+}
+
+/// Read a 32-bit big-endian integer at a position.
+pub(crate) fn get_u32(data: &[u8], position: usize) -> Option<u32> {
+    data.get(position..(position + 4))
+        .map(|value| u32::from_be_bytes([value[0], value[1], value[2], value[3]]))
+}
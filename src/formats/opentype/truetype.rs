@@ -1,13 +1,19 @@
 use std::io::Result;
 
 use opentype::truetype::tables::glyph_data::{
-    self, CompositeDescription, GlyphData, SimpleDescription,
+    self, CompositeDescription, GlyphData, Options, SimpleDescription,
 };
+use opentype::truetype::{q16, GlyphID};
 
-use crate::formats::opentype::mapping::Forward as Mapping;
+use crate::formats::opentype::font::Drawing;
 use crate::formats::opentype::metrics::Metrics;
-use crate::glyph::{Builder, Glyph};
+use crate::glyph::transform::Transform;
+use crate::glyph::{Component, Pen};
 use crate::offset::Offset;
+use crate::Number;
+
+type Placement = (Offset, Scale);
+type Scale = (Number, Number, Number, Number);
 
 macro_rules! expect(
     ($condition:expr) => (
@@ -19,81 +25,207 @@ macro_rules! expect(
 
 pub(super) fn draw(
     glyph_data: &GlyphData,
-    mapping: &Mapping,
     metrics: &Metrics,
-    character: char,
-) -> Result<Option<Glyph>> {
-    let mut builder = Builder::default();
-    let glyph_id = match mapping.get(character) {
-        Some(glyph_id) => glyph_id,
-        _ => return Ok(None),
-    };
+    glyph_id: GlyphID,
+    flatten: bool,
+    pen: &mut dyn Pen,
+) -> Result<Drawing> {
+    use opentype::truetype::tables::glyph_data::Description::*;
+
     let glyph = match glyph_data.get(glyph_id as usize) {
         Some(glyph) => glyph,
-        _ => raise!(
-            "found no data for character {} with glyph {}",
-            character,
-            glyph_id,
-        ),
+        _ => raise!("found no data for glyph {}", glyph_id),
     };
-    builder.set_horizontal_metrics(metrics.get(glyph_id));
+    let mut horizontal_metrics = metrics.get(glyph_id);
+    let mut bounding_box = None;
+    let mut components = vec![];
     if let Some(ref glyph) = glyph {
-        draw_glyph(glyph_data, metrics, &mut builder, glyph)?;
-        builder.set_bounding_box((glyph.min_x, glyph.min_y, glyph.max_x, glyph.max_y));
+        match &glyph.description {
+            Composite(ref description) => {
+                let (placements, _) = place(glyph_data, description)?;
+                components = description
+                    .components
+                    .iter()
+                    .zip(placements.iter())
+                    .map(|(component, &(offset, scale))| Component {
+                        glyph_id: component.glyph_id,
+                        offset,
+                        scale,
+                    })
+                    .collect();
+                if flatten {
+                    draw_composite(
+                        glyph_data,
+                        metrics,
+                        &mut horizontal_metrics,
+                        pen,
+                        description,
+                        placements,
+                    )?;
+                } else {
+                    for component in description.components.iter() {
+                        if component.flags.should_use_metrics() {
+                            horizontal_metrics = metrics.get(component.glyph_id);
+                        }
+                    }
+                }
+            }
+            Simple(ref description) => draw_simple(pen, description)?,
+        }
+        bounding_box = Some((
+            glyph.min_x.into(),
+            glyph.min_y.into(),
+            glyph.max_x.into(),
+            glyph.max_y.into(),
+        ));
     }
-    Ok(Some(builder.into()))
+    Ok(Drawing {
+        horizontal_metrics,
+        bounding_box,
+        components,
+        hints: None,
+    })
 }
 
 fn draw_glyph(
     glyph_data: &GlyphData,
     metrics: &Metrics,
-    builder: &mut Builder,
+    horizontal_metrics: &mut (Number, Number),
+    pen: &mut dyn Pen,
     glyph: &glyph_data::Glyph,
 ) -> Result<()> {
     use opentype::truetype::tables::glyph_data::Description::*;
 
     match &glyph.description {
-        Composite(ref description) => draw_composite(glyph_data, metrics, builder, description),
-        Simple(ref description) => draw_simple(builder, description),
+        Composite(ref description) => {
+            let (placements, _) = place(glyph_data, description)?;
+            draw_composite(
+                glyph_data,
+                metrics,
+                horizontal_metrics,
+                pen,
+                description,
+                placements,
+            )
+        }
+        Simple(ref description) => draw_simple(pen, description),
     }
 }
 
 fn draw_composite(
     glyph_data: &GlyphData,
     metrics: &Metrics,
-    builder: &mut Builder,
+    horizontal_metrics: &mut (Number, Number),
+    pen: &mut dyn Pen,
     description: &CompositeDescription,
+    placements: Vec<Placement>,
 ) -> Result<()> {
-    use opentype::truetype::tables::glyph_data::{Arguments, Options};
-
-    for component in description.components.iter() {
+    for (component, (offset, scale)) in description.components.iter().zip(placements) {
         let glyph_id = component.glyph_id;
-        let offset = match &component.arguments {
-            &Arguments::Offsets(x, y) => Offset::from((x, y)),
-            arguments => raise!("found a unknown component with arguments {arguments:?}"),
-        };
-        let scale = match component.options {
-            Options::None => (1.0, 0.0, 0.0, 1.0),
-            Options::Scalar(value) => (value.into(), 0.0, 0.0, value.into()),
-            Options::Vector(x, y) => (x.into(), 0.0, 0.0, y.into()),
-            Options::Matrix(xx, xy, yx, yy) => (xx.into(), xy.into(), yx.into(), yy.into()),
-        };
         let glyph = match glyph_data.get(glyph_id as usize) {
             Some(Some(glyph)) => glyph,
             Some(&None) => continue,
             _ => raise!("found no data for glyph {}", glyph_id),
         };
         if component.flags.should_use_metrics() {
-            builder.set_horizontal_metrics(metrics.get(glyph_id));
+            *horizontal_metrics = metrics.get(glyph_id);
         }
-        builder.nest(offset, scale, |builder| {
-            draw_glyph(glyph_data, metrics, builder, glyph)
-        })?;
+        let mut pen = Transform { pen, offset, scale };
+        draw_glyph(glyph_data, metrics, horizontal_metrics, &mut pen, glyph)?;
     }
     Ok(())
 }
 
-fn draw_simple(builder: &mut Builder, description: &SimpleDescription) -> Result<()> {
+// The placement of a component is given by an offset and a scale, and
+// components positioned by point matching are aligned using the points of the
+// components preceding them and their own points, both transformed.
+fn place(
+    glyph_data: &GlyphData,
+    description: &CompositeDescription,
+) -> Result<(Vec<Placement>, Vec<Offset>)> {
+    use opentype::truetype::tables::glyph_data::Arguments;
+
+    let mut placements = Vec::with_capacity(description.components.len());
+    let mut points = vec![];
+    for component in description.components.iter() {
+        let glyph_id = component.glyph_id;
+        let scale = match matrix(component.options) {
+            Some((xx, xy, yx, yy)) => (xx.into(), xy.into(), yx.into(), yy.into()),
+            _ => (1.0, 0.0, 0.0, 1.0),
+        };
+        let apply = |value: Offset| {
+            let (xx, xy, yx, yy) = scale;
+            Offset(xx * value.0 + xy * value.1, yx * value.0 + yy * value.1)
+        };
+        let children = match glyph_data.get(glyph_id as usize) {
+            Some(Some(glyph)) => collect(glyph_data, glyph)?,
+            Some(&None) => vec![],
+            _ => raise!("found no data for glyph {}", glyph_id),
+        };
+        let children = children.into_iter().map(apply).collect::<Vec<_>>();
+        let offset = match component.arguments {
+            Arguments::Offsets(x, y) => {
+                let flags = component.flags;
+                let mut offset = Offset::from((x, y));
+                if flags.is_offset_scaled() && !flags.is_offset_unscaled() {
+                    offset = apply(offset);
+                }
+                if flags.should_round_xy_to_grid() {
+                    offset = Offset(offset.0.round(), offset.1.round());
+                }
+                offset
+            }
+            Arguments::Indices(one, other) => {
+                match (points.get(one as usize), children.get(other as usize)) {
+                    (Some(&one), Some(&other)) => one - other,
+                    _ => raise!(
+                        "found a component of glyph {} with unknown points ({} and {})",
+                        glyph_id,
+                        one,
+                        other,
+                    ),
+                }
+            }
+        };
+        points.extend(children.into_iter().map(|point| point + offset));
+        placements.push((offset, scale));
+    }
+    Ok((placements, points))
+}
+
+// The transformation of a component is returned as `(xx, xy, yx, yy)`, which
+// maps a point `(x, y)` to `(xx * x + xy * y, yx * x + yy * y)`, whereas the
+// matrix is stored with the two off-diagonal elements in the reverse order.
+pub(crate) fn matrix(options: Options) -> Option<(q16, q16, q16, q16)> {
+    match options {
+        Options::None => None,
+        Options::Scalar(value) => Some((value, q16(0), q16(0), value)),
+        Options::Vector(x, y) => Some((x, q16(0), q16(0), y)),
+        Options::Matrix(xx, yx, xy, yy) => Some((xx, xy, yx, yy)),
+    }
+}
+
+fn collect(glyph_data: &GlyphData, glyph: &glyph_data::Glyph) -> Result<Vec<Offset>> {
+    use opentype::truetype::tables::glyph_data::Description::*;
+
+    match &glyph.description {
+        Composite(ref description) => Ok(place(glyph_data, description)?.1),
+        Simple(ref description) => {
+            let SimpleDescription { x, y, .. } = description;
+            expect!(x.len() == y.len());
+            let mut sum = Offset::default();
+            Ok(x.iter()
+                .zip(y.iter())
+                .map(|(&x, &y)| {
+                    sum += (x, y);
+                    sum
+                })
+                .collect())
+        }
+    }
+}
+
+fn draw_simple(pen: &mut dyn Pen, description: &SimpleDescription) -> Result<()> {
     let SimpleDescription {
         end_points,
         flags,
@@ -107,77 +239,161 @@ fn draw_simple(builder: &mut Builder, description: &SimpleDescription) -> Result
     let mut i = 0;
     let mut sum = Offset::default();
     for k in end_points.iter().map(|&k| k as usize) {
-        expect!(i < point_count);
-        let start = Offset::from((x[i], y[i]));
-        let mut control = match flags[i].is_on_curve() {
-            false => Some(Offset::default()),
-            _ => None,
-        };
-        let mut sum_delta = start;
-        let mut offset = Offset::default();
-        for j in (i + 1)..=k {
-            expect!(j < point_count);
-            let current = (x[j], y[j]).into();
-            sum_delta += current;
-            match (flags[j].is_on_curve(), &mut control) {
-                (false, control @ &mut None) => {
-                    *control = Some(current);
-                }
-                (false, &mut Some(ref mut control)) => {
-                    let current = current / 2.0;
-                    builder.add_quadratic(*control, current);
-                    offset += *control + current;
-                    *control = current;
-                }
-                (true, &mut None) => {
-                    builder.add_linear(current);
-                    offset += current;
-                }
-                (true, control @ &mut Some(_)) => {
-                    let control = control.take().unwrap();
-                    builder.add_quadratic(control, current);
-                    offset += control + current;
-                }
-            }
+        expect!(i <= k && k < point_count);
+        let mut points = Vec::with_capacity(k + 1 - i);
+        for j in i..=k {
+            sum += (x[j], y[j]);
+            points.push((sum, flags[j].is_on_curve()));
         }
-        match (flags[i].is_on_curve(), control) {
-            (false, None) => {
-                let control = (sum + start) - (sum + sum_delta);
-                builder.move_control(control);
-                offset += control;
-                builder.move_absolute(sum + sum_delta);
-            }
-            (false, Some(control)) => {
-                let current = ((sum + start) - (sum + sum_delta)) / 2.0;
-                if !control.is_zero() || !current.is_zero() {
-                    builder.add_quadratic(control, current);
-                }
-                offset += control + current;
-                builder.move_control(current);
-                offset += current;
-                builder.move_absolute(sum + sum_delta + current);
+        trace(pen, points);
+        i = k + 1;
+    }
+    Ok(())
+}
+
+pub(super) fn trace(pen: &mut dyn Pen, mut points: Vec<(Offset, bool)>) {
+    if points.is_empty() {
+        return;
+    }
+    let (first, last) = (points[0], points[points.len() - 1]);
+    let (start, skip) = match (first.1, last.1) {
+        (true, _) => (first.0, 1),
+        (false, true) => {
+            points.pop();
+            (last.0, 0)
+        }
+        (false, false) => ((last.0 + first.0) / 2.0, 0),
+    };
+    pen.move_to(start);
+    let mut current = start;
+    let mut control: Option<Offset> = None;
+    for &(point, on_curve) in points.iter().skip(skip) {
+        match (on_curve, control) {
+            (false, None) => control = Some(point),
+            (false, Some(value)) => {
+                let middle = (value + point) / 2.0;
+                pen.quad_to(value, middle);
+                current = middle;
+                control = Some(point);
             }
             (true, None) => {
-                let current = -offset;
-                if !current.is_zero() {
-                    builder.add_linear(current);
-                }
-                offset += current;
-                builder.move_absolute(sum + start);
+                pen.line_to(point);
+                current = point;
             }
-            (true, Some(control)) => {
-                let current = -offset - control;
-                if !control.is_zero() || !current.is_zero() {
-                    builder.add_quadratic(control, current);
-                }
-                offset += control + current;
-                builder.move_absolute(sum + start);
+            (true, Some(value)) => {
+                pen.quad_to(value, point);
+                current = point;
+                control = None;
             }
         }
-        debug_assert!(offset.is_zero());
-        builder.flush();
-        sum += sum_delta;
-        i = k + 1;
     }
-    Ok(())
+    match control {
+        Some(value) if value != current || value != start => pen.quad_to(value, start),
+        None if current != start => pen.line_to(start),
+        _ => {}
+    }
+    pen.close();
+}
+
+#[cfg(test)]
+mod tests {
+    use opentype::truetype::q16;
+    use opentype::truetype::tables::glyph_data::{
+        Arguments, Component, ComponentFlags, CompositeDescription, Description, Glyph, GlyphData,
+        Options, PointFlags, SimpleDescription,
+    };
+
+    use crate::offset::Offset;
+
+    macro_rules! ok(($result:expr) => ($result.unwrap()));
+
+    fn simple(x: Vec<i16>, y: Vec<i16>) -> Option<Glyph> {
+        Some(Glyph {
+            contour_count: 1,
+            min_x: 0,
+            min_y: 0,
+            max_x: 0,
+            max_y: 0,
+            description: Description::Simple(SimpleDescription {
+                end_points: vec![x.len() as u16 - 1],
+                instruction_size: 0,
+                instructions: vec![],
+                flags: vec![PointFlags(1); x.len()],
+                x,
+                y,
+            }),
+        })
+    }
+
+    fn composite(components: Vec<(u16, u16, Arguments, Options)>) -> CompositeDescription {
+        CompositeDescription {
+            components: components
+                .into_iter()
+                .map(|(flags, glyph_id, arguments, options)| Component {
+                    flags: ComponentFlags(flags),
+                    glyph_id,
+                    arguments,
+                    options,
+                })
+                .collect(),
+            instruction_size: 0,
+            instructions: vec![],
+        }
+    }
+
+    #[test]
+    fn place() {
+        let inner = composite(vec![
+            (0x2, 0, Arguments::Offsets(100, 0), Options::None),
+            (0x0, 1, Arguments::Indices(2, 0), Options::None),
+        ]);
+        let glyph_data = GlyphData(vec![
+            simple(vec![0, 0, 10, 0], vec![0, 10, 0, -10]),
+            simple(vec![5, 1, 0], vec![5, 0, 1]),
+            Some(Glyph {
+                contour_count: -1,
+                min_x: 0,
+                min_y: 0,
+                max_x: 0,
+                max_y: 0,
+                description: Description::Composite(inner.clone()),
+            }),
+        ]);
+
+        let (placements, points) = ok!(super::place(&glyph_data, &inner));
+        assert_eq!(placements[0].0, Offset(100.0, 0.0));
+        assert_eq!(placements[1].0, Offset(105.0, 5.0));
+        assert_eq!(points.len(), 7);
+        assert_eq!(points[4], Offset(110.0, 10.0));
+
+        let outer = composite(vec![
+            (
+                0x2 | 0x4 | 0x800,
+                2,
+                Arguments::Offsets(3, 0),
+                Options::Scalar(q16(0x2000)),
+            ),
+            (0x0, 1, Arguments::Indices(6, 1), Options::None),
+        ]);
+        let (placements, points) = ok!(super::place(&glyph_data, &outer));
+        assert_eq!(placements[0].0, Offset(2.0, 0.0));
+        assert_eq!(points[6], Offset(57.5, 5.5));
+        assert_eq!(placements[1].0, Offset(51.5, 0.5));
+
+        let outer = composite(vec![(0x0, 1, Arguments::Indices(3, 0), Options::None)]);
+        assert!(super::place(&glyph_data, &outer).is_err());
+    }
+
+    #[test]
+    fn matrix() {
+        let glyph_data = GlyphData(vec![simple(vec![5, 1, 0], vec![5, 0, 1])]);
+        // The off-diagonal elements are stored as the contribution of x to y
+        // followed by the contribution of y to x.
+        let options = Options::Matrix(q16(0x4000), q16(0x2000), q16(0), q16(0x4000));
+        let description = composite(vec![(0x2, 0, Arguments::Offsets(0, 0), options)]);
+        let (placements, points) = ok!(super::place(&glyph_data, &description));
+        assert_eq!(placements[0].1, (1.0, 0.0, 0.5, 1.0));
+        assert_eq!(points[0], Offset(5.0, 7.5));
+        assert_eq!(points[2], Offset(6.0, 9.0));
+    }
 }
@@ -0,0 +1,461 @@
+use std::io::Result;
+use std::ops::Range;
+
+use opentype::truetype::GlyphID;
+
+use crate::formats::opentype::charstring;
+use crate::formats::opentype::subset::Subroutines;
+use crate::formats::opentype::writing::type2;
+
+const CHARSET: u16 = 15;
+const CHARSTRINGS: u16 = 17;
+const ENCODING: u16 = 16;
+const FONT_BBOX: u16 = 5;
+const FONT_MATRIX: u16 = 0x0C00 | 7;
+const FULL_NAME: u16 = 2;
+const NOMINAL_WIDTH_X: u16 = 21;
+const PRIVATE: u16 = 18;
+const ROS: u16 = 0x0C00 | 30;
+const SUBRS: u16 = 19;
+
+type Operations = Vec<(u16, Vec<u8>)>;
+
+/// Compose a font set out of glyph names and character strings.
+///
+/// The font set is name-keyed and has a single font, which uses neither
+/// subroutines nor a custom encoding. The character strings are expected to
+/// encode advance widths relative to zero.
+pub fn compose(
+    name: &str,
+    glyph_names: &[&str],
+    strings: &[Vec<u8>],
+    bounding_box: [i16; 4],
+    granularity: u16,
+) -> Result<Vec<u8>> {
+    if glyph_names.len() != strings.len() {
+        raise!("found a mismatch between glyph names and character strings");
+    }
+    let mut names = vec![name.as_bytes()];
+    let mut character_set = vec![0];
+    for name in glyph_names.iter().skip(1) {
+        character_set.extend((391 + names.len() as u16).to_be_bytes());
+        names.push(name.as_bytes());
+    }
+    let names = encode_index(&names);
+    let character_strings = encode_index(&strings.iter().map(Vec::as_slice).collect::<Vec<_>>());
+    let mut private = vec![];
+    set(&mut private, NOMINAL_WIDTH_X, &[0]);
+    let private = encode_dictionary(&private);
+
+    let mut top = vec![];
+    set(&mut top, FULL_NAME, &[391]);
+    set(&mut top, FONT_BBOX, &bounding_box.map(|value| value as i32));
+    if granularity != 1000 {
+        let scale = real(1.0 / granularity as f64);
+        let zero = real(0.0);
+        let mut operands = vec![];
+        for value in [&scale, &zero, &zero, &scale, &zero, &zero] {
+            operands.extend_from_slice(value);
+        }
+        top.push((FONT_MATRIX, operands));
+    }
+    set(&mut top, CHARSET, &[0]);
+    set(&mut top, CHARSTRINGS, &[0]);
+    set(&mut top, PRIVATE, &[0, 0]);
+    let top_size = encode_index(&[&encode_dictionary(&top)]).len();
+    let names_size = encode_index(&[name.as_bytes()]).len();
+    let character_set_offset = 4 + names_size + top_size + names.len() + 2;
+    let character_strings_offset = character_set_offset + character_set.len();
+    let private_offset = character_strings_offset + character_strings.len();
+    set(&mut top, CHARSET, &[character_set_offset as i32]);
+    set(&mut top, CHARSTRINGS, &[character_strings_offset as i32]);
+    set(
+        &mut top,
+        PRIVATE,
+        &[private.len() as i32, private_offset as i32],
+    );
+    let top = encode_dictionary(&top);
+
+    let mut output = vec![1, 0, 4, 4];
+    output.extend(encode_index(&[name.as_bytes()]));
+    output.extend(encode_index(&[&top]));
+    output.extend(names);
+    output.extend(encode_index(&[]));
+    output.extend(character_set);
+    output.extend(character_strings);
+    output.extend(private);
+    Ok(output)
+}
+
+/// Rewrite a font set retaining the given glyphs in the given order.
+///
+/// Only name-keyed font sets with a single font are supported. The custom
+/// encoding, if any, is left out, as it is superseded by the character-to-glyph
+/// mapping. Unless subroutines are retained, the global ones are left out, and
+/// the local ones are either left out or recomputed.
+pub fn rewrite(data: &[u8], glyph_ids: &[GlyphID], subroutines: Subroutines) -> Result<Vec<u8>> {
+    let header_size = get(data, 2)? as usize;
+    let (_, names_end) = index(data, header_size)?;
+    let (dictionaries, dictionaries_end) = index(data, names_end)?;
+    let (_, strings_end) = index(data, dictionaries_end)?;
+    let (global_subroutines, subroutines_end) = index(data, strings_end)?;
+    if dictionaries.len() != 1 {
+        raise!("found a font set with more than one font, which is not supported yet");
+    }
+
+    let mut top = tokenize(&data[dictionaries[0].clone()])?;
+    if find(&top, ROS).is_some() {
+        raise!("found a character-ID-keyed font set, which is not supported yet");
+    }
+    let character_strings = match find(&top, CHARSTRINGS).map(integers) {
+        Some(Ok(values)) if values.len() == 1 => index(data, values[0] as usize)?.0,
+        _ => raise!("found no valid character strings"),
+    };
+    let glyph_count = character_strings.len();
+    let names = match find(&top, CHARSET).map(integers) {
+        Some(Ok(values)) if values.len() == 1 => charset(data, values[0] as usize, glyph_count)?,
+        None => charset(data, 0, glyph_count)?,
+        _ => raise!("found a malformed character set"),
+    };
+    let (mut private, private_range) = match find(&top, PRIVATE).map(integers) {
+        Some(Ok(values)) if values.len() == 2 => {
+            let range = (values[1] as usize)..(values[1] as usize + values[0] as usize);
+            match data.get(range.clone()) {
+                Some(value) => (tokenize(value)?, range),
+                _ => raise!("found a malformed private dictionary"),
+            }
+        }
+        _ => raise!("found no valid private dictionary"),
+    };
+    let local_subroutines = match find(&private, SUBRS).map(integers) {
+        Some(Ok(values)) if values.len() == 1 => {
+            let start = private_range.start + values[0] as usize;
+            let (ranges, end) = index(data, start)?;
+            Some((&data[start..end], ranges))
+        }
+        None => None,
+        _ => raise!("found malformed local subroutines"),
+    };
+
+    let mut character_set = vec![0];
+    let mut strings = Vec::with_capacity(glyph_ids.len());
+    for (i, &glyph_id) in glyph_ids.iter().enumerate() {
+        let glyph_id = glyph_id as usize;
+        match (character_strings.get(glyph_id), names.get(glyph_id)) {
+            (Some(range), Some(&name)) => {
+                strings.push(&data[range.clone()]);
+                if i > 0 {
+                    character_set.extend(name.to_be_bytes());
+                }
+            }
+            _ => raise!("found no glyph with index {}", glyph_id),
+        }
+    }
+    let (global_subroutines, local_subroutines, strings) = match subroutines {
+        Subroutines::Retain => (
+            data[strings_end..subroutines_end].to_vec(),
+            local_subroutines.map(|(data, _)| data.to_vec()),
+            strings.into_iter().map(|value| value.to_vec()).collect(),
+        ),
+        _ => {
+            let global = global_subroutines
+                .into_iter()
+                .map(|range| &data[range])
+                .collect::<Vec<_>>();
+            let local = local_subroutines
+                .map(|(_, ranges)| ranges.into_iter().map(|range| &data[range]).collect())
+                .unwrap_or_else(Vec::new);
+            let strings = strings
+                .into_iter()
+                .map(|code| charstring::flatten(code, &global, &local))
+                .collect::<Result<Vec<_>>>()?;
+            match subroutines {
+                Subroutines::Factor => {
+                    let (strings, local) = type2::factor(&strings)?;
+                    let local = match local.is_empty() {
+                        true => None,
+                        _ => Some(encode_index(
+                            &local.iter().map(Vec::as_slice).collect::<Vec<_>>(),
+                        )),
+                    };
+                    (encode_index(&[]), local, strings)
+                }
+                _ => (encode_index(&[]), None, strings),
+            }
+        }
+    };
+    let character_strings = encode_index(&strings.iter().map(Vec::as_slice).collect::<Vec<_>>());
+
+    match local_subroutines {
+        Some(_) => {
+            set(&mut private, SUBRS, &[0]);
+            let size = encode_dictionary(&private).len();
+            set(&mut private, SUBRS, &[size as i32]);
+        }
+        _ => private.retain(|(operator, _)| *operator != SUBRS),
+    }
+    let private = encode_dictionary(&private);
+
+    top.retain(|(operator, _)| *operator != ENCODING);
+    set(&mut top, CHARSET, &[0]);
+    set(&mut top, CHARSTRINGS, &[0]);
+    set(&mut top, PRIVATE, &[0, 0]);
+    let top_size = encode_index(&[&encode_dictionary(&top)]).len();
+    let character_set_offset = 4
+        + (names_end - header_size)
+        + top_size
+        + (strings_end - dictionaries_end)
+        + global_subroutines.len();
+    let character_strings_offset = character_set_offset + character_set.len();
+    let private_offset = character_strings_offset + character_strings.len();
+    set(&mut top, CHARSET, &[character_set_offset as i32]);
+    set(&mut top, CHARSTRINGS, &[character_strings_offset as i32]);
+    set(
+        &mut top,
+        PRIVATE,
+        &[private.len() as i32, private_offset as i32],
+    );
+    let top = encode_dictionary(&top);
+
+    let mut output = vec![get(data, 0)?, get(data, 1)?, 4, 4];
+    output.extend_from_slice(&data[header_size..names_end]);
+    output.extend(encode_index(&[&top]));
+    output.extend_from_slice(&data[dictionaries_end..strings_end]);
+    output.extend(global_subroutines);
+    output.extend(character_set);
+    output.extend(character_strings);
+    output.extend(private);
+    if let Some(value) = local_subroutines {
+        output.extend(value);
+    }
+    Ok(output)
+}
+
+fn charset(data: &[u8], offset: usize, glyph_count: usize) -> Result<Vec<u16>> {
+    let mut values = Vec::with_capacity(glyph_count);
+    values.push(0);
+    match offset {
+        0 => values.extend(1..(glyph_count as u16)),
+        1 | 2 => raise!("found a predefined expert character set, which is not supported yet"),
+        _ => {
+            let mut position = offset + 1;
+            let format = get(data, offset)?;
+            while values.len() < glyph_count {
+                let first = u16::from_be_bytes([get(data, position)?, get(data, position + 1)?]);
+                match format {
+                    0 => {
+                        values.push(first);
+                        position += 2;
+                    }
+                    1 => {
+                        let count = get(data, position + 2)? as u16;
+                        values.extend((0..=count).map(|i| first.wrapping_add(i)));
+                        position += 3;
+                    }
+                    2 => {
+                        let count = u16::from_be_bytes([
+                            get(data, position + 2)?,
+                            get(data, position + 3)?,
+                        ]);
+                        values.extend((0..=count).map(|i| first.wrapping_add(i)));
+                        position += 4;
+                    }
+                    _ => raise!("found an unknown format of character sets ({format})"),
+                }
+            }
+            values.truncate(glyph_count);
+        }
+    }
+    Ok(values)
+}
+
+fn encode_dictionary(operations: &Operations) -> Vec<u8> {
+    let mut data = vec![];
+    for (operator, operands) in operations.iter() {
+        data.extend_from_slice(operands);
+        if *operator > 0xFF {
+            data.extend([12, *operator as u8]);
+        } else {
+            data.push(*operator as u8);
+        }
+    }
+    data
+}
+
+fn encode_index(values: &[&[u8]]) -> Vec<u8> {
+    if values.is_empty() {
+        return vec![0, 0];
+    }
+    let total = values.iter().map(|value| value.len()).sum::<usize>() + 1;
+    let offset_size = match total {
+        0..=0xFF => 1,
+        0x100..=0xFFFF => 2,
+        0x10000..=0xFF_FFFF => 3,
+        _ => 4,
+    };
+    let mut data = Vec::with_capacity(3 + (values.len() + 1) * offset_size + total);
+    data.extend((values.len() as u16).to_be_bytes());
+    data.push(offset_size as u8);
+    let mut offset = 1u32;
+    data.extend_from_slice(&offset.to_be_bytes()[(4 - offset_size)..]);
+    for value in values.iter() {
+        offset += value.len() as u32;
+        data.extend_from_slice(&offset.to_be_bytes()[(4 - offset_size)..]);
+    }
+    for value in values.iter() {
+        data.extend_from_slice(value);
+    }
+    data
+}
+
+fn find(operations: &Operations, operator: u16) -> Option<&[u8]> {
+    operations
+        .iter()
+        .find(|(other, _)| *other == operator)
+        .map(|(_, operands)| &operands[..])
+}
+
+#[inline]
+fn get(data: &[u8], position: usize) -> Result<u8> {
+    match data.get(position) {
+        Some(value) => Ok(*value),
+        _ => raise!("found a malformed font set"),
+    }
+}
+
+fn index(data: &[u8], position: usize) -> Result<(Vec<Range<usize>>, usize)> {
+    let count = u16::from_be_bytes([get(data, position)?, get(data, position + 1)?]) as usize;
+    if count == 0 {
+        return Ok((vec![], position + 2));
+    }
+    let offset_size = get(data, position + 2)? as usize;
+    if !(1..=4).contains(&offset_size) {
+        raise!("found a malformed index");
+    }
+    let offset = |i: usize| -> Result<usize> {
+        let start = position + 3 + i * offset_size;
+        let mut value = 0;
+        for j in 0..offset_size {
+            value = (value << 8) | get(data, start + j)? as usize;
+        }
+        Ok(value)
+    };
+    let base = position + 2 + (count + 1) * offset_size;
+    let mut values = Vec::with_capacity(count);
+    let mut start = offset(0)?;
+    for i in 0..count {
+        let end = offset(i + 1)?;
+        if start > end || base + end > data.len() {
+            raise!("found a malformed index");
+        }
+        values.push((base + start)..(base + end));
+        start = end;
+    }
+    Ok((values, base + start))
+}
+
+fn integers(data: &[u8]) -> Result<Vec<i32>> {
+    let mut values = vec![];
+    let mut i = 0;
+    while i < data.len() {
+        let value = data[i] as i32;
+        match value {
+            28 => {
+                values.push(i16::from_be_bytes([get(data, i + 1)?, get(data, i + 2)?]) as i32);
+                i += 3;
+            }
+            29 => {
+                values.push(i32::from_be_bytes([
+                    get(data, i + 1)?,
+                    get(data, i + 2)?,
+                    get(data, i + 3)?,
+                    get(data, i + 4)?,
+                ]));
+                i += 5;
+            }
+            32..=246 => {
+                values.push(value - 139);
+                i += 1;
+            }
+            247..=250 => {
+                values.push((value - 247) * 256 + get(data, i + 1)? as i32 + 108);
+                i += 2;
+            }
+            251..=254 => {
+                values.push(-(value - 251) * 256 - get(data, i + 1)? as i32 - 108);
+                i += 2;
+            }
+            _ => raise!("found a malformed integer operand"),
+        }
+    }
+    Ok(values)
+}
+
+fn real(value: f64) -> Vec<u8> {
+    let mut nibbles = vec![];
+    for character in value.to_string().chars() {
+        nibbles.push(match character {
+            '0'..='9' => character as u8 - b'0',
+            '.' => 0xA,
+            _ => 0xE,
+        });
+    }
+    nibbles.push(0xF);
+    if nibbles.len() % 2 == 1 {
+        nibbles.push(0xF);
+    }
+    let mut data = vec![30];
+    data.extend(nibbles.chunks_exact(2).map(|pair| (pair[0] << 4) | pair[1]));
+    data
+}
+
+fn set(operations: &mut Operations, operator: u16, values: &[i32]) {
+    let mut operands = Vec::with_capacity(5 * values.len());
+    for value in values.iter() {
+        operands.push(29);
+        operands.extend(value.to_be_bytes());
+    }
+    match operations.iter_mut().find(|(other, _)| *other == operator) {
+        Some((_, other)) => *other = operands,
+        _ => operations.push((operator, operands)),
+    }
+}
+
+fn tokenize(data: &[u8]) -> Result<Operations> {
+    let mut operations = vec![];
+    let mut start = 0;
+    let mut i = 0;
+    while i < data.len() {
+        match data[i] {
+            12 => {
+                operations.push((0x0C00 | get(data, i + 1)? as u16, data[start..i].to_vec()));
+                i += 2;
+                start = i;
+            }
+            value @ 0..=21 => {
+                operations.push((value as u16, data[start..i].to_vec()));
+                i += 1;
+                start = i;
+            }
+            28 => i += 3,
+            29 => i += 5,
+            30 => {
+                i += 1;
+                loop {
+                    let value = get(data, i)?;
+                    i += 1;
+                    if value >> 4 == 0xF || value & 0xF == 0xF {
+                        break;
+                    }
+                }
+            }
+            32..=246 => i += 1,
+            247..=254 => i += 2,
+            value => raise!("found a malformed dictionary with byte {value}"),
+        }
+    }
+    if start != data.len() {
+        raise!("found a malformed dictionary");
+    }
+    Ok(operations)
+}
@@ -0,0 +1,1423 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::Result;
+
+use opentype::layout::context::Action;
+use opentype::layout::{ChainedContext, Class, Context, Correction, Coverage, Directory};
+use opentype::tables::glyph_definition::{Attachments, Header, Marks};
+use opentype::tables::glyph_positioning::{self as positioning, Anchor, Single};
+use opentype::tables::glyph_substitution as substitution;
+use opentype::truetype::{GlyphID, Tag};
+
+use crate::formats::opentype::writing::node::Node;
+
+/// A type of subtables of lookups.
+pub trait Subtable: Clone + opentype::walue::Read<'static, Parameter = u16> {
+    /// The type of extension lookups.
+    const EXTENSION: u16;
+
+    /// Return the type and offset of the extended subtable if any.
+    fn extension(&self) -> Option<(u16, u32)>;
+
+    /// Return the lookups invoked by the subtable.
+    fn actions(&self) -> Vec<u16>;
+
+    /// Rewrite the subtable, which is nothing if the subtable becomes empty.
+    fn rewrite(&self, remap: &Remap) -> Option<Node>;
+}
+
+/// A mapping of glyphs, lookups, and coordinates.
+pub struct Remap<'l> {
+    pub glyphs: &'l HashMap<GlyphID, GlyphID>,
+    pub lookups: HashMap<u16, u16>,
+    pub scale: f32,
+}
+
+/// An adjustment of a table merged with others.
+#[derive(Clone, Copy, Debug)]
+pub struct Shift {
+    /// The scaling factor of coordinates.
+    pub scale: f32,
+    /// The shift of mark-attachment classes.
+    pub mark_classes: u16,
+    /// The shift of mark sets.
+    pub mark_sets: u16,
+}
+
+/// A glyph-definition table.
+#[derive(Clone, Debug, Default)]
+pub struct Definition {
+    glyph_classes: BTreeMap<GlyphID, u16>,
+    attachments: BTreeMap<GlyphID, Vec<u16>>,
+    mark_classes: BTreeMap<GlyphID, u16>,
+    mark_sets: Option<Vec<Vec<GlyphID>>>,
+}
+
+struct Layout {
+    scripts: Vec<Script>,
+    features: Vec<(Tag, Vec<u16>)>,
+    lookups: Vec<Lookup>,
+    extension: u16,
+}
+
+struct Script {
+    tag: Tag,
+    default: Option<Language>,
+    languages: Vec<(Tag, Language)>,
+}
+
+struct Language {
+    required: u16,
+    features: Vec<u16>,
+}
+
+struct Lookup {
+    r#type: u16,
+    flags: u16,
+    mark_filtering_set: Option<u16>,
+    tables: Vec<Node>,
+}
+
+/// Replace extension subtables with the subtables they refer to.
+pub fn resolve<T, U>(tape: &mut T, position: u64, directory: &mut Directory<U>) -> Result<()>
+where
+    T: crate::Read,
+    U: Subtable,
+{
+    let position = position + directory.lookup_offset as u64;
+    let lookups = &mut directory.lookups;
+    for (record, offset) in lookups.records.iter_mut().zip(lookups.offsets.iter()) {
+        if record.r#type != U::EXTENSION {
+            continue;
+        }
+        for (table, table_offset) in record.tables.iter_mut().zip(record.table_offsets.iter()) {
+            let (r#type, extension_offset) = match table.extension() {
+                Some(value) => value,
+                _ => raise!("found a malformed extension lookup"),
+            };
+            tape.jump(position + *offset as u64 + *table_offset as u64 + extension_offset as u64)?;
+            *table = tape.take_given(r#type)?;
+            record.r#type = r#type;
+        }
+    }
+    Ok(())
+}
+
+/// Return the lookups of features including those invoked by other lookups.
+pub fn select<T, F>(directory: &Directory<T>, features: F) -> BTreeSet<u16>
+where
+    T: Subtable,
+    F: Fn(&Tag) -> bool,
+{
+    let mut pending = directory
+        .features
+        .headers
+        .iter()
+        .zip(directory.features.records.iter())
+        .filter(|(header, _)| features(&header.tag))
+        .flat_map(|(_, record)| record.lookup_indices.iter().cloned())
+        .collect::<Vec<_>>();
+    let mut values = BTreeSet::default();
+    while let Some(index) = pending.pop() {
+        let record = match directory.lookups.records.get(index as usize) {
+            Some(record) if values.insert(index) => record,
+            _ => continue,
+        };
+        for table in record.tables.iter() {
+            pending.extend(table.actions());
+        }
+    }
+    values
+}
+
+/// Rewrite a glyph-substitution or glyph-positioning table.
+///
+/// Only the given features and the lookups they need are retained, and glyphs
+/// without a mapping are removed. Extension subtables should be resolved
+/// beforehand, and they are reintroduced only if offsets overflow otherwise.
+pub fn rewrite<T, F>(
+    directory: &Directory<T>,
+    glyphs: &HashMap<GlyphID, GlyphID>,
+    features: F,
+) -> Result<Option<Vec<u8>>>
+where
+    T: Subtable,
+    F: Fn(&Tag) -> bool,
+{
+    match build(directory, glyphs, features, &Shift::default(), (0, 0)) {
+        Some(layout) => pack(&layout).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// Merge glyph-substitution or glyph-positioning tables.
+///
+/// Lookups are concatenated, and language systems are merged under their
+/// script and language tags. Extension subtables should be resolved
+/// beforehand.
+pub fn merge<T>(
+    sources: &[(&Directory<T>, &HashMap<GlyphID, GlyphID>, Shift)],
+) -> Result<Option<Vec<u8>>>
+where
+    T: Subtable,
+{
+    let mut layout: Option<Layout> = None;
+    for (directory, glyphs, shift) in sources.iter() {
+        let bases = match &layout {
+            Some(layout) => (layout.features.len() as u16, layout.lookups.len() as u16),
+            _ => (0, 0),
+        };
+        let other = match build(directory, glyphs, |_| true, shift, bases) {
+            Some(other) => other,
+            _ => continue,
+        };
+        layout = match layout {
+            Some(mut layout) => {
+                layout.extend(other);
+                Some(layout)
+            }
+            _ => Some(other),
+        };
+    }
+    match layout {
+        Some(layout) => pack(&layout).map(Some),
+        _ => Ok(None),
+    }
+}
+
+fn build<T, F>(
+    directory: &Directory<T>,
+    glyphs: &HashMap<GlyphID, GlyphID>,
+    features: F,
+    shift: &Shift,
+    (feature_base, lookup_base): (u16, u16),
+) -> Option<Layout>
+where
+    T: Subtable,
+    F: Fn(&Tag) -> bool,
+{
+    let feature_indices = directory
+        .features
+        .headers
+        .iter()
+        .enumerate()
+        .filter(|(_, header)| features(&header.tag))
+        .enumerate()
+        .map(|(j, (i, _))| (i as u16, feature_base + j as u16))
+        .collect::<HashMap<_, _>>();
+    if feature_indices.is_empty() {
+        return None;
+    }
+    let lookup_indices = select(directory, &features)
+        .into_iter()
+        .enumerate()
+        .map(|(j, i)| (i, lookup_base + j as u16))
+        .collect::<HashMap<_, _>>();
+    let remap = Remap {
+        glyphs,
+        lookups: lookup_indices,
+        scale: shift.scale,
+    };
+
+    let language = |record: &opentype::layout::language::Record| Language {
+        required: feature_indices
+            .get(&record.required_feature_index)
+            .cloned()
+            .unwrap_or(0xFFFF),
+        features: record
+            .feature_indices
+            .iter()
+            .filter_map(|index| feature_indices.get(index).cloned())
+            .collect(),
+    };
+    let scripts = directory
+        .scripts
+        .headers
+        .iter()
+        .zip(directory.scripts.records.iter())
+        .map(|(header, record)| Script {
+            tag: header.tag,
+            default: record.default_language.as_ref().map(language),
+            languages: record
+                .language_headers
+                .iter()
+                .zip(record.language_records.iter())
+                .map(|(header, record)| (header.tag, language(record)))
+                .collect(),
+        })
+        .collect();
+    let features = directory
+        .features
+        .headers
+        .iter()
+        .zip(directory.features.records.iter())
+        .filter(|(header, _)| features(&header.tag))
+        .map(|(header, record)| {
+            (
+                header.tag,
+                record
+                    .lookup_indices
+                    .iter()
+                    .filter_map(|index| remap.lookups.get(index).cloned())
+                    .collect(),
+            )
+        })
+        .collect();
+    let lookups = directory
+        .lookups
+        .records
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| remap.lookups.contains_key(&(*i as u16)))
+        .map(|(_, record)| Lookup {
+            r#type: record.r#type,
+            flags: match record.flags.0 >> 8 {
+                0 => record.flags.0,
+                class => ((class + shift.mark_classes) << 8) | (record.flags.0 & 0x00FF),
+            },
+            mark_filtering_set: record
+                .mark_filtering_set
+                .map(|value| value + shift.mark_sets),
+            tables: record
+                .tables
+                .iter()
+                .filter_map(|table| table.rewrite(&remap))
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+    Some(Layout {
+        scripts,
+        features,
+        lookups,
+        extension: T::EXTENSION,
+    })
+}
+
+fn pack(layout: &Layout) -> Result<Vec<u8>> {
+    if let Some(data) = encode(layout, None).pack() {
+        return Ok(data);
+    }
+    match encode(layout, Some(layout.extension)).pack() {
+        Some(data) => Ok(data),
+        _ => raise!("found an overflow of offsets in a layout table"),
+    }
+}
+
+impl Definition {
+    /// Read a glyph-definition table.
+    ///
+    /// Glyph classes, attachment points, mark-attachment classes, and mark sets
+    /// are retained. Ligature carets and variations are left out.
+    pub fn read<T>(tape: &mut T, position: u64, glyphs: &HashMap<GlyphID, GlyphID>) -> Result<Self>
+    where
+        T: crate::Read,
+    {
+        let remap = Remap {
+            glyphs,
+            lookups: Default::default(),
+            scale: 1.0,
+        };
+        tape.jump(position)?;
+        let (glyph_class_offset, attachment_offset, mark_class_offset, mark_offset) =
+            match tape.take::<Header>()? {
+                Header::Version1(header) => (
+                    header.glyph_class_offset,
+                    header.attachment_offset,
+                    header.mark_class_offset,
+                    0,
+                ),
+                Header::Version12(header) => (
+                    header.glyph_class_offset,
+                    header.attachment_offset,
+                    header.mark_class_offset,
+                    header.mark_offset,
+                ),
+                Header::Version13(header) => (
+                    header.glyph_class_offset,
+                    header.attachment_offset,
+                    header.mark_class_offset,
+                    header.mark_offset,
+                ),
+            };
+        macro_rules! take(
+            ($offset:expr, $type:ty) => (
+                if $offset > 0 {
+                    tape.jump(position + $offset as u64)?;
+                    Some(tape.take::<$type>()?)
+                } else {
+                    None
+                }
+            );
+        );
+        let glyph_class = take!(glyph_class_offset, Class);
+        let attachments = take!(attachment_offset, Attachments);
+        let mark_class = take!(mark_class_offset, Class);
+        let marks = take!(mark_offset, Marks);
+        Ok(Self {
+            glyph_classes: glyph_class
+                .map(|value| remap.class(&value))
+                .unwrap_or_default(),
+            attachments: attachments
+                .map(|value| {
+                    remap
+                        .coverage(&value.coverage)
+                        .into_iter()
+                        .filter_map(|(glyph_id, index)| {
+                            Some((glyph_id, value.records.get(index)?.indices.clone()))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            mark_classes: mark_class
+                .map(|value| remap.class(&value))
+                .unwrap_or_default(),
+            mark_sets: marks.map(|value| {
+                value
+                    .coverages
+                    .iter()
+                    .map(|value| first(&remap.coverage(value)))
+                    .collect()
+            }),
+        })
+    }
+
+    /// Add another definition.
+    ///
+    /// The function returns the shift of mark-attachment classes and mark sets
+    /// that the other definition has undergone.
+    pub fn extend(&mut self, other: Self) -> (u16, u16) {
+        let mark_classes = self.mark_classes.values().cloned().max().unwrap_or(0);
+        let mark_sets = self.mark_sets.as_ref().map(Vec::len).unwrap_or(0) as u16;
+        self.glyph_classes.extend(other.glyph_classes);
+        self.attachments.extend(other.attachments);
+        self.mark_classes.extend(
+            other
+                .mark_classes
+                .into_iter()
+                .map(|(glyph_id, class)| (glyph_id, class + mark_classes)),
+        );
+        if let Some(values) = other.mark_sets {
+            self.mark_sets.get_or_insert_with(Vec::new).extend(values);
+        }
+        (mark_classes, mark_sets)
+    }
+
+    /// Encode the definition.
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        let optional = |value: &BTreeMap<GlyphID, u16>| (!value.is_empty()).then(|| class(value));
+        let mut node = Node::new();
+        node.u16(1)
+            .u16(if self.mark_sets.is_some() { 2 } else { 0 });
+        node.link(optional(&self.glyph_classes));
+        node.link((!self.attachments.is_empty()).then(|| {
+            let glyph_ids = self.attachments.keys().cloned().collect::<Vec<_>>();
+            let mut node = Node::new();
+            node.link(Some(coverage(&glyph_ids)))
+                .u16(glyph_ids.len() as u16);
+            for indices in self.attachments.values() {
+                node.link(Some(sequence(indices)));
+            }
+            node
+        }));
+        node.link(None);
+        node.link(optional(&self.mark_classes));
+        if let Some(values) = &self.mark_sets {
+            let mut child = Node::new();
+            child.u16(1).u16(values.len() as u16);
+            for glyph_ids in values.iter() {
+                child.link_wide(Some(coverage(glyph_ids)));
+            }
+            node.link(Some(child));
+        }
+        match node.pack() {
+            Some(data) => Ok(data),
+            _ => raise!("found an overflow of offsets in the glyph definition"),
+        }
+    }
+}
+
+impl Default for Shift {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            mark_classes: 0,
+            mark_sets: 0,
+        }
+    }
+}
+
+impl Layout {
+    fn extend(&mut self, other: Self) {
+        for script in other.scripts {
+            match self
+                .scripts
+                .iter_mut()
+                .find(|value| value.tag == script.tag)
+            {
+                Some(value) => {
+                    match (&mut value.default, script.default) {
+                        (Some(value), Some(other)) => value.extend(other),
+                        (value @ None, other) => *value = other,
+                        _ => {}
+                    }
+                    for (tag, language) in script.languages {
+                        match value.languages.iter_mut().find(|(other, _)| *other == tag) {
+                            Some((_, value)) => value.extend(language),
+                            _ => value.languages.push((tag, language)),
+                        }
+                    }
+                    value.languages.sort_by_key(|(tag, _)| *tag);
+                }
+                _ => self.scripts.push(script),
+            }
+        }
+        self.scripts.sort_by_key(|script| script.tag);
+        self.features.extend(other.features);
+        self.lookups.extend(other.lookups);
+    }
+}
+
+impl Language {
+    fn extend(&mut self, other: Self) {
+        if self.required == 0xFFFF {
+            self.required = other.required;
+        }
+        self.features.extend(other.features);
+    }
+}
+
+impl Remap<'_> {
+    #[inline]
+    fn glyph(&self, glyph_id: GlyphID) -> Option<GlyphID> {
+        self.glyphs.get(&glyph_id).cloned()
+    }
+
+    fn glyphs(&self, glyph_ids: &[GlyphID]) -> Option<Vec<GlyphID>> {
+        glyph_ids
+            .iter()
+            .map(|&glyph_id| self.glyph(glyph_id))
+            .collect()
+    }
+
+    fn coverage(&self, value: &Coverage) -> Vec<(GlyphID, usize)> {
+        let mut values = uncover(value)
+            .into_iter()
+            .filter_map(|(glyph_id, index)| self.glyph(glyph_id).map(|glyph_id| (glyph_id, index)))
+            .collect::<Vec<_>>();
+        values.sort();
+        values
+    }
+
+    fn coverages(&self, values: &[Coverage]) -> Option<Vec<Node>> {
+        values
+            .iter()
+            .map(|value| {
+                let glyph_ids = first(&self.coverage(value));
+                if glyph_ids.is_empty() {
+                    None
+                } else {
+                    Some(coverage(&glyph_ids))
+                }
+            })
+            .collect()
+    }
+
+    fn class(&self, value: &Class) -> BTreeMap<GlyphID, u16> {
+        let mut values = BTreeMap::default();
+        match value {
+            Class::Format1(value) => {
+                for (i, &index) in value.indices.iter().enumerate() {
+                    if let Some(glyph_id) = self.glyph(value.start_glyph_id + i as GlyphID) {
+                        values.insert(glyph_id, index);
+                    }
+                }
+            }
+            Class::Format2(value) => {
+                for record in value.records.iter() {
+                    for glyph_id in record.start_glyph_id..=record.end_glyph_id {
+                        if let Some(glyph_id) = self.glyph(glyph_id) {
+                            values.insert(glyph_id, record.index);
+                        }
+                    }
+                }
+            }
+        }
+        values
+    }
+
+    fn actions(&self, values: &[Action]) -> Vec<Action> {
+        values
+            .iter()
+            .filter_map(|action| {
+                self.lookups
+                    .get(&action.lookup_index)
+                    .map(|&lookup_index| Action {
+                        position_index: action.position_index,
+                        lookup_index,
+                    })
+            })
+            .collect()
+    }
+
+    fn anchor(&self, value: &Anchor) -> Node {
+        let mut node = Node::new();
+        match value {
+            Anchor::Format1(value) => {
+                node.u16(1)
+                    .i16(self.scale(value.x))
+                    .i16(self.scale(value.y));
+            }
+            Anchor::Format2(value) => {
+                node.u16(2)
+                    .i16(self.scale(value.x))
+                    .i16(self.scale(value.y))
+                    .u16(value.index);
+            }
+            Anchor::Format3(value) => {
+                let x = device(&value.x_correction);
+                let y = device(&value.y_correction);
+                node.u16(if x.is_none() && y.is_none() { 1 } else { 3 })
+                    .i16(self.scale(value.x))
+                    .i16(self.scale(value.y));
+                if x.is_some() || y.is_some() {
+                    node.link(x).link(y);
+                }
+            }
+        }
+        node
+    }
+
+    fn mark1s(&self, value: &positioning::Mark1s, pairs: &[(GlyphID, usize)]) -> Option<Node> {
+        let mut node = Node::new();
+        node.u16(pairs.len() as u16);
+        for &(_, index) in pairs.iter() {
+            let record = value.records.get(index)?;
+            node.u16(record.class_id)
+                .link(Some(self.anchor(&record.anchor)));
+        }
+        Some(node)
+    }
+
+    #[inline]
+    fn scale(&self, value: i16) -> i16 {
+        (value as f32 * self.scale).round() as i16
+    }
+
+    fn value(&self, node: &mut Node, flags: u16, value: Option<&Single>) {
+        let values = value
+            .map(|value| {
+                [
+                    value.x_placement,
+                    value.y_placement,
+                    value.x_advance,
+                    value.y_advance,
+                ]
+            })
+            .unwrap_or_default();
+        for (i, value) in values.iter().enumerate() {
+            if flags & (1 << i) != 0 {
+                node.i16(self.scale(value.unwrap_or(0)));
+            }
+        }
+    }
+}
+
+impl Subtable for substitution::Type {
+    const EXTENSION: u16 = 7;
+
+    fn extension(&self) -> Option<(u16, u32)> {
+        match self {
+            Self::ExtensionSubstitution(table) => Some((table.r#type, table.offset)),
+            _ => None,
+        }
+    }
+
+    fn actions(&self) -> Vec<u16> {
+        match self {
+            Self::ContextualSubstitution(table) => context_actions(table),
+            Self::ChainedContextualSubstitution(table) => chained_context_actions(table),
+            _ => vec![],
+        }
+    }
+
+    fn rewrite(&self, remap: &Remap) -> Option<Node> {
+        use opentype::tables::glyph_substitution::SingleSubstitution;
+
+        let mut node = Node::new();
+        match self {
+            Self::SingleSubstitution(table) => {
+                let mut pairs = match table {
+                    SingleSubstitution::Format1(table) => uncover(&table.coverage)
+                        .into_iter()
+                        .filter_map(|(glyph_id, _)| {
+                            let other_id =
+                                (glyph_id as i32 + table.delta_glyph_id as i32) as GlyphID;
+                            Some((remap.glyph(glyph_id)?, remap.glyph(other_id)?))
+                        })
+                        .collect::<Vec<_>>(),
+                    SingleSubstitution::Format2(table) => remap
+                        .coverage(&table.coverage)
+                        .into_iter()
+                        .filter_map(|(glyph_id, index)| {
+                            Some((glyph_id, remap.glyph(*table.glyph_ids.get(index)?)?))
+                        })
+                        .collect(),
+                };
+                if pairs.is_empty() {
+                    return None;
+                }
+                pairs.sort();
+                node.u16(2)
+                    .link(Some(coverage(&first(&pairs))))
+                    .u16(pairs.len() as u16);
+                pairs.iter().for_each(|&(_, glyph_id)| {
+                    node.u16(glyph_id);
+                });
+            }
+            Self::MultipleSubstitution(table) => {
+                let pairs = remap
+                    .coverage(&table.coverage)
+                    .into_iter()
+                    .filter_map(|(glyph_id, index)| {
+                        Some((
+                            glyph_id,
+                            remap.glyphs(&table.records.get(index)?.glyph_ids)?,
+                        ))
+                    })
+                    .collect::<Vec<_>>();
+                if pairs.is_empty() {
+                    return None;
+                }
+                node.u16(1)
+                    .link(Some(coverage(&first(&pairs))))
+                    .u16(pairs.len() as u16);
+                for (_, glyph_ids) in pairs {
+                    node.link(Some(sequence(&glyph_ids)));
+                }
+            }
+            Self::AlternateSubstitution(table) => {
+                let pairs = remap
+                    .coverage(&table.coverage)
+                    .into_iter()
+                    .filter_map(|(glyph_id, index)| {
+                        let glyph_ids = table
+                            .records
+                            .get(index)?
+                            .glyph_ids
+                            .iter()
+                            .filter_map(|&glyph_id| remap.glyph(glyph_id))
+                            .collect::<Vec<_>>();
+                        (!glyph_ids.is_empty()).then_some((glyph_id, glyph_ids))
+                    })
+                    .collect::<Vec<_>>();
+                if pairs.is_empty() {
+                    return None;
+                }
+                node.u16(1)
+                    .link(Some(coverage(&first(&pairs))))
+                    .u16(pairs.len() as u16);
+                for (_, glyph_ids) in pairs {
+                    node.link(Some(sequence(&glyph_ids)));
+                }
+            }
+            Self::LigatureSubstitution(table) => {
+                let pairs = remap
+                    .coverage(&table.coverage)
+                    .into_iter()
+                    .filter_map(|(glyph_id, index)| {
+                        let records = table
+                            .records
+                            .get(index)?
+                            .records
+                            .iter()
+                            .filter_map(|record| {
+                                Some((
+                                    remap.glyph(record.glyph_id)?,
+                                    remap.glyphs(&record.glyph_ids)?,
+                                ))
+                            })
+                            .collect::<Vec<_>>();
+                        (!records.is_empty()).then_some((glyph_id, records))
+                    })
+                    .collect::<Vec<_>>();
+                if pairs.is_empty() {
+                    return None;
+                }
+                node.u16(1)
+                    .link(Some(coverage(&first(&pairs))))
+                    .u16(pairs.len() as u16);
+                for (_, records) in pairs {
+                    let mut child = Node::new();
+                    child.u16(records.len() as u16);
+                    for (glyph_id, glyph_ids) in records {
+                        let mut grandchild = Node::new();
+                        grandchild.u16(glyph_id).u16(glyph_ids.len() as u16 + 1);
+                        glyph_ids.iter().for_each(|&glyph_id| {
+                            grandchild.u16(glyph_id);
+                        });
+                        child.link(Some(grandchild));
+                    }
+                    node.link(Some(child));
+                }
+            }
+            Self::ContextualSubstitution(table) => return context(table, remap),
+            Self::ChainedContextualSubstitution(table) => return chained_context(table, remap),
+            Self::ExtensionSubstitution(_) => return None,
+            Self::ReverseChainedContextualSubstibution(table) => {
+                let pairs = remap
+                    .coverage(&table.coverage)
+                    .into_iter()
+                    .filter_map(|(glyph_id, index)| {
+                        Some((glyph_id, remap.glyph(*table.glyph_ids.get(index)?)?))
+                    })
+                    .collect::<Vec<_>>();
+                if pairs.is_empty() {
+                    return None;
+                }
+                let backward_coverages = remap.coverages(&table.backward_coverages)?;
+                let forward_coverages = remap.coverages(&table.forward_coverages)?;
+                node.u16(1).link(Some(coverage(&first(&pairs))));
+                node.u16(backward_coverages.len() as u16);
+                backward_coverages.into_iter().for_each(|value| {
+                    node.link(Some(value));
+                });
+                node.u16(forward_coverages.len() as u16);
+                forward_coverages.into_iter().for_each(|value| {
+                    node.link(Some(value));
+                });
+                node.u16(pairs.len() as u16);
+                pairs.iter().for_each(|&(_, glyph_id)| {
+                    node.u16(glyph_id);
+                });
+            }
+        }
+        Some(node)
+    }
+}
+
+impl Subtable for positioning::Type {
+    const EXTENSION: u16 = 9;
+
+    fn extension(&self) -> Option<(u16, u32)> {
+        match self {
+            Self::ExtensionPositioning(table) => Some((table.r#type, table.offset)),
+            _ => None,
+        }
+    }
+
+    fn actions(&self) -> Vec<u16> {
+        match self {
+            Self::ContextualPositioning(table) => context_actions(table),
+            Self::ChainedContextualPositioning(table) => chained_context_actions(table),
+            _ => vec![],
+        }
+    }
+
+    // Device and variation corrections of value records are left out, as their
+    // offsets are relative to the subtable rather than to the value record.
+    fn rewrite(&self, remap: &Remap) -> Option<Node> {
+        use opentype::tables::glyph_positioning::{PairAdjustment, SingleAdjustment};
+
+        let mut node = Node::new();
+        match self {
+            Self::SingleAdjustment(SingleAdjustment::Format1(table)) => {
+                let glyph_ids = first(&remap.coverage(&table.coverage));
+                if glyph_ids.is_empty() {
+                    return None;
+                }
+                let flags = table.value_flags.0 & 0x000F;
+                node.u16(1).link(Some(coverage(&glyph_ids))).u16(flags);
+                remap.value(&mut node, flags, Some(&table.value));
+            }
+            Self::SingleAdjustment(SingleAdjustment::Format2(table)) => {
+                let pairs = remap.coverage(&table.coverage);
+                if pairs.is_empty() {
+                    return None;
+                }
+                let flags = table.value_flags.0 & 0x000F;
+                node.u16(2)
+                    .link(Some(coverage(&first(&pairs))))
+                    .u16(flags)
+                    .u16(pairs.len() as u16);
+                for (_, index) in pairs {
+                    remap.value(&mut node, flags, table.values.get(index));
+                }
+            }
+            Self::PairAdjustment(PairAdjustment::Format1(table)) => {
+                let pairs = remap
+                    .coverage(&table.coverage)
+                    .into_iter()
+                    .filter_map(|(glyph_id, index)| {
+                        let mut records = table
+                            .records
+                            .get(index)?
+                            .records
+                            .iter()
+                            .filter_map(|record| Some((remap.glyph(record.glyph2_id)?, record)))
+                            .collect::<Vec<_>>();
+                        records.sort_by_key(|(glyph_id, _)| *glyph_id);
+                        (!records.is_empty()).then_some((glyph_id, records))
+                    })
+                    .collect::<Vec<_>>();
+                if pairs.is_empty() {
+                    return None;
+                }
+                let flags1 = table.value1_flags.0 & 0x000F;
+                let flags2 = table.value2_flags.0 & 0x000F;
+                node.u16(1)
+                    .link(Some(coverage(&first(&pairs))))
+                    .u16(flags1)
+                    .u16(flags2)
+                    .u16(pairs.len() as u16);
+                for (_, records) in pairs {
+                    let mut child = Node::new();
+                    child.u16(records.len() as u16);
+                    for (glyph_id, record) in records {
+                        child.u16(glyph_id);
+                        remap.value(&mut child, flags1, record.value1.as_ref());
+                        remap.value(&mut child, flags2, record.value2.as_ref());
+                    }
+                    node.link(Some(child));
+                }
+            }
+            Self::PairAdjustment(PairAdjustment::Format2(table)) => {
+                let glyph_ids = first(&remap.coverage(&table.coverage));
+                if glyph_ids.is_empty() {
+                    return None;
+                }
+                let flags1 = table.value1_flags.0 & 0x000F;
+                let flags2 = table.value2_flags.0 & 0x000F;
+                node.u16(2)
+                    .link(Some(coverage(&glyph_ids)))
+                    .u16(flags1)
+                    .u16(flags2)
+                    .link(Some(class(&remap.class(&table.class1))))
+                    .link(Some(class(&remap.class(&table.class2))))
+                    .u16(table.class1_count)
+                    .u16(table.class2_count);
+                for records in table.records.iter() {
+                    for record in records.records.iter() {
+                        remap.value(&mut node, flags1, record.value1.as_ref());
+                        remap.value(&mut node, flags2, record.value2.as_ref());
+                    }
+                }
+            }
+            Self::CursiveAttachment(table) => {
+                let pairs = remap.coverage(&table.coverage);
+                if pairs.is_empty() {
+                    return None;
+                }
+                node.u16(1)
+                    .link(Some(coverage(&first(&pairs))))
+                    .u16(pairs.len() as u16);
+                for (_, index) in pairs {
+                    let connection = table.connections.get(index)?;
+                    node.link(
+                        connection
+                            .start_anchor
+                            .as_ref()
+                            .map(|value| remap.anchor(value)),
+                    );
+                    node.link(
+                        connection
+                            .end_anchor
+                            .as_ref()
+                            .map(|value| remap.anchor(value)),
+                    );
+                }
+            }
+            Self::MarkToBaseAttachment(table) => {
+                let marks = remap.coverage(&table.mark_coverage);
+                let bases = remap.coverage(&table.base_coverage);
+                if marks.is_empty() || bases.is_empty() {
+                    return None;
+                }
+                let mut child = Node::new();
+                child.u16(bases.len() as u16);
+                for &(_, index) in bases.iter() {
+                    for value in table.bases.records.get(index)?.anchors.iter() {
+                        child.link(value.as_ref().map(|value| remap.anchor(value)));
+                    }
+                }
+                node.u16(1)
+                    .link(Some(coverage(&first(&marks))))
+                    .link(Some(coverage(&first(&bases))))
+                    .u16(table.mark_class_count)
+                    .link(Some(remap.mark1s(&table.marks, &marks)?))
+                    .link(Some(child));
+            }
+            Self::MarkToLigatureAttachment(table) => {
+                let marks = remap.coverage(&table.mark_coverage);
+                let ligatures = remap.coverage(&table.ligature_coverage);
+                if marks.is_empty() || ligatures.is_empty() {
+                    return None;
+                }
+                let mut child = Node::new();
+                child.u16(ligatures.len() as u16);
+                for &(_, index) in ligatures.iter() {
+                    let ligature = table.ligatures.records.get(index)?;
+                    let mut grandchild = Node::new();
+                    grandchild.u16(ligature.components.len() as u16);
+                    for component in ligature.components.iter() {
+                        for value in component.anchors.iter() {
+                            grandchild.link(value.as_ref().map(|value| remap.anchor(value)));
+                        }
+                    }
+                    child.link(Some(grandchild));
+                }
+                node.u16(1)
+                    .link(Some(coverage(&first(&marks))))
+                    .link(Some(coverage(&first(&ligatures))))
+                    .u16(table.mark_class_count)
+                    .link(Some(remap.mark1s(&table.marks, &marks)?))
+                    .link(Some(child));
+            }
+            Self::MarkToMarkAttachment(table) => {
+                let marks1 = remap.coverage(&table.mark1_coverage);
+                let marks2 = remap.coverage(&table.mark2_coverage);
+                if marks1.is_empty() || marks2.is_empty() {
+                    return None;
+                }
+                let mut child = Node::new();
+                child.u16(marks2.len() as u16);
+                for &(_, index) in marks2.iter() {
+                    for value in table.mark2s.records.get(index)?.anchors.iter() {
+                        child.link(value.as_ref().map(|value| remap.anchor(value)));
+                    }
+                }
+                node.u16(1)
+                    .link(Some(coverage(&first(&marks1))))
+                    .link(Some(coverage(&first(&marks2))))
+                    .u16(table.mark_class_count)
+                    .link(Some(remap.mark1s(&table.mark1s, &marks1)?))
+                    .link(Some(child));
+            }
+            Self::ContextualPositioning(table) => return context(table, remap),
+            Self::ChainedContextualPositioning(table) => return chained_context(table, remap),
+            Self::ExtensionPositioning(_) => return None,
+        }
+        Some(node)
+    }
+}
+
+fn encode(layout: &Layout, extension: Option<u16>) -> Node {
+    let language = |value: &Language| {
+        let mut node = Node::new();
+        node.u16(0)
+            .u16(value.required)
+            .u16(value.features.len() as u16);
+        value.features.iter().for_each(|&index| {
+            node.u16(index);
+        });
+        node
+    };
+
+    let mut scripts = Node::new();
+    scripts.u16(layout.scripts.len() as u16);
+    for script in layout.scripts.iter() {
+        let mut node = Node::new();
+        node.link(script.default.as_ref().map(language))
+            .u16(script.languages.len() as u16);
+        for (tag, value) in script.languages.iter() {
+            node.bytes(&tag.0).link(Some(language(value)));
+        }
+        scripts.bytes(&script.tag.0).link(Some(node));
+    }
+
+    let mut features = Node::new();
+    features.u16(layout.features.len() as u16);
+    for (tag, indices) in layout.features.iter() {
+        let mut node = Node::new();
+        node.u16(0).u16(indices.len() as u16);
+        indices.iter().for_each(|&index| {
+            node.u16(index);
+        });
+        features.bytes(&tag.0).link(Some(node));
+    }
+
+    let mut lookups = Node::new();
+    lookups.u16(layout.lookups.len() as u16);
+    for lookup in layout.lookups.iter() {
+        let mut node = Node::new();
+        node.u16(extension.unwrap_or(lookup.r#type))
+            .u16(lookup.flags)
+            .u16(lookup.tables.len() as u16);
+        for table in lookup.tables.iter() {
+            match extension {
+                Some(_) => {
+                    let mut child = Node::new();
+                    child
+                        .u16(1)
+                        .u16(lookup.r#type)
+                        .link_wide(Some(table.clone()));
+                    node.link(Some(child));
+                }
+                _ => {
+                    node.link(Some(table.clone()));
+                }
+            }
+        }
+        if let Some(value) = lookup.mark_filtering_set {
+            node.u16(value);
+        }
+        lookups.link(Some(node));
+    }
+
+    let mut node = Node::new();
+    node.u16(1)
+        .u16(0)
+        .link(Some(scripts))
+        .link(Some(features))
+        .link(Some(lookups));
+    node
+}
+
+fn context(table: &Context, remap: &Remap) -> Option<Node> {
+    let mut node = Node::new();
+    match table {
+        Context::Format1(table) => {
+            let pairs = remap
+                .coverage(&table.coverage)
+                .into_iter()
+                .filter_map(|(glyph_id, index)| {
+                    let records = table
+                        .records
+                        .get(index)?
+                        .records
+                        .iter()
+                        .filter_map(|record| {
+                            Some((
+                                remap.glyphs(&record.glyph_ids)?,
+                                remap.actions(&record.actions),
+                            ))
+                        })
+                        .collect::<Vec<_>>();
+                    (!records.is_empty()).then_some((glyph_id, records))
+                })
+                .collect::<Vec<_>>();
+            if pairs.is_empty() {
+                return None;
+            }
+            node.u16(1)
+                .link(Some(coverage(&first(&pairs))))
+                .u16(pairs.len() as u16);
+            for (_, records) in pairs {
+                let mut child = Node::new();
+                child.u16(records.len() as u16);
+                for (glyph_ids, actions) in records {
+                    let mut grandchild = Node::new();
+                    grandchild
+                        .u16(glyph_ids.len() as u16 + 1)
+                        .u16(actions.len() as u16);
+                    glyph_ids.iter().for_each(|&glyph_id| {
+                        grandchild.u16(glyph_id);
+                    });
+                    self::actions(&mut grandchild, &actions);
+                    child.link(Some(grandchild));
+                }
+                node.link(Some(child));
+            }
+        }
+        Context::Format2(table) => {
+            let glyph_ids = first(&remap.coverage(&table.coverage));
+            if glyph_ids.is_empty() {
+                return None;
+            }
+            node.u16(2)
+                .link(Some(coverage(&glyph_ids)))
+                .link(Some(class(&remap.class(&table.class))))
+                .u16(table.records.len() as u16);
+            for records in table.records.iter() {
+                node.link(records.as_ref().map(|records| {
+                    let mut child = Node::new();
+                    child.u16(records.records.len() as u16);
+                    for record in records.records.iter() {
+                        let actions = remap.actions(&record.actions);
+                        let mut grandchild = Node::new();
+                        grandchild
+                            .u16(record.indices.len() as u16 + 1)
+                            .u16(actions.len() as u16);
+                        record.indices.iter().for_each(|&index| {
+                            grandchild.u16(index);
+                        });
+                        self::actions(&mut grandchild, &actions);
+                        child.link(Some(grandchild));
+                    }
+                    child
+                }));
+            }
+        }
+        Context::Format3(table) => {
+            let coverages = remap.coverages(&table.coverages)?;
+            let actions = remap.actions(&table.actions);
+            node.u16(3)
+                .u16(coverages.len() as u16)
+                .u16(actions.len() as u16);
+            coverages.into_iter().for_each(|value| {
+                node.link(Some(value));
+            });
+            self::actions(&mut node, &actions);
+        }
+    }
+    Some(node)
+}
+
+fn chained_context(table: &ChainedContext, remap: &Remap) -> Option<Node> {
+    let mut node = Node::new();
+    match table {
+        ChainedContext::Format1(table) => {
+            let pairs = remap
+                .coverage(&table.coverage)
+                .into_iter()
+                .filter_map(|(glyph_id, index)| {
+                    let records = table
+                        .records
+                        .get(index)?
+                        .records
+                        .iter()
+                        .filter_map(|record| {
+                            Some((
+                                remap.glyphs(&record.backward_glyph_ids)?,
+                                remap.glyphs(&record.glyph_ids)?,
+                                remap.glyphs(&record.forward_glyph_ids)?,
+                                remap.actions(&record.actions),
+                            ))
+                        })
+                        .collect::<Vec<_>>();
+                    (!records.is_empty()).then_some((glyph_id, records))
+                })
+                .collect::<Vec<_>>();
+            if pairs.is_empty() {
+                return None;
+            }
+            node.u16(1)
+                .link(Some(coverage(&first(&pairs))))
+                .u16(pairs.len() as u16);
+            for (_, records) in pairs {
+                let mut child = Node::new();
+                child.u16(records.len() as u16);
+                for (backward, glyph_ids, forward, actions) in records {
+                    let mut grandchild = Node::new();
+                    sequence_into(&mut grandchild, &backward);
+                    grandchild.u16(glyph_ids.len() as u16 + 1);
+                    glyph_ids.iter().for_each(|&glyph_id| {
+                        grandchild.u16(glyph_id);
+                    });
+                    sequence_into(&mut grandchild, &forward);
+                    grandchild.u16(actions.len() as u16);
+                    self::actions(&mut grandchild, &actions);
+                    child.link(Some(grandchild));
+                }
+                node.link(Some(child));
+            }
+        }
+        ChainedContext::Format2(table) => {
+            let glyph_ids = first(&remap.coverage(&table.coverage));
+            if glyph_ids.is_empty() {
+                return None;
+            }
+            node.u16(2)
+                .link(Some(coverage(&glyph_ids)))
+                .link(Some(class(&remap.class(&table.backward_class))))
+                .link(Some(class(&remap.class(&table.class))))
+                .link(Some(class(&remap.class(&table.forward_class))))
+                .u16(table.records.len() as u16);
+            for records in table.records.iter() {
+                node.link(records.as_ref().map(|records| {
+                    let mut child = Node::new();
+                    child.u16(records.records.len() as u16);
+                    for record in records.records.iter() {
+                        let actions = remap.actions(&record.actions);
+                        let mut grandchild = Node::new();
+                        sequence_into(&mut grandchild, &record.backward_indices);
+                        grandchild.u16(record.indices.len() as u16 + 1);
+                        record.indices.iter().for_each(|&index| {
+                            grandchild.u16(index);
+                        });
+                        sequence_into(&mut grandchild, &record.forward_indices);
+                        grandchild.u16(actions.len() as u16);
+                        self::actions(&mut grandchild, &actions);
+                        child.link(Some(grandchild));
+                    }
+                    child
+                }));
+            }
+        }
+        ChainedContext::Format3(table) => {
+            let backward_coverages = remap.coverages(&table.backward_coverages)?;
+            let coverages = remap.coverages(&table.coverages)?;
+            let forward_coverages = remap.coverages(&table.forward_coverages)?;
+            let actions = remap.actions(&table.actions);
+            node.u16(3);
+            for values in [backward_coverages, coverages, forward_coverages] {
+                node.u16(values.len() as u16);
+                values.into_iter().for_each(|value| {
+                    node.link(Some(value));
+                });
+            }
+            node.u16(actions.len() as u16);
+            self::actions(&mut node, &actions);
+        }
+    }
+    Some(node)
+}
+
+fn context_actions(table: &Context) -> Vec<u16> {
+    let mut values = vec![];
+    match table {
+        Context::Format1(table) => {
+            for records in table.records.iter() {
+                for record in records.records.iter() {
+                    values.extend(record.actions.iter().map(|action| action.lookup_index));
+                }
+            }
+        }
+        Context::Format2(table) => {
+            for records in table.records.iter().flatten() {
+                for record in records.records.iter() {
+                    values.extend(record.actions.iter().map(|action| action.lookup_index));
+                }
+            }
+        }
+        Context::Format3(table) => {
+            values.extend(table.actions.iter().map(|action| action.lookup_index));
+        }
+    }
+    values
+}
+
+fn chained_context_actions(table: &ChainedContext) -> Vec<u16> {
+    let mut values = vec![];
+    match table {
+        ChainedContext::Format1(table) => {
+            for records in table.records.iter() {
+                for record in records.records.iter() {
+                    values.extend(record.actions.iter().map(|action| action.lookup_index));
+                }
+            }
+        }
+        ChainedContext::Format2(table) => {
+            for records in table.records.iter().flatten() {
+                for record in records.records.iter() {
+                    values.extend(record.actions.iter().map(|action| action.lookup_index));
+                }
+            }
+        }
+        ChainedContext::Format3(table) => {
+            values.extend(table.actions.iter().map(|action| action.lookup_index));
+        }
+    }
+    values
+}
+
+fn actions(node: &mut Node, values: &[Action]) {
+    for action in values.iter() {
+        node.u16(action.position_index).u16(action.lookup_index);
+    }
+}
+
+fn class(values: &BTreeMap<GlyphID, u16>) -> Node {
+    let mut ranges: Vec<(GlyphID, GlyphID, u16)> = vec![];
+    for (&glyph_id, &index) in values.iter().filter(|(_, &index)| index > 0) {
+        match ranges.last_mut() {
+            Some(last) if last.1 + 1 == glyph_id && last.2 == index => last.1 = glyph_id,
+            _ => ranges.push((glyph_id, glyph_id, index)),
+        }
+    }
+    let mut node = Node::new();
+    match (ranges.first(), ranges.last()) {
+        (Some(first), Some(last)) if (last.1 - first.0 + 1) < 3 * ranges.len() as GlyphID => {
+            node.u16(1).u16(first.0).u16(last.1 - first.0 + 1);
+            for glyph_id in first.0..=last.1 {
+                node.u16(values.get(&glyph_id).cloned().unwrap_or(0));
+            }
+        }
+        _ => {
+            node.u16(2).u16(ranges.len() as u16);
+            for (start, end, index) in ranges {
+                node.u16(start).u16(end).u16(index);
+            }
+        }
+    }
+    node
+}
+
+fn coverage(glyph_ids: &[GlyphID]) -> Node {
+    let mut ranges: Vec<(GlyphID, GlyphID, u16)> = vec![];
+    for (index, &glyph_id) in glyph_ids.iter().enumerate() {
+        match ranges.last_mut() {
+            Some(last) if last.1 + 1 == glyph_id => last.1 = glyph_id,
+            _ => ranges.push((glyph_id, glyph_id, index as u16)),
+        }
+    }
+    let mut node = Node::new();
+    if 3 * ranges.len() < glyph_ids.len() {
+        node.u16(2).u16(ranges.len() as u16);
+        for (start, end, index) in ranges {
+            node.u16(start).u16(end).u16(index);
+        }
+    } else {
+        node.u16(1).u16(glyph_ids.len() as u16);
+        glyph_ids.iter().for_each(|&glyph_id| {
+            node.u16(glyph_id);
+        });
+    }
+    node
+}
+
+fn device(value: &Option<Correction>) -> Option<Node> {
+    match value {
+        Some(Correction::Device(value)) => {
+            let mut node = Node::new();
+            node.u16(value.start_size)
+                .u16(value.end_size)
+                .u16(value.format);
+            value.deltas.iter().for_each(|&delta| {
+                node.u16(delta);
+            });
+            Some(node)
+        }
+        _ => None,
+    }
+}
+
+fn first<T, U: Copy>(values: &[(U, T)]) -> Vec<U> {
+    values.iter().map(|(value, _)| *value).collect()
+}
+
+fn sequence(values: &[u16]) -> Node {
+    let mut node = Node::new();
+    sequence_into(&mut node, values);
+    node
+}
+
+fn sequence_into(node: &mut Node, values: &[u16]) {
+    node.u16(values.len() as u16);
+    values.iter().for_each(|&value| {
+        node.u16(value);
+    });
+}
+
+/// Return the glyphs of a coverage table paired with their coverage indices.
+pub fn uncover(value: &Coverage) -> Vec<(GlyphID, usize)> {
+    match value {
+        Coverage::Format1(value) => value
+            .glyph_ids
+            .iter()
+            .enumerate()
+            .map(|(index, &glyph_id)| (glyph_id, index))
+            .collect(),
+        Coverage::Format2(value) => value
+            .records
+            .iter()
+            .flat_map(|record| {
+                (record.start_glyph_id..=record.end_glyph_id).map(move |glyph_id| {
+                    (
+                        glyph_id,
+                        (record.index + glyph_id - record.start_glyph_id) as usize,
+                    )
+                })
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,431 @@
+//! Rewriting of tables.
+
+pub mod compact;
+pub mod layout;
+pub mod node;
+pub mod outline;
+pub mod type2;
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Cursor, Result};
+
+use opentype::truetype::tables::offsets::{Header, Offsets, Record};
+use opentype::truetype::tables::{FontHeader, GlyphMapping};
+use opentype::truetype::{GlyphID, Tag};
+
+use crate::formats::opentype::font::{pad, search};
+use crate::formats::opentype::kerning;
+
+/// Assemble a font out of tables.
+pub fn assemble(version: u32, mut tables: Vec<(Tag, Vec<u8>)>) -> Result<Vec<u8>> {
+    use crate::Write;
+
+    tables.sort_by_key(|(tag, _)| *tag);
+    for pair in tables.windows(2) {
+        if pair[0].0 == pair[1].0 {
+            raise!("found duplicate tables with {:?}", pair[0].0);
+        }
+    }
+    let (search_range, entry_selector, range_shift) = search(tables.len());
+    let mut offsets = Offsets {
+        header: Header {
+            version,
+            table_count: tables.len() as u16,
+            search_range,
+            entry_selector,
+            range_shift,
+        },
+        records: Vec::with_capacity(tables.len()),
+    };
+    let mut offset = 12 + 16 * tables.len();
+    for (tag, data) in tables.iter_mut() {
+        if tag == b"head" {
+            match data.get_mut(8..12) {
+                Some(value) => value.copy_from_slice(&[0; 4]),
+                _ => raise!("found a malformed font header"),
+            }
+        }
+        offsets.records.push(Record {
+            tag: *tag,
+            checksum: 0,
+            offset: offset as u32,
+            size: data.len() as u32,
+        });
+        offset += (data.len() + 3) & !3;
+    }
+
+    let mut tape = Cursor::new(Vec::with_capacity(offset));
+    tape.give(&offsets)?;
+    for (_, data) in tables.iter() {
+        tape.give_bytes(data)?;
+        pad(&mut tape, data.len())?;
+    }
+    for record in offsets.records.iter_mut() {
+        record.checksum = record.checksum(&mut tape)?;
+    }
+    tape.set_position(0);
+    tape.give(&offsets)?;
+    tape.set_position(0);
+    let checksum_adjustment = FontHeader::checksum(&mut tape)?;
+    let mut data = tape.into_inner();
+    if let Some(record) = offsets.records.iter().find(|record| record.tag == b"head") {
+        let position = record.offset as usize + 8;
+        data[position..(position + 4)].copy_from_slice(&checksum_adjustment.to_be_bytes());
+    }
+    Ok(data)
+}
+
+/// Encode a character-to-glyph mapping.
+///
+/// The basic multilingual plane is encoded using format 4, and, if there are
+/// other characters, the whole repertoire is additionally encoded using format
+/// 12.
+pub fn character_mapping(mapping: &BTreeMap<u32, GlyphID>) -> Result<Vec<u8>> {
+    let mut segments: Vec<(u32, u32, u32)> = vec![];
+    for (&character, &glyph_id) in mapping.iter() {
+        match segments.last_mut() {
+            Some(last)
+                if last.1 + 1 == character && last.2 + (character - last.0) == glyph_id as u32 =>
+            {
+                last.1 = character;
+            }
+            _ => segments.push((character, character, glyph_id as u32)),
+        }
+    }
+
+    let mut basic = segments
+        .iter()
+        .filter(|segment| segment.0 < 0xFFFF)
+        .map(|&(start, end, glyph_id)| (start, end.min(0xFFFE), glyph_id))
+        .collect::<Vec<_>>();
+    basic.push((0xFFFF, 0xFFFF, 0));
+    let count = basic.len();
+    let size = 16 + 8 * count;
+    if size > 0xFFFF {
+        raise!("found too many segments for a character-to-glyph mapping");
+    }
+    let (search_range, entry_selector, range_shift) = search(count);
+    let mut format4 = vec![];
+    for value in [4, size as u16, 0, 2 * count as u16] {
+        format4.extend(value.to_be_bytes());
+    }
+    for value in [search_range / 8, entry_selector, range_shift / 8] {
+        format4.extend(value.to_be_bytes());
+    }
+    basic
+        .iter()
+        .for_each(|segment| format4.extend((segment.1 as u16).to_be_bytes()));
+    format4.extend([0, 0]);
+    basic
+        .iter()
+        .for_each(|segment| format4.extend((segment.0 as u16).to_be_bytes()));
+    basic.iter().for_each(|segment| {
+        let delta = if segment.0 == 0xFFFF {
+            1
+        } else {
+            (segment.2 as u16).wrapping_sub(segment.0 as u16)
+        };
+        format4.extend(delta.to_be_bytes());
+    });
+    basic.iter().for_each(|_| format4.extend([0, 0]));
+
+    let mut format12 = vec![];
+    if segments.iter().any(|segment| segment.1 > 0xFFFF) {
+        format12.extend([0, 12, 0, 0]);
+        format12.extend((16 + 12 * segments.len() as u32).to_be_bytes());
+        format12.extend([0, 0, 0, 0]);
+        format12.extend((segments.len() as u32).to_be_bytes());
+        for (start, end, glyph_id) in segments.iter() {
+            format12.extend(start.to_be_bytes());
+            format12.extend(end.to_be_bytes());
+            format12.extend(glyph_id.to_be_bytes());
+        }
+    }
+
+    let records: &[(u16, u16, bool)] = if format12.is_empty() {
+        &[(0, 3, false), (3, 1, false)]
+    } else {
+        &[(0, 4, true), (3, 1, false), (3, 10, true)]
+    };
+    let start = 4 + 8 * records.len() as u32;
+    let mut data = vec![0, 0];
+    data.extend((records.len() as u16).to_be_bytes());
+    for &(platform_id, encoding_id, extended) in records.iter() {
+        data.extend(platform_id.to_be_bytes());
+        data.extend(encoding_id.to_be_bytes());
+        let offset = if extended {
+            start + format4.len() as u32
+        } else {
+            start
+        };
+        data.extend(offset.to_be_bytes());
+    }
+    data.extend(format4);
+    data.extend(format12);
+    Ok(data)
+}
+
+/// Update the character range of an OS/2 table.
+pub fn windows_metrics(data: &mut [u8], mapping: &BTreeMap<u32, GlyphID>) {
+    if data.len() >= 68 {
+        let first = mapping.keys().next().cloned().unwrap_or(0);
+        let last = mapping.keys().last().cloned().unwrap_or(0);
+        data[64..66].copy_from_slice(&(first.min(0xFFFF) as u16).to_be_bytes());
+        data[66..68].copy_from_slice(&(last.min(0xFFFF) as u16).to_be_bytes());
+    }
+}
+
+/// Return the components of a glyph given its data as pairs of positions and
+/// glyph identifiers.
+pub fn components(data: &[u8]) -> Result<Vec<(usize, GlyphID)>> {
+    let mut values = vec![];
+    if data.len() < 10 || i16::from_be_bytes([data[0], data[1]]) >= 0 {
+        return Ok(values);
+    }
+    let mut position = 10;
+    loop {
+        let (flags, glyph_id) = match data.get(position..(position + 4)) {
+            Some(value) => (
+                u16::from_be_bytes([value[0], value[1]]),
+                u16::from_be_bytes([value[2], value[3]]),
+            ),
+            _ => raise!("found a malformed composite glyph"),
+        };
+        values.push((position + 2, glyph_id));
+        position += 4;
+        position += if flags & 0x0001 > 0 { 4 } else { 2 };
+        if flags & 0x0008 > 0 {
+            position += 2;
+        } else if flags & 0x0040 > 0 {
+            position += 4;
+        } else if flags & 0x0080 > 0 {
+            position += 8;
+        }
+        if flags & 0x0020 == 0 {
+            break;
+        }
+    }
+    Ok(values)
+}
+
+/// Rewrite glyph data retaining the given glyphs in the given order.
+///
+/// The function returns the glyph data, the glyph-to-location mapping, and the
+/// format of the latter.
+pub fn glyph_data(
+    data: &[u8],
+    mapping: &GlyphMapping,
+    glyph_ids: &[GlyphID],
+) -> Result<(Vec<u8>, Vec<u8>, i16)> {
+    let offsets = offsets(mapping);
+    let glyph_indices = glyph_ids
+        .iter()
+        .enumerate()
+        .map(|(i, &glyph_id)| (glyph_id, i as GlyphID))
+        .collect::<HashMap<_, _>>();
+    let mut values = Vec::with_capacity(glyph_ids.len());
+    for &glyph_id in glyph_ids.iter() {
+        let range = match (
+            offsets.get(glyph_id as usize),
+            offsets.get(glyph_id as usize + 1),
+        ) {
+            (Some(&start), Some(&end)) if start <= end && end <= data.len() => start..end,
+            _ => raise!("found no data for glyph {}", glyph_id),
+        };
+        let mut value = data[range].to_vec();
+        for (position, glyph_id) in components(&value)? {
+            match glyph_indices.get(&glyph_id) {
+                Some(index) => {
+                    value[position..(position + 2)].copy_from_slice(&index.to_be_bytes())
+                }
+                _ => raise!("found no glyph {} among the retained ones", glyph_id),
+            }
+        }
+        values.push(value);
+    }
+    Ok(locate(&values))
+}
+
+/// Concatenate glyphs.
+///
+/// The function returns the glyph data, the glyph-to-location mapping, and the
+/// format of the latter.
+pub fn locate(glyphs: &[Vec<u8>]) -> (Vec<u8>, Vec<u8>, i16) {
+    let mut glyph_data = vec![];
+    let mut positions = Vec::with_capacity(glyphs.len() + 1);
+    for value in glyphs.iter() {
+        positions.push(glyph_data.len());
+        glyph_data.extend_from_slice(value);
+        if glyph_data.len() % 2 == 1 {
+            glyph_data.push(0);
+        }
+    }
+    positions.push(glyph_data.len());
+    let mut mapping = vec![];
+    let format = if glyph_data.len() <= 2 * 0xFFFF {
+        positions
+            .iter()
+            .for_each(|&value| mapping.extend(((value / 2) as u16).to_be_bytes()));
+        0
+    } else {
+        positions
+            .iter()
+            .for_each(|&value| mapping.extend((value as u32).to_be_bytes()));
+        1
+    };
+    (glyph_data, mapping, format)
+}
+
+/// Return the offsets of glyphs in bytes.
+pub fn offsets(mapping: &GlyphMapping) -> Vec<usize> {
+    match mapping {
+        GlyphMapping::HalfOffsets(values) => {
+            values.iter().map(|&value| 2 * value as usize).collect()
+        }
+        GlyphMapping::Offsets(values) => values.iter().map(|&value| value as usize).collect(),
+    }
+}
+
+/// Encode horizontal metrics.
+///
+/// The function returns the metrics and the number of full records.
+pub fn horizontal_metrics(values: &[(u16, i16)]) -> (Vec<u8>, u16) {
+    let mut count = values.len();
+    while count > 1 && values[count - 1].0 == values[count - 2].0 {
+        count -= 1;
+    }
+    let mut data = Vec::with_capacity(4 * count + 2 * (values.len() - count));
+    for (i, &(advance_width, left_side_bearing)) in values.iter().enumerate() {
+        if i < count {
+            data.extend(advance_width.to_be_bytes());
+        }
+        data.extend(left_side_bearing.to_be_bytes());
+    }
+    (data, count as u16)
+}
+
+/// Rewrite a legacy kerning table given a mapping of glyphs.
+///
+/// Only horizontal subtables of format 0 are retained, and pairs with glyphs
+/// left out of the mapping are dropped. The function returns nothing if no
+/// pairs remain.
+pub fn kerning(data: &[u8], glyphs: &HashMap<GlyphID, GlyphID>) -> Result<Option<Vec<u8>>> {
+    let mut subtables = vec![];
+    for (coverage, pairs) in kerning::legacy(data)? {
+        let mut pairs = pairs
+            .into_iter()
+            .filter_map(
+                |(one, other, value)| match (glyphs.get(&one), glyphs.get(&other)) {
+                    (Some(&one), Some(&other)) => Some((one, other, value)),
+                    _ => None,
+                },
+            )
+            .collect::<Vec<_>>();
+        pairs.sort();
+        if !pairs.is_empty() {
+            subtables.push((coverage, pairs));
+        }
+    }
+    if subtables.is_empty() {
+        return Ok(None);
+    }
+    let mut output = vec![];
+    output.extend(0u16.to_be_bytes());
+    output.extend((subtables.len() as u16).to_be_bytes());
+    for (coverage, pairs) in subtables {
+        let size = match u16::try_from(14 + 6 * pairs.len()) {
+            Ok(size) => size,
+            _ => raise!("found an overflow of pairs in a kerning subtable"),
+        };
+        let (search_range, entry_selector, range_shift) = search(pairs.len());
+        for value in [
+            0,
+            size,
+            coverage,
+            pairs.len() as u16,
+            search_range / 16 * 6,
+            entry_selector,
+            range_shift / 16 * 6,
+        ] {
+            output.extend(value.to_be_bytes());
+        }
+        for (one, other, value) in pairs {
+            output.extend(one.to_be_bytes());
+            output.extend(other.to_be_bytes());
+            output.extend(value.to_be_bytes());
+        }
+    }
+    Ok(Some(output))
+}
+
+/// Rewrite a PostScript table retaining the given glyphs in the given order.
+///
+/// Glyph names are retained for versions 1 and 2 by writing version 2, and
+/// other versions are rewritten as version 3.
+pub fn postscript(data: &[u8], glyph_ids: &[GlyphID]) -> Result<Vec<u8>> {
+    if data.len() < 32 {
+        raise!("found a malformed PostScript table");
+    }
+    let mut output = data[..32].to_vec();
+    let indices = match &data[..4] {
+        [0, 1, 0, 0] => glyph_ids.iter().map(|&glyph_id| (glyph_id, None)).collect(),
+        [0, 2, 0, 0] => {
+            let count = match data.get(32..34) {
+                Some(value) => u16::from_be_bytes([value[0], value[1]]) as usize,
+                _ => raise!("found a malformed PostScript table"),
+            };
+            let indices = match data.get(34..(34 + 2 * count)) {
+                Some(value) => value
+                    .chunks_exact(2)
+                    .map(|value| u16::from_be_bytes([value[0], value[1]]))
+                    .collect::<Vec<_>>(),
+                _ => raise!("found a malformed PostScript table"),
+            };
+            let mut names = vec![];
+            let mut position = 34 + 2 * count;
+            while position < data.len() {
+                let size = data[position] as usize;
+                match data.get((position + 1)..(position + 1 + size)) {
+                    Some(value) => names.push(value),
+                    _ => raise!("found a malformed PostScript table"),
+                }
+                position += 1 + size;
+            }
+            let mut values = Vec::with_capacity(glyph_ids.len());
+            for &glyph_id in glyph_ids.iter() {
+                match indices.get(glyph_id as usize) {
+                    Some(&index) if index < 258 => values.push((index, None)),
+                    Some(&index) => match names.get(index as usize - 258) {
+                        Some(&name) => values.push((0, Some(name))),
+                        _ => raise!("found no name for glyph {}", glyph_id),
+                    },
+                    _ => raise!("found no name for glyph {}", glyph_id),
+                }
+            }
+            values
+        }
+        _ => {
+            output[..4].copy_from_slice(&[0, 3, 0, 0]);
+            return Ok(output);
+        }
+    };
+    output[..4].copy_from_slice(&[0, 2, 0, 0]);
+    output.extend((indices.len() as u16).to_be_bytes());
+    let mut names = Vec::<&[u8]>::new();
+    let mut name_indices = HashMap::<&[u8], u16>::new();
+    for (index, name) in indices.iter() {
+        let index = match name {
+            Some(name) => *name_indices.entry(name).or_insert_with(|| {
+                names.push(name);
+                257 + names.len() as u16
+            }),
+            _ => *index,
+        };
+        output.extend(index.to_be_bytes());
+    }
+    for name in names {
+        output.push(name.len() as u8);
+        output.extend_from_slice(name);
+    }
+    Ok(output)
+}
@@ -0,0 +1,91 @@
+use std::collections::VecDeque;
+
+/// A table linked to other tables via offsets.
+#[derive(Clone, Debug, Default)]
+pub struct Node {
+    data: Vec<u8>,
+    links: Vec<(usize, bool, Node)>,
+}
+
+impl Node {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn u16(&mut self, value: u16) -> &mut Self {
+        self.data.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    #[inline]
+    pub fn i16(&mut self, value: i16) -> &mut Self {
+        self.data.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    #[inline]
+    pub fn bytes(&mut self, value: &[u8]) -> &mut Self {
+        self.data.extend_from_slice(value);
+        self
+    }
+
+    /// Add a 16-bit offset to another table, which is null if there is none.
+    #[inline]
+    pub fn link(&mut self, node: Option<Node>) -> &mut Self {
+        self.attach(node, false)
+    }
+
+    /// Add a 32-bit offset to another table, which is null if there is none.
+    #[inline]
+    pub fn link_wide(&mut self, node: Option<Node>) -> &mut Self {
+        self.attach(node, true)
+    }
+
+    /// Lay out the graph.
+    ///
+    /// Tables referred to via 16-bit offsets follow their parents depth first,
+    /// and those referred to via 32-bit offsets are moved to the end. The
+    /// function returns nothing if a 16-bit offset overflows.
+    pub fn pack(self) -> Option<Vec<u8>> {
+        let mut data = vec![];
+        let mut queue = VecDeque::new();
+        place(self, &mut data, &mut queue)?;
+        while let Some((parent, position, node)) = queue.pop_front() {
+            let offset = place(node, &mut data, &mut queue)? - parent;
+            let offset = u32::try_from(offset).ok()?;
+            data[position..(position + 4)].copy_from_slice(&offset.to_be_bytes());
+        }
+        Some(data)
+    }
+
+    fn attach(&mut self, node: Option<Node>, wide: bool) -> &mut Self {
+        let position = self.data.len();
+        self.data
+            .extend_from_slice(if wide { &[0; 4] } else { &[0; 2] });
+        if let Some(node) = node {
+            self.links.push((position, wide, node));
+        }
+        self
+    }
+}
+
+fn place(
+    node: Node,
+    data: &mut Vec<u8>,
+    queue: &mut VecDeque<(usize, usize, Node)>,
+) -> Option<usize> {
+    let start = data.len();
+    data.extend(node.data);
+    for (position, wide, child) in node.links {
+        if wide {
+            queue.push_back((start, start + position, child));
+            continue;
+        }
+        let offset = place(child, data, queue)? - start;
+        let offset = u16::try_from(offset).ok()?;
+        data[(start + position)..(start + position + 2)].copy_from_slice(&offset.to_be_bytes());
+    }
+    Some(start)
+}
@@ -0,0 +1,306 @@
+use std::io::Result;
+
+const ARGUMENTS_ARE_WORDS: u16 = 0x0001;
+const ARGUMENTS_ARE_OFFSETS: u16 = 0x0002;
+const HAS_SCALE: u16 = 0x0008;
+const HAS_MORE_COMPONENTS: u16 = 0x0020;
+const HAS_SCALES: u16 = 0x0040;
+const HAS_MATRIX: u16 = 0x0080;
+const HAS_INSTRUCTIONS: u16 = 0x0100;
+
+const ON_CURVE: u8 = 0x01;
+const X_SHORT: u8 = 0x02;
+const Y_SHORT: u8 = 0x04;
+const REPEAT: u8 = 0x08;
+const X_SAME_OR_POSITIVE: u8 = 0x10;
+const Y_SAME_OR_POSITIVE: u8 = 0x20;
+
+/// Scale a glyph given its data and leave out its instructions.
+pub fn transform(data: &[u8], scale: f32) -> Result<Vec<u8>> {
+    if data.is_empty() {
+        return Ok(vec![]);
+    }
+    let contour_count = match data.get(0..10) {
+        Some(value) => i16::from_be_bytes([value[0], value[1]]),
+        _ => raise!("found a malformed glyph"),
+    };
+    let mut output = data[0..2].to_vec();
+    for i in 0..4 {
+        let value = i16::from_be_bytes([data[2 + 2 * i], data[3 + 2 * i]]);
+        output.extend(round(value as f32 * scale).to_be_bytes());
+    }
+    if contour_count < 0 {
+        compound(data, scale, &mut output)?;
+    } else {
+        simple(data, contour_count as usize, scale, &mut output)?;
+    }
+    Ok(output)
+}
+
+/// Encode a simple glyph given its contours as points with on-curve flags.
+///
+/// The bounding box is computed from the points, and no instructions are
+/// written.
+pub fn encode(contours: &[Vec<(i16, i16, bool)>]) -> Vec<u8> {
+    let points = contours.iter().flatten().collect::<Vec<_>>();
+    if points.is_empty() {
+        return vec![];
+    }
+    let mut output = (contours.len() as i16).to_be_bytes().to_vec();
+    output.extend(
+        points
+            .iter()
+            .map(|point| point.0)
+            .min()
+            .unwrap()
+            .to_be_bytes(),
+    );
+    output.extend(
+        points
+            .iter()
+            .map(|point| point.1)
+            .min()
+            .unwrap()
+            .to_be_bytes(),
+    );
+    output.extend(
+        points
+            .iter()
+            .map(|point| point.0)
+            .max()
+            .unwrap()
+            .to_be_bytes(),
+    );
+    output.extend(
+        points
+            .iter()
+            .map(|point| point.1)
+            .max()
+            .unwrap()
+            .to_be_bytes(),
+    );
+    let mut end = 0;
+    for contour in contours.iter() {
+        end += contour.len();
+        output.extend((end as u16 - 1).to_be_bytes());
+    }
+    output.extend([0, 0]);
+    let flags = points
+        .iter()
+        .map(|point| if point.2 { ON_CURVE } else { 0 })
+        .collect::<Vec<_>>();
+    let delta = |value: fn(&&(i16, i16, bool)) -> i16| -> Vec<i32> {
+        let mut last = 0;
+        points
+            .iter()
+            .map(|point| {
+                let value = value(point) as i32;
+                let delta = value - last;
+                last = value;
+                delta
+            })
+            .collect()
+    };
+    let xs = delta(|point| point.0);
+    let ys = delta(|point| point.1);
+    coordinates(&flags, &xs, &ys, &mut output);
+    output
+}
+
+/// Return the left edge of a glyph given its data.
+pub fn left(data: &[u8]) -> Option<i16> {
+    data.get(2..4)
+        .map(|value| i16::from_be_bytes([value[0], value[1]]))
+}
+
+fn compound(data: &[u8], scale: f32, output: &mut Vec<u8>) -> Result<()> {
+    let mut position = 10;
+    loop {
+        let flags = u16::from_be_bytes([get(data, position)?, get(data, position + 1)?]);
+        let glyph_id = [get(data, position + 2)?, get(data, position + 3)?];
+        position += 4;
+        let (x, y) = if flags & ARGUMENTS_ARE_WORDS > 0 {
+            position += 4;
+            (
+                i16::from_be_bytes([get(data, position - 4)?, get(data, position - 3)?]) as i32,
+                i16::from_be_bytes([get(data, position - 2)?, get(data, position - 1)?]) as i32,
+            )
+        } else if flags & ARGUMENTS_ARE_OFFSETS > 0 {
+            position += 2;
+            (
+                get(data, position - 2)? as i8 as i32,
+                get(data, position - 1)? as i8 as i32,
+            )
+        } else {
+            position += 2;
+            (
+                get(data, position - 2)? as i32,
+                get(data, position - 1)? as i32,
+            )
+        };
+        let size = if flags & HAS_SCALE > 0 {
+            2
+        } else if flags & HAS_SCALES > 0 {
+            4
+        } else if flags & HAS_MATRIX > 0 {
+            8
+        } else {
+            0
+        };
+        let transformation = match data.get(position..(position + size)) {
+            Some(value) => value,
+            _ => raise!("found a malformed composite glyph"),
+        };
+        position += size;
+
+        let mut flags = flags & !HAS_INSTRUCTIONS & !ARGUMENTS_ARE_WORDS;
+        let (x, y) = if flags & ARGUMENTS_ARE_OFFSETS > 0 {
+            (
+                round(x as f32 * scale) as i32,
+                round(y as f32 * scale) as i32,
+            )
+        } else {
+            (x, y)
+        };
+        let short = if flags & ARGUMENTS_ARE_OFFSETS > 0 {
+            (-128..=127).contains(&x) && (-128..=127).contains(&y)
+        } else {
+            (0..=255).contains(&x) && (0..=255).contains(&y)
+        };
+        if !short {
+            flags |= ARGUMENTS_ARE_WORDS;
+        }
+        output.extend(flags.to_be_bytes());
+        output.extend(glyph_id);
+        if short {
+            output.extend([x as u8, y as u8]);
+        } else {
+            output.extend((x as i16).to_be_bytes());
+            output.extend((y as i16).to_be_bytes());
+        }
+        output.extend_from_slice(transformation);
+        if flags & HAS_MORE_COMPONENTS == 0 {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn simple(data: &[u8], contour_count: usize, scale: f32, output: &mut Vec<u8>) -> Result<()> {
+    let mut position = 10 + 2 * contour_count;
+    let point_count = match contour_count {
+        0 => 0,
+        _ => u16::from_be_bytes([get(data, position - 2)?, get(data, position - 1)?]) as usize + 1,
+    };
+    output.extend_from_slice(&data[10..position]);
+    output.extend([0, 0]);
+    position += 2 + u16::from_be_bytes([get(data, position)?, get(data, position + 1)?]) as usize;
+
+    let mut flags = Vec::with_capacity(point_count);
+    while flags.len() < point_count {
+        let flag = get(data, position)?;
+        position += 1;
+        flags.push(flag);
+        if flag & REPEAT > 0 {
+            let count = get(data, position)?;
+            position += 1;
+            flags.extend(std::iter::repeat_n(flag, count as usize));
+        }
+    }
+    flags.truncate(point_count);
+    let mut read = |short: u8, same: u8| -> Result<Vec<i32>> {
+        let mut value = 0i32;
+        let mut values = Vec::with_capacity(point_count);
+        for &flag in flags.iter() {
+            if flag & short > 0 {
+                let delta = get(data, position)? as i32;
+                position += 1;
+                value += if flag & same > 0 { delta } else { -delta };
+            } else if flag & same == 0 {
+                value +=
+                    i16::from_be_bytes([get(data, position)?, get(data, position + 1)?]) as i32;
+                position += 2;
+            }
+            values.push(value);
+        }
+        Ok(values)
+    };
+    let xs = read(X_SHORT, X_SAME_OR_POSITIVE)?;
+    let ys = read(Y_SHORT, Y_SAME_OR_POSITIVE)?;
+
+    let scale = |values: Vec<i32>| -> Vec<i32> {
+        let values = values
+            .into_iter()
+            .map(|value| round(value as f32 * scale) as i32)
+            .collect::<Vec<_>>();
+        let mut last = 0;
+        values
+            .into_iter()
+            .map(|value| {
+                let delta = value - last;
+                last = value;
+                delta
+            })
+            .collect()
+    };
+    let xs = scale(xs);
+    let ys = scale(ys);
+
+    let flags = flags.iter().map(|flag| flag & ON_CURVE).collect::<Vec<_>>();
+    coordinates(&flags, &xs, &ys, output);
+    Ok(())
+}
+
+fn coordinates(flags: &[u8], xs: &[i32], ys: &[i32], output: &mut Vec<u8>) {
+    let encode = |delta: i32, short: u8, same: u8, flag: &mut u8, data: &mut Vec<u8>| {
+        if delta == 0 {
+            *flag |= same;
+        } else if (-255..=255).contains(&delta) {
+            *flag |= short;
+            if delta > 0 {
+                *flag |= same;
+            }
+            data.push(delta.unsigned_abs() as u8);
+        } else {
+            data.extend((delta as i16).to_be_bytes());
+        }
+    };
+    let mut new_flags = Vec::with_capacity(flags.len());
+    let mut x_data = vec![];
+    let mut y_data = vec![];
+    for i in 0..flags.len() {
+        let mut flag = flags[i];
+        encode(xs[i], X_SHORT, X_SAME_OR_POSITIVE, &mut flag, &mut x_data);
+        encode(ys[i], Y_SHORT, Y_SAME_OR_POSITIVE, &mut flag, &mut y_data);
+        new_flags.push(flag);
+    }
+    let mut i = 0;
+    while i < new_flags.len() {
+        let flag = new_flags[i];
+        let mut count = 0;
+        while count < 255 && i + count + 1 < new_flags.len() && new_flags[i + count + 1] == flag {
+            count += 1;
+        }
+        if count > 0 {
+            output.extend([flag | REPEAT, count as u8]);
+        } else {
+            output.push(flag);
+        }
+        i += count + 1;
+    }
+    output.extend(x_data);
+    output.extend(y_data);
+}
+
+#[inline]
+fn get(data: &[u8], position: usize) -> Result<u8> {
+    match data.get(position) {
+        Some(value) => Ok(*value),
+        _ => raise!("found a malformed glyph"),
+    }
+}
+
+#[inline]
+fn round(value: f32) -> i16 {
+    value.round() as i16
+}
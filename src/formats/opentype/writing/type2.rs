@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::io::Result;
+
+use crate::formats::opentype::charstring::{bias, split};
+
+const CALLSUBR: u8 = 10;
+const ENDCHAR: u8 = 14;
+const RETURN: u8 = 11;
+const RLINETO: u8 = 5;
+const RMOVETO: u8 = 21;
+const RRCURVETO: u8 = 8;
+
+const LENGTH: usize = 32;
+const SUBROUTINES: usize = 1239;
+
+type Codes = Vec<Vec<u8>>;
+
+/// Encode a character string given an advance width and contours.
+///
+/// Each contour is a sequence of steps in absolute coordinates, where the
+/// first step is a single point to move to, and each other step is either a
+/// single point to draw a line to or three points to draw a curve through.
+pub fn encode(width: i32, contours: &[Vec<Vec<(i32, i32)>>]) -> Vec<u8> {
+    let mut data = number(width);
+    let mut last = (0, 0);
+    for contour in contours.iter() {
+        for (i, step) in contour.iter().enumerate() {
+            for &(x, y) in step.iter() {
+                data.extend(number(x - last.0));
+                data.extend(number(y - last.1));
+                last = (x, y);
+            }
+            data.push(match (i, step.len()) {
+                (0, _) => RMOVETO,
+                (_, 1) => RLINETO,
+                _ => RRCURVETO,
+            });
+        }
+    }
+    data.push(ENDCHAR);
+    data
+}
+
+/// Factor repeated sequences of operations out of character strings.
+///
+/// The function returns the rewritten strings and the local subroutines they
+/// call. The strings are expected to contain no subroutine calls.
+pub fn factor(codes: &[Vec<u8>]) -> Result<(Codes, Codes)> {
+    let mut tokens = Vec::<&[u8]>::new();
+    let mut token_indices = HashMap::<&[u8], usize>::new();
+    let mut sequences = Vec::with_capacity(codes.len());
+    for code in codes.iter() {
+        let mut sequence = vec![];
+        for range in split(code)? {
+            let token = &code[range];
+            let index = *token_indices.entry(token).or_insert_with(|| {
+                tokens.push(token);
+                tokens.len() - 1
+            });
+            sequence.push(index);
+        }
+        sequences.push(sequence);
+    }
+
+    let size = |items: &[usize]| -> usize { items.iter().map(|&index| tokens[index].len()).sum() };
+    let callable = |index: usize| tokens[index].last() != Some(&ENDCHAR);
+    // The repeated sequences are limited in length, which bounds the number of
+    // candidates by the total length times the limit, and each candidate is
+    // then checked only where it occurs.
+    let limit = |sequence: &[usize], i: usize| -> usize {
+        let end = sequence.len().min(i + LENGTH);
+        (i..end).find(|&j| !callable(sequence[j])).unwrap_or(end)
+    };
+    let mut counts = HashMap::<&[usize], usize>::new();
+    for sequence in sequences.iter() {
+        for i in 0..sequence.len() {
+            for j in (i + 1)..(limit(sequence, i) + 1) {
+                *counts.entry(&sequence[i..j]).or_default() += 1;
+            }
+        }
+    }
+    let mut occurrences = counts
+        .into_iter()
+        .filter(|&(items, count)| saving(size(items), count) > 0)
+        .map(|(items, _)| (items, vec![]))
+        .collect::<HashMap<&[usize], Vec<(usize, usize)>>>();
+    for (k, sequence) in sequences.iter().enumerate() {
+        for i in 0..sequence.len() {
+            for j in (i + 1)..(limit(sequence, i) + 1) {
+                if let Some(positions) = occurrences.get_mut(&sequence[i..j]) {
+                    positions.push((k, i));
+                }
+            }
+        }
+    }
+    let mut candidates = occurrences
+        .into_iter()
+        .map(|(items, positions)| (saving(size(items), positions.len()), items, positions))
+        .collect::<Vec<_>>();
+    candidates.sort_by(|one, other| other.0.cmp(&one.0).then_with(|| one.1.cmp(other.1)));
+
+    let mut taken = sequences
+        .iter()
+        .map(|sequence| vec![false; sequence.len()])
+        .collect::<Vec<_>>();
+    let mut calls = vec![HashMap::<usize, usize>::new(); sequences.len()];
+    let mut subroutines = vec![];
+    for (_, candidate, positions) in candidates.into_iter() {
+        if subroutines.len() == SUBROUTINES {
+            break;
+        }
+        let length = candidate.len();
+        let mut end = (usize::MAX, 0);
+        let positions = positions
+            .into_iter()
+            .filter(|&(k, i)| {
+                let free = (k != end.0 || i >= end.1) && !taken[k][i..(i + length)].contains(&true);
+                if free {
+                    end = (k, i + length);
+                }
+                free
+            })
+            .collect::<Vec<_>>();
+        if saving(size(candidate), positions.len()) <= 0 {
+            continue;
+        }
+        for (k, i) in positions {
+            taken[k][i..(i + length)].fill(true);
+            calls[k].insert(i, subroutines.len());
+        }
+        subroutines.push(candidate);
+    }
+
+    let codes = sequences
+        .iter()
+        .zip(calls.iter())
+        .map(|(sequence, calls)| {
+            let mut data = vec![];
+            let mut i = 0;
+            while i < sequence.len() {
+                match calls.get(&i) {
+                    Some(&index) => {
+                        data.extend(number(index as i32 - bias(SUBROUTINES)));
+                        data.push(CALLSUBR);
+                        i += subroutines[index].len();
+                    }
+                    _ => {
+                        data.extend_from_slice(tokens[sequence[i]]);
+                        i += 1;
+                    }
+                }
+            }
+            data
+        })
+        .collect();
+    let subroutines = subroutines
+        .iter()
+        .map(|items| {
+            let mut data = items
+                .iter()
+                .flat_map(|&index| tokens[index].iter().cloned())
+                .collect::<Vec<_>>();
+            data.push(RETURN);
+            data
+        })
+        .collect();
+    Ok((codes, subroutines))
+}
+
+fn number(value: i32) -> Vec<u8> {
+    match value {
+        -107..=107 => vec![(value + 139) as u8],
+        108..=1131 => {
+            let value = value - 108;
+            vec![(value / 256 + 247) as u8, (value % 256) as u8]
+        }
+        -1131..=-108 => {
+            let value = -value - 108;
+            vec![(value / 256 + 251) as u8, (value % 256) as u8]
+        }
+        _ => {
+            let mut data = vec![28];
+            data.extend((value as i16).to_be_bytes());
+            data
+        }
+    }
+}
+
+fn saving(size: usize, count: usize) -> isize {
+    count as isize * (size as isize - 3) - (size as isize + 3)
+}
@@ -35,6 +35,13 @@ impl<T: crate::Read> crate::font::Case for Font<T> {
         metrics::read(&mut self.cache.borrow_mut())
     }
 
+    #[inline]
+    fn bounding_box(
+        &mut self,
+    ) -> Result<(crate::Number, crate::Number, crate::Number, crate::Number)> {
+        metrics::bounding_box(&mut self.cache.borrow_mut())
+    }
+
     #[inline]
     fn names(&mut self) -> Result<crate::Names> {
         names::read(&mut self.cache.borrow_mut())
@@ -55,10 +62,69 @@ impl<T: crate::Read> crate::font::Case for Font<T> {
         timestamps::read(&mut self.cache.borrow_mut())
     }
 
+    #[inline]
+    fn grid_fitting(&mut self) -> Result<Option<Rc<crate::GridFitting>>> {
+        Ok(self.cache.borrow_mut().grid_fitting()?.cloned())
+    }
+
+    #[inline]
+    fn device_widths(&mut self) -> Result<Option<Rc<crate::DeviceWidths>>> {
+        Ok(self.cache.borrow_mut().device_widths()?.cloned())
+    }
+
+    #[inline]
+    fn linear_thresholds(&mut self) -> Result<Option<Rc<crate::LinearThresholds>>> {
+        Ok(self.cache.borrow_mut().linear_thresholds()?.cloned())
+    }
+
+    #[inline]
+    fn vertical_device_metrics(&mut self) -> Result<Option<Rc<crate::VerticalDeviceMetrics>>> {
+        Ok(self.cache.borrow_mut().vertical_device_metrics()?.cloned())
+    }
+
     #[inline]
     fn glyph(&mut self, _: char) -> Result<Option<crate::Glyph>> {
         error!("working with glyphs is not supported yet")
     }
+
+    #[inline]
+    fn glyph_id(&mut self, _: char) -> Result<Option<opentype::truetype::GlyphID>> {
+        error!("working with glyphs is not supported yet")
+    }
+
+    #[inline]
+    fn glyph_by_id(&mut self, _: opentype::truetype::GlyphID, _: bool) -> Result<crate::Glyph> {
+        error!("working with glyphs is not supported yet")
+    }
+
+    #[inline]
+    fn alignment(&mut self) -> Result<Option<crate::glyph::Alignment>> {
+        error!("working with glyphs is not supported yet")
+    }
+
+    #[inline]
+    fn draw(&mut self, _: char, _: &mut dyn crate::glyph::Pen) -> Result<bool> {
+        error!("working with glyphs is not supported yet")
+    }
+
+    #[inline]
+    fn kerning(&mut self, _: char, _: char) -> Result<crate::Number> {
+        error!("working with glyphs is not supported yet")
+    }
+
+    fn kerning_pairs(&mut self, _: &[char]) -> Result<Vec<(char, char, crate::Number)>> {
+        error!("working with glyphs is not supported yet")
+    }
+
+    #[inline]
+    fn hinted_glyph(&mut self, _: char, _: u16) -> Result<Option<crate::Glyph>> {
+        error!("working with glyphs is not supported yet")
+    }
+
+    #[inline]
+    fn bitmap(&mut self, _: char, _: u16) -> Result<Option<crate::EmbeddedBitmap>> {
+        error!("working with glyphs is not supported yet")
+    }
 }
 
 pub fn read<T>(tape: Reference<Cursor<Vec<u8>>>, backend: webtype::Font) -> Result<Vec<Font<T>>>
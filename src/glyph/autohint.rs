@@ -0,0 +1,343 @@
+use crate::glyph::{Alignment, Glyph, Point};
+use crate::{Metrics, Number, Offset};
+
+// The largest slope of a step between points still considered flat.
+const FLATNESS: Number = 1.0 / 14.0;
+
+#[derive(Clone, Copy, Debug)]
+struct Edge {
+    position: Number,
+    extent: (Number, Number),
+    // The side of the ink along the axis, which is positive if the ink is
+    // above or to the right of the edge.
+    side: Number,
+    round: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Zone {
+    flat: Number,
+    overshoot: Number,
+    side: Number,
+}
+
+impl Alignment {
+    /// Detect alignment zones and standard stem widths from the metrics and
+    /// the outlines of reference glyphs.
+    ///
+    /// The zones are at the baseline, the x-height, and the cap height. Their
+    /// flat edges are the most common straight edges of the glyphs near the
+    /// heights given by the metrics, and their overshoots are the extrema of
+    /// the round edges beyond. Lowercase and uppercase glyphs with both flat
+    /// and round shapes such as `x`, `o`, `H`, and `O` make good references.
+    pub fn detect(metrics: &Metrics, glyphs: &[Glyph]) -> Self {
+        let fuzz = metrics.granularity / 20.0;
+        let horizontal = glyphs
+            .iter()
+            .flat_map(|glyph| edges(glyph, 1))
+            .collect::<Vec<_>>();
+        let zone = |height: Number, side: Number| {
+            let flats = horizontal
+                .iter()
+                .filter(|edge| !edge.round && edge.side == side)
+                .filter(|edge| (edge.position - height).abs() <= fuzz)
+                .map(|edge| (edge.position, edge.extent.1 - edge.extent.0))
+                .collect::<Vec<_>>();
+            let flat = dominant(flats).unwrap_or(height);
+            let overshoot = horizontal
+                .iter()
+                .filter(|edge| edge.round && edge.side == side)
+                .map(|edge| (edge.position - flat) * -side)
+                .filter(|&distance| (0.0..=fuzz).contains(&distance))
+                .fold(0.0, Number::max);
+            let overshoot = flat - side * overshoot;
+            (flat.min(overshoot), flat.max(overshoot))
+        };
+        let mut blue_values = vec![zone(metrics.baseline, 1.0)];
+        for height in [metrics.x_height, metrics.cap_height] {
+            let value = zone(height, -1.0);
+            if height > metrics.baseline && blue_values.iter().all(|zone| zone.1 < value.0) {
+                blue_values.push(value);
+            }
+        }
+        let widths = |axis: usize| {
+            let widths = glyphs
+                .iter()
+                .flat_map(|glyph| {
+                    let edges = edges(glyph, axis);
+                    stems(&edges).into_iter().map(move |(i, j)| {
+                        let (lower, upper) = (edges[i], edges[j]);
+                        let width = (upper.position - lower.position).round();
+                        (width, overlap(&lower, &upper))
+                    })
+                })
+                .collect::<Vec<_>>();
+            dominant(widths)
+        };
+        Self {
+            blue_values,
+            other_blues: vec![],
+            standard_horizontal_width: widths(1),
+            standard_vertical_width: widths(0),
+        }
+    }
+}
+
+impl Glyph {
+    /// Grid-fit using alignment zones and stems found in the outline.
+    ///
+    /// The scale is the number of pixels per font unit, and the result is in
+    /// pixels with the origin at zero. Edges within alignment zones snap to
+    /// the rounded flat edges of the zones with overshoots rounded separately,
+    /// stems get whole-pixel widths and positions in both directions, and the
+    /// other points are interpolated between the fitted edges. The zones are
+    /// the ones of a font, which can also be detected by
+    /// [`Alignment::detect`].
+    pub fn autohint(&self, alignment: &Alignment, scale: Number) -> Glyph {
+        let origin = self.origin();
+        let mut zones = vec![];
+        for (i, &(lower, upper)) in alignment.blue_values.iter().enumerate() {
+            zones.push(match i {
+                0 => Zone {
+                    flat: upper,
+                    overshoot: lower,
+                    side: 1.0,
+                },
+                _ => Zone {
+                    flat: lower,
+                    overshoot: upper,
+                    side: -1.0,
+                },
+            });
+        }
+        for &(lower, upper) in alignment.other_blues.iter() {
+            zones.push(Zone {
+                flat: upper,
+                overshoot: lower,
+                side: 1.0,
+            });
+        }
+        let mut moved = self.clone();
+        let translated = self.transform((1.0, 0.0, 0.0, 1.0, -origin, 0.0));
+        let vertical = fit(
+            &edges(&translated, 0),
+            &[],
+            alignment.standard_vertical_width,
+            scale,
+        );
+        let horizontal = fit(
+            &edges(&translated, 1),
+            &zones,
+            alignment.standard_horizontal_width,
+            scale,
+        );
+        let apply = |value: Offset| {
+            Offset(
+                interpolate(&vertical, value.0, scale),
+                interpolate(&horizontal, value.1, scale),
+            )
+        };
+        moved.set_points(translated.points().into_iter().map(|points| {
+            points.into_iter().map(|point| match point {
+                Point::On(value) => Point::On(apply(value)),
+                Point::Off(value) => Point::Off(apply(value)),
+            })
+        }));
+        moved.components.clear();
+        moved.hints = None;
+        self.adjust(moved, 0.0, (self.advance_width * scale).round())
+    }
+}
+
+// Find the runs of points flat along an axis, which is 0 for vertical edges
+// and 1 for horizontal ones.
+fn edges(glyph: &Glyph, axis: usize) -> Vec<Edge> {
+    let sign = if glyph.area() < 0.0 { -1.0 } else { 1.0 };
+    let sign = if axis == 1 { sign } else { -sign };
+    let split = |value: Offset| match axis {
+        0 => (value.0, value.1),
+        _ => (value.1, value.0),
+    };
+    let mut edges = vec![];
+    for contour in glyph.iter() {
+        let mut points = contour
+            .points()
+            .map(|point| (split(point.offset()), point.is_on_curve()))
+            .collect::<Vec<_>>();
+        if points.len() > 1
+            && points.first().map(|point| point.0) == points.last().map(|point| point.0)
+        {
+            points.pop();
+        }
+        let count = points.len();
+        let flat = |i: usize| {
+            let ((one, along), _) = points[i % count];
+            let ((other, other_along), _) = points[(i + 1) % count];
+            let (delta, delta_along) = ((other - one).abs(), (other_along - along).abs());
+            delta_along > 0.0 && delta <= (delta_along * FLATNESS).max(0.5)
+        };
+        let start = match (0..count).find(|&i| !flat(i)) {
+            Some(value) => value + 1,
+            _ => continue,
+        };
+        let mut i = 0;
+        while i < count {
+            if !flat(start + i) {
+                i += 1;
+                continue;
+            }
+            let first = start + i;
+            while i < count && flat(start + i) {
+                i += 1;
+            }
+            let run = (first..=start + i)
+                .map(|j| points[j % count])
+                .collect::<Vec<_>>();
+            let delta = run[run.len() - 1].0 .1 - run[0].0 .1;
+            if delta.abs() < 1.0 {
+                continue;
+            }
+            let mut positions = run.iter().map(|point| point.0 .0).collect::<Vec<_>>();
+            positions.sort_by(|one, other| one.total_cmp(other));
+            let position = positions[positions.len() / 2];
+            let extent = run
+                .iter()
+                .fold((Number::MAX, Number::MIN), |extent, point| {
+                    (extent.0.min(point.0 .1), extent.1.max(point.0 .1))
+                });
+            edges.push(Edge {
+                position,
+                extent,
+                side: sign * delta.signum(),
+                round: !run.windows(2).any(|pair| pair[0].1 && pair[1].1),
+            });
+        }
+    }
+    edges
+}
+
+// Pair edges with ink between them and overlapping by more than the distance
+// between them, taking the narrowest pairs first.
+fn stems(edges: &[Edge]) -> Vec<(usize, usize)> {
+    let mut candidates = vec![];
+    for (i, lower) in edges.iter().enumerate() {
+        for (j, upper) in edges.iter().enumerate() {
+            if lower.side > 0.0
+                && upper.side < 0.0
+                && upper.position > lower.position
+                && overlap(lower, upper) > upper.position - lower.position
+            {
+                candidates.push((upper.position - lower.position, i, j));
+            }
+        }
+    }
+    candidates.sort_by(|one, other| one.0.total_cmp(&other.0));
+    let mut used = vec![false; edges.len()];
+    let mut stems = vec![];
+    for (_, i, j) in candidates {
+        if !used[i] && !used[j] {
+            used[i] = true;
+            used[j] = true;
+            stems.push((i, j));
+        }
+    }
+    stems
+}
+
+// Compute the positions of the edges in pixels and return the pairs of the
+// original and fitted positions in increasing order.
+fn fit(
+    edges: &[Edge],
+    zones: &[Zone],
+    standard_width: Option<Number>,
+    scale: Number,
+) -> Vec<(Number, Number)> {
+    let fuzz = 0.25 / scale;
+    let mut fitted = vec![None; edges.len()];
+    for (edge, fitted) in edges.iter().zip(fitted.iter_mut()) {
+        *fitted = zones
+            .iter()
+            .find(|zone| {
+                let (lower, upper) = (zone.flat.min(zone.overshoot), zone.flat.max(zone.overshoot));
+                edge.side == zone.side
+                    && edge.position >= lower - fuzz
+                    && edge.position <= upper + fuzz
+            })
+            .map(|zone| {
+                (zone.flat * scale).round() + ((edge.position - zone.flat) * scale).round()
+            });
+    }
+    let mut stems = stems(edges);
+    stems.sort_by_key(|&(i, j)| fitted[i].is_none() && fitted[j].is_none());
+    for (i, j) in stems {
+        let (lower, upper) = (edges[i].position, edges[j].position);
+        let width = match standard_width {
+            Some(value) if ((upper - lower - value) * scale).abs() < 0.5 => value,
+            _ => upper - lower,
+        };
+        let width = (width * scale).round().max(1.0);
+        match (fitted[i], fitted[j]) {
+            (Some(_), Some(_)) => {}
+            (Some(value), None) => fitted[j] = Some(value + width),
+            (None, Some(value)) => fitted[i] = Some(value - width),
+            (None, None) => {
+                let value = ((lower + upper) * scale / 2.0 - width / 2.0).round();
+                fitted[i] = Some(value);
+                fitted[j] = Some(value + width);
+            }
+        }
+    }
+    let mut pairs = edges
+        .iter()
+        .zip(fitted)
+        .filter_map(|(edge, fitted)| fitted.map(|value| (edge.position, value)))
+        .collect::<Vec<_>>();
+    pairs.sort_by(|one, other| one.0.total_cmp(&other.0));
+    // Edges that would reverse the order of the others are left to the
+    // interpolation.
+    let mut result: Vec<(Number, Number)> = Vec::with_capacity(pairs.len());
+    for (position, value) in pairs {
+        match result.last() {
+            Some(&(last, last_value)) if position <= last || value < last_value => {}
+            _ => result.push((position, value)),
+        }
+    }
+    result
+}
+
+fn interpolate(pairs: &[(Number, Number)], value: Number, scale: Number) -> Number {
+    let i = pairs.partition_point(|pair| pair.0 < value);
+    match (i.checked_sub(1).map(|i| pairs[i]), pairs.get(i)) {
+        (_, Some(&(position, fitted))) if position == value => fitted,
+        (Some((lower, lower_fitted)), Some(&(upper, upper_fitted))) => {
+            lower_fitted + (value - lower) * (upper_fitted - lower_fitted) / (upper - lower)
+        }
+        (Some((position, fitted)), None) | (None, Some(&(position, fitted))) => {
+            fitted + (value - position) * scale
+        }
+        (None, None) => value * scale,
+    }
+}
+
+fn overlap(one: &Edge, other: &Edge) -> Number {
+    one.extent.1.min(other.extent.1) - one.extent.0.max(other.extent.0)
+}
+
+// Find the value with the largest total weight of the values up to a unit
+// above it, which are the lengths of the edges behind the values.
+fn dominant(mut values: Vec<(Number, Number)>) -> Option<Number> {
+    values.sort_by(|one, other| one.0.total_cmp(&other.0));
+    let mut best: Option<(Number, Number)> = None;
+    for (i, &(value, _)) in values.iter().enumerate() {
+        let weight = values[i..]
+            .iter()
+            .take_while(|other| other.0 - value <= 1.0)
+            .map(|other| other.1)
+            .sum::<Number>();
+        match best {
+            Some((best, _)) if weight <= best => {}
+            _ => best = Some((weight, value)),
+        }
+    }
+    best.map(|(_, value)| value)
+}
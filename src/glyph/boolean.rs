@@ -0,0 +1,470 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::glyph::geometry::Curve;
+use crate::glyph::{Builder, Glyph, Pen};
+use crate::{Number, Offset};
+
+const GRID: f64 = 256.0;
+const PROBE: f64 = 1e-2;
+const TOLERANCE: Number = 0.1;
+
+/// A Boolean operation on outlines.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Operation {
+    /// The area covered by either outline.
+    Union,
+    /// The area covered by both outlines.
+    Intersection,
+    /// The area covered by the first outline but not the second one.
+    Difference,
+    /// The area covered by exactly one of the outlines.
+    Xor,
+}
+
+type Point = (i64, i64);
+
+#[derive(Clone, Copy)]
+struct Edge {
+    from: Point,
+    to: Point,
+    operand: usize,
+    curve: usize,
+    t: (f64, f64),
+}
+
+impl Glyph {
+    /// Combine the outline with the outline of another glyph.
+    ///
+    /// The inside of each outline is determined with the non-zero winding
+    /// rule, and the resulting contours do not overlap. Curves are preserved
+    /// except where they are cut, and the horizontal metrics are taken from
+    /// the glyph itself.
+    pub fn combine(&self, other: &Glyph, operation: Operation) -> Glyph {
+        let mut curves = vec![];
+        let mut edges = vec![];
+        for (operand, glyph) in [self, other].into_iter().enumerate() {
+            for contour in glyph.iter() {
+                let mut end = contour.position;
+                for curve in contour.curves() {
+                    end = curve.end();
+                    flatten(&curve, operand, curves.len(), &mut edges);
+                    curves.push(curve);
+                }
+                if end != contour.position {
+                    let curve = Curve::Linear(end, contour.position);
+                    flatten(&curve, operand, curves.len(), &mut edges);
+                    curves.push(curve);
+                }
+            }
+        }
+        let edges = split(&edges);
+        let bands = Bands::new(edges.clone());
+        let inside = |point: (f64, f64)| {
+            let (one, other) = bands.winding(point);
+            let (one, other) = (one != 0, other != 0);
+            match operation {
+                Operation::Union => one || other,
+                Operation::Intersection => one && other,
+                Operation::Difference => one && !other,
+                Operation::Xor => one != other,
+            }
+        };
+        let mut seen = HashSet::new();
+        let mut kept = vec![];
+        for edge in edges {
+            if !seen.insert((edge.from.min(edge.to), edge.from.max(edge.to))) {
+                continue;
+            }
+            let direction = (
+                (edge.to.0 - edge.from.0) as f64,
+                (edge.to.1 - edge.from.1) as f64,
+            );
+            let norm = direction.0.hypot(direction.1);
+            let normal = (-direction.1 / norm * PROBE, direction.0 / norm * PROBE);
+            let middle = (
+                (edge.from.0 + edge.to.0) as f64 / 2.0,
+                (edge.from.1 + edge.to.1) as f64 / 2.0,
+            );
+            let left = inside((middle.0 + normal.0, middle.1 + normal.1));
+            let right = inside((middle.0 - normal.0, middle.1 - normal.1));
+            match (left, right) {
+                (true, false) => kept.push(edge),
+                (false, true) => kept.push(Edge {
+                    from: edge.to,
+                    to: edge.from,
+                    t: (edge.t.1, edge.t.0),
+                    ..edge
+                }),
+                _ => {}
+            }
+        }
+        let clockwise = [self, other]
+            .into_iter()
+            .flat_map(|glyph| glyph.iter())
+            .map(|contour| contour.area())
+            .fold(
+                0.0,
+                |sum: Number, value| {
+                    if value.abs() > sum.abs() {
+                        value
+                    } else {
+                        sum
+                    }
+                },
+            )
+            < 0.0;
+        let mut builder = Builder::default();
+        for mut chain in link(&kept) {
+            if clockwise {
+                chain.reverse();
+                for edge in chain.iter_mut() {
+                    *edge = Edge {
+                        from: edge.to,
+                        to: edge.from,
+                        t: (edge.t.1, edge.t.0),
+                        ..*edge
+                    };
+                }
+            }
+            draw(&mut builder, &curves, &chain);
+        }
+        let mut glyph = Glyph::from(builder);
+        glyph.bounding_box = glyph.bounds();
+        glyph.advance_width = self.advance_width;
+        glyph.side_bearings.0 = if self.bounding_box.0.is_finite() {
+            self.side_bearings.0 + glyph.bounding_box.0 - self.bounding_box.0
+        } else {
+            glyph.bounding_box.0
+        };
+        glyph.side_bearings.1 = glyph.advance_width - (glyph.side_bearings.0 + glyph.width());
+        glyph
+    }
+
+    /// Return the union with another glyph.
+    #[inline]
+    pub fn union(&self, other: &Glyph) -> Glyph {
+        self.combine(other, Operation::Union)
+    }
+
+    /// Return the intersection with another glyph.
+    #[inline]
+    pub fn intersection(&self, other: &Glyph) -> Glyph {
+        self.combine(other, Operation::Intersection)
+    }
+
+    /// Return the difference with another glyph.
+    #[inline]
+    pub fn difference(&self, other: &Glyph) -> Glyph {
+        self.combine(other, Operation::Difference)
+    }
+
+    /// Return the symmetric difference with another glyph.
+    #[inline]
+    pub fn xor(&self, other: &Glyph) -> Glyph {
+        self.combine(other, Operation::Xor)
+    }
+
+    /// Remove overlaps between and within contours.
+    pub fn remove_overlaps(&mut self) {
+        *self = self.combine(&Glyph::default(), Operation::Union);
+    }
+}
+
+// The winding numbers are computed against the edges overlapping the
+// horizontal band that contains the point.
+struct Bands {
+    edges: Vec<Edge>,
+    bottom: i64,
+    step: i64,
+    lists: Vec<Vec<usize>>,
+}
+
+impl Bands {
+    fn new(edges: Vec<Edge>) -> Self {
+        let bottom = edges.iter().map(|edge| edge.from.1.min(edge.to.1)).min();
+        let top = edges.iter().map(|edge| edge.from.1.max(edge.to.1)).max();
+        let (bottom, top) = match (bottom, top) {
+            (Some(bottom), Some(top)) => (bottom, top),
+            _ => {
+                return Self {
+                    edges,
+                    bottom: 0,
+                    step: 1,
+                    lists: vec![],
+                }
+            }
+        };
+        let count = ((edges.len() as f64).sqrt().ceil() as i64).max(1);
+        let step = ((top - bottom) / count + 1).max(1);
+        let mut lists = vec![vec![]; ((top - bottom) / step + 1) as usize];
+        for (i, edge) in edges.iter().enumerate() {
+            let (low, high) = (edge.from.1.min(edge.to.1), edge.from.1.max(edge.to.1));
+            for list in lists
+                .iter_mut()
+                .take(((high - bottom) / step + 1) as usize)
+                .skip(((low - bottom) / step) as usize)
+            {
+                list.push(i);
+            }
+        }
+        Self {
+            edges,
+            bottom,
+            step,
+            lists,
+        }
+    }
+
+    fn winding(&self, point: (f64, f64)) -> (i32, i32) {
+        let mut values = [0, 0];
+        let index = ((point.1 - self.bottom as f64) / self.step as f64).floor();
+        let list = match self.lists.get(index as usize) {
+            Some(list) if index >= 0.0 => list,
+            _ => return (0, 0),
+        };
+        for &i in list.iter() {
+            let edge = &self.edges[i];
+            let (a, b) = (
+                (edge.from.0 as f64, edge.from.1 as f64),
+                (edge.to.0 as f64, edge.to.1 as f64),
+            );
+            let side = (b.0 - a.0) * (point.1 - a.1) - (b.1 - a.1) * (point.0 - a.0);
+            if a.1 <= point.1 {
+                if b.1 > point.1 && side > 0.0 {
+                    values[edge.operand] += 1;
+                }
+            } else if b.1 <= point.1 && side < 0.0 {
+                values[edge.operand] -= 1;
+            }
+        }
+        (values[0], values[1])
+    }
+}
+
+fn flatten(curve: &Curve, operand: usize, index: usize, edges: &mut Vec<Edge>) {
+    let count = match *curve {
+        Curve::Linear(..) => 1,
+        _ => pieces(curve.deviation()),
+    };
+    let mut start = (snap(curve.start()), 0.0);
+    for i in 1..=count {
+        let t = i as f64 / count as f64;
+        let point = if i == count {
+            snap(curve.end())
+        } else {
+            snap(curve.evaluate(t as Number))
+        };
+        if point != start.0 {
+            edges.push(Edge {
+                from: start.0,
+                to: point,
+                operand,
+                curve: index,
+                t: (start.1, t),
+            });
+            start = (point, t);
+        }
+    }
+}
+
+fn split(edges: &[Edge]) -> Vec<Edge> {
+    let mut points = vec![vec![]; edges.len()];
+    let mut order = (0..edges.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| edges[i].from.0.min(edges[i].to.0));
+    for (k, &i) in order.iter().enumerate() {
+        let one = &edges[i];
+        let right = one.from.0.max(one.to.0);
+        for &j in order[(k + 1)..].iter() {
+            let other = &edges[j];
+            if other.from.0.min(other.to.0) > right {
+                break;
+            }
+            let (low, high) = (one.from.1.min(one.to.1), one.from.1.max(one.to.1));
+            if other.from.1.max(other.to.1) < low || other.from.1.min(other.to.1) > high {
+                continue;
+            }
+            intersect(one, other, |first, point| {
+                points[if first { i } else { j }].push(point)
+            });
+        }
+    }
+    let mut output = Vec::with_capacity(edges.len());
+    for (edge, mut points) in edges.iter().zip(points) {
+        let direction = (edge.to.0 - edge.from.0, edge.to.1 - edge.from.1);
+        let total = dot(direction, direction) as f64;
+        let position = |point: Point| {
+            dot((point.0 - edge.from.0, point.1 - edge.from.1), direction) as f64 / total
+        };
+        points.retain(|&point| point != edge.from && point != edge.to);
+        points.sort_by(|one, other| position(*one).total_cmp(&position(*other)));
+        points.dedup();
+        let mut start = (edge.from, edge.t.0);
+        for point in points.into_iter().chain(std::iter::once(edge.to)) {
+            let t = if point == edge.to {
+                edge.t.1
+            } else {
+                edge.t.0 + (edge.t.1 - edge.t.0) * position(point)
+            };
+            output.push(Edge {
+                from: start.0,
+                to: point,
+                t: (start.1, t),
+                ..*edge
+            });
+            start = (point, t);
+        }
+    }
+    output
+}
+
+fn intersect<F>(one: &Edge, other: &Edge, mut found: F)
+where
+    F: FnMut(bool, Point),
+{
+    let (a, b, c, d) = (one.from, one.to, other.from, other.to);
+    let r = (b.0 - a.0, b.1 - a.1);
+    let s = (d.0 - c.0, d.1 - c.1);
+    let ac = (c.0 - a.0, c.1 - a.1);
+    let denominator = cross(r, s);
+    if denominator == 0 {
+        if cross(ac, r) != 0 {
+            return;
+        }
+        let within = |point: Point, from: Point, direction: Point| {
+            let value = dot((point.0 - from.0, point.1 - from.1), direction);
+            value > 0 && value < dot(direction, direction)
+        };
+        for point in [c, d] {
+            if within(point, a, r) {
+                found(true, point);
+            }
+        }
+        for point in [a, b] {
+            if within(point, c, s) {
+                found(false, point);
+            }
+        }
+        return;
+    }
+    let u = cross(ac, s) as f64 / denominator as f64;
+    let v = cross(ac, r) as f64 / denominator as f64;
+    if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+        return;
+    }
+    let point = (
+        (a.0 as f64 + r.0 as f64 * u).round() as i64,
+        (a.1 as f64 + r.1 as f64 * u).round() as i64,
+    );
+    if point != a && point != b {
+        found(true, point);
+    }
+    if point != c && point != d {
+        found(false, point);
+    }
+}
+
+fn link(edges: &[Edge]) -> Vec<Vec<Edge>> {
+    let mut outgoing: HashMap<Point, Vec<usize>> = HashMap::new();
+    for (i, edge) in edges.iter().enumerate() {
+        outgoing.entry(edge.from).or_default().push(i);
+    }
+    let mut used = vec![false; edges.len()];
+    let mut chains = vec![];
+    for first in 0..edges.len() {
+        if used[first] {
+            continue;
+        }
+        let mut chain = vec![];
+        let mut current = first;
+        let closed = loop {
+            used[current] = true;
+            chain.push(edges[current]);
+            let edge = &edges[current];
+            if edge.to == edges[first].from {
+                break true;
+            }
+            let incoming = (edge.to.0 - edge.from.0, edge.to.1 - edge.from.1);
+            let next = outgoing.get(&edge.to).and_then(|candidates| {
+                candidates
+                    .iter()
+                    .filter(|&&i| !used[i])
+                    .map(|&i| {
+                        let other = &edges[i];
+                        let outgoing = (other.to.0 - other.from.0, other.to.1 - other.from.1);
+                        let angle = (cross(incoming, outgoing) as f64)
+                            .atan2(dot(incoming, outgoing) as f64);
+                        (angle, i)
+                    })
+                    .max_by(|one, other| one.0.total_cmp(&other.0))
+                    .map(|(_, i)| i)
+            });
+            match next {
+                Some(next) => current = next,
+                _ => break false,
+            }
+        };
+        if closed {
+            chains.push(chain);
+        }
+    }
+    chains
+}
+
+fn draw(pen: &mut dyn Pen, curves: &[Curve], chain: &[Edge]) {
+    let count = chain.len();
+    let continues = |i: usize| {
+        let (previous, current) = (&chain[(i + count - 1) % count], &chain[i]);
+        previous.curve == current.curve && previous.t.1 == current.t.0
+    };
+    let start = (0..count).find(|&i| !continues(i)).unwrap_or(0);
+    let mut runs: Vec<(Edge, Edge)> = vec![];
+    for i in 0..count {
+        let edge = chain[(start + i) % count];
+        match runs.last_mut() {
+            Some(run) if i > 0 && continues((start + i) % count) => run.1 = edge,
+            _ => runs.push((edge, edge)),
+        }
+    }
+    pen.move_to(unsnap(runs[0].0.from));
+    for (first, last) in runs {
+        let (from, to) = (unsnap(first.from), unsnap(last.to));
+        match curves[first.curve].split(first.t.0 as Number, last.t.1 as Number) {
+            Curve::Linear(..) => pen.line_to(to),
+            Curve::Quadratic(a, b, c) => pen.quad_to(b + (from - a + to - c) / 2.0, to),
+            Curve::Cubic(a, b, c, d) => pen.curve_to(b + (from - a), c + (to - d), to),
+        }
+    }
+    pen.close();
+}
+
+fn pieces(deviation: Number) -> usize {
+    ((deviation / TOLERANCE).sqrt().ceil() as usize).clamp(1, 256)
+}
+
+#[inline]
+fn snap(value: Offset) -> Point {
+    (
+        (value.0 as f64 * GRID).round() as i64,
+        (value.1 as f64 * GRID).round() as i64,
+    )
+}
+
+#[inline]
+fn unsnap(value: Point) -> Offset {
+    Offset(
+        (value.0 as f64 / GRID) as Number,
+        (value.1 as f64 / GRID) as Number,
+    )
+}
+
+// The products of snapped points are computed exactly, which keeps the
+// intersection tests consistent, unlike the ones of offsets in geometry.
+#[inline]
+fn cross(one: Point, other: Point) -> i64 {
+    one.0 * other.1 - one.1 * other.0
+}
+
+#[inline]
+fn dot(one: Point, other: Point) -> i64 {
+    one.0 * other.0 + one.1 * other.1
+}
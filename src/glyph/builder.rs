@@ -1,150 +1,84 @@
-use crate::glyph::{Contour, Glyph, Segment};
+use crate::glyph::{Contour, Glyph, Pen, Segment};
 use crate::{Number, Offset};
 
+/// A pen building glyphs.
+///
+/// Contours are closed implicitly, and contours without segments are left
+/// out.
+#[derive(Default)]
 pub struct Builder {
     contour: Contour,
     glyph: Glyph,
 
-    offset: Offset,
-    scale: (Number, Number, Number, Number),
+    current: Offset,
+    last: Offset,
 }
 
 impl Builder {
-    pub fn flush(&mut self) {
-        if self.contour.is_empty() {
-            return;
-        }
-        self.glyph.contours.push(std::mem::take(&mut self.contour));
-    }
-
-    pub fn nest<T, U, V, F>(&mut self, offset: T, scale: (U, U, U, U), build: F) -> V
-    where
-        T: Into<Offset>,
-        U: Into<Number>,
-        F: Fn(&mut Builder) -> V,
-    {
-        macro_rules! multiply(
-            ($product:expr, $left:expr, $right:expr) => (
-                $product.0 = $left.0 * $right.0 + $left.1 * $right.2;
-                $product.1 = $left.0 * $right.1 + $left.1 * $right.3;
-                $product.2 = $left.2 * $right.0 + $left.3 * $right.2;
-                $product.3 = $left.2 * $right.1 + $left.3 * $right.3;
-            )
-        );
-
-        let offset = offset.into();
-        let scale = (
-            scale.0.into(),
-            scale.1.into(),
-            scale.2.into(),
-            scale.3.into(),
-        );
-        let previous_offset = self.offset;
-        let previous_scale = self.scale;
-        self.offset += offset;
-        multiply!(self.scale, scale, self.scale);
-        let result = build(self);
-        self.scale = previous_scale;
-        self.offset = previous_offset;
-        result
+    /// Set the bounding box.
+    #[inline]
+    pub fn set_bounding_box<T: Into<Number>>(
+        &mut self,
+        (min_x, min_y, max_x, max_y): (T, T, T, T),
+    ) {
+        self.glyph.bounding_box = (min_x.into(), min_y.into(), max_x.into(), max_y.into());
     }
 
+    /// Set the advance width and left side bearing.
     #[inline]
-    pub fn transform<T: Into<Offset>>(&self, value: T) -> Offset {
-        macro_rules! multiply(
-            ($product:expr, $left:expr, $right:expr) => (
-                $product.0 = $left.0 * $right.0 + $left.1 * $right.1;
-                $product.1 = $left.2 * $right.0 + $left.3 * $right.1;
-            )
-        );
+    pub fn set_horizontal_metrics(&mut self, (advance_width, left_side_bearing): (Number, Number)) {
+        self.glyph.advance_width = advance_width;
+        self.glyph.side_bearings.0 = left_side_bearing;
+    }
 
-        let mut value = value.into();
-        multiply!(value, self.scale, value);
-        value
+    fn flush(&mut self) {
+        let contour = std::mem::take(&mut self.contour);
+        if contour.is_empty() {
+            return;
+        }
+        self.last = self.current;
+        self.glyph.contours.push(contour);
     }
 }
 
-impl Builder {
-    pub fn move_absolute<T: Into<Offset>>(&mut self, a: T) {
-        let last_position = match self.glyph.len() {
-            0 => Offset::default(),
-            count => self.glyph[count - 1].position,
-        };
-        let a = self.offset + self.transform(a);
-        self.contour.offset = a - last_position;
+impl Pen for Builder {
+    fn move_to(&mut self, a: Offset) {
+        self.flush();
+        self.contour.offset = a - self.last;
         self.contour.position = a;
+        self.current = a;
     }
 
-    pub fn move_relative<T: Into<Offset>>(&mut self, a: T) {
-        let a = self.transform(a);
-        self.contour.offset += a;
-        self.contour.position += a;
-    }
-
-    pub fn move_control<T: Into<Offset>>(&mut self, a: T) {
-        let a = self.transform(a);
-        let b = match self.contour.segments.get_mut(0) {
-            Some(&mut Segment::Quadratic(ref mut b, _)) => b,
-            Some(&mut Segment::Cubic(ref mut b, _, _)) => b,
-            _ => return,
-        };
-        *b = a;
-    }
-}
-
-impl Builder {
-    pub fn add_linear<T: Into<Offset>>(&mut self, a: T) {
+    fn line_to(&mut self, a: Offset) {
         self.contour
             .segments
-            .push(Segment::Linear(self.transform(a)));
+            .push(Segment::Linear(a - self.current));
+        self.current = a;
     }
 
-    pub fn add_quadratic<T: Into<Offset>>(&mut self, a: T, b: T) {
+    fn quad_to(&mut self, a: Offset, b: Offset) {
         self.contour
             .segments
-            .push(Segment::Quadratic(self.transform(a), self.transform(b)));
-    }
-
-    pub fn add_cubic<T: Into<Offset>>(&mut self, a: T, b: T, c: T) {
-        self.contour.segments.push(Segment::Cubic(
-            self.transform(a),
-            self.transform(b),
-            self.transform(c),
-        ));
-    }
-}
-
-impl Builder {
-    #[inline]
-    pub fn set_bounding_box<T: Into<Number>>(
-        &mut self,
-        (min_x, min_y, max_x, max_y): (T, T, T, T),
-    ) {
-        self.glyph.bounding_box = (min_x.into(), min_y.into(), max_x.into(), max_y.into());
+            .push(Segment::Quadratic(a - self.current, b - a));
+        self.current = b;
     }
 
-    #[inline]
-    pub fn set_horizontal_metrics(&mut self, (advance_width, left_side_bearing): (Number, Number)) {
-        self.glyph.advance_width = advance_width;
-        self.glyph.side_bearings.0 = left_side_bearing;
+    fn curve_to(&mut self, a: Offset, b: Offset, c: Offset) {
+        self.contour
+            .segments
+            .push(Segment::Cubic(a - self.current, b - a, c - b));
+        self.current = c;
     }
-}
 
-impl Default for Builder {
     #[inline]
-    fn default() -> Self {
-        Self {
-            contour: Default::default(),
-            glyph: Default::default(),
-
-            offset: Default::default(),
-            scale: (1.0, 0.0, 0.0, 1.0),
-        }
+    fn close(&mut self) {
+        self.flush();
     }
 }
 
 impl From<Builder> for Glyph {
-    fn from(builder: Builder) -> Glyph {
+    fn from(mut builder: Builder) -> Glyph {
+        builder.flush();
         let Builder { mut glyph, .. } = builder;
         let width = glyph.bounding_box.2 - glyph.bounding_box.0;
         glyph.side_bearings.1 = glyph.advance_width - (glyph.side_bearings.0 + width);
@@ -0,0 +1,113 @@
+use crate::glyph::geometry::{length, Curve};
+use crate::glyph::{Contour, Glyph, Segment};
+use crate::{Number, Offset};
+
+const LIMIT: usize = 100;
+
+impl Glyph {
+    /// Convert quadratic curves into cubic ones.
+    ///
+    /// The conversion is exact, and the bounding box is left intact.
+    pub fn to_cubic(&self) -> Glyph {
+        self.convert(|curve, segments| match curve {
+            Curve::Quadratic(a, b, c) => {
+                let (b, c, d) = elevate(a, b, c);
+                segments.push(Segment::Cubic(b - a, c - b, d - c));
+            }
+            _ => segments.push(segment(curve)),
+        })
+    }
+
+    /// Approximate cubic curves with quadratic ones.
+    ///
+    /// The tolerance is the maximal distance in font units between each cubic
+    /// curve and the quadratic curves approximating it. The bounding box is
+    /// left intact.
+    pub fn to_quadratic(&self, tolerance: Number) -> Glyph {
+        self.convert(|curve, segments| match curve {
+            Curve::Cubic(a, b, c, d) => {
+                let mut current = a;
+                for (control, end) in approximate(a, b, c, d, tolerance) {
+                    segments.push(Segment::Quadratic(control - current, end - control));
+                    current = end;
+                }
+            }
+            _ => segments.push(segment(curve)),
+        })
+    }
+
+    fn convert<F>(&self, mut convert: F) -> Glyph
+    where
+        F: FnMut(Curve, &mut Vec<Segment>),
+    {
+        let contours = self
+            .iter()
+            .map(|contour| {
+                let mut segments = Vec::with_capacity(contour.len());
+                for curve in contour.curves() {
+                    convert(curve, &mut segments);
+                }
+                Contour {
+                    offset: contour.offset,
+                    position: contour.position,
+                    segments,
+                }
+            })
+            .collect();
+        Glyph {
+            contours,
+            ..self.clone()
+        }
+    }
+}
+
+/// Return the control points and the end point of the cubic curve equivalent
+/// to a quadratic one.
+pub(crate) fn elevate(a: Offset, b: Offset, c: Offset) -> (Offset, Offset, Offset) {
+    (a + (b - a) * (2.0 / 3.0), c + (b - c) * (2.0 / 3.0), c)
+}
+
+/// Return the control points and the end points of the quadratic curves
+/// approximating a cubic one within a tolerance.
+///
+/// The cubic curve is split into equal parts whose number is derived from the
+/// third derivative, and each part is replaced with the quadratic curve that
+/// shares its end points and whose control point is the average of the ones
+/// implied by the tangents at the two ends.
+pub(crate) fn approximate(
+    a: Offset,
+    b: Offset,
+    c: Offset,
+    d: Offset,
+    tolerance: Number,
+) -> Vec<(Offset, Offset)> {
+    let curve = Curve::Cubic(a, b, c, d);
+    let error = d - c * 3.0 + b * 3.0 - a;
+    let error = length(error) * 3f32.sqrt() / 36.0;
+    let count = match tolerance > 0.0 {
+        true => (error / tolerance).cbrt().ceil(),
+        _ => LIMIT as Number,
+    };
+    let count = count.clamp(1.0, LIMIT as Number) as usize;
+    (0..count)
+        .map(|i| {
+            let t0 = i as Number / count as Number;
+            let t1 = (i + 1) as Number / count as Number;
+            match curve.split(t0, t1) {
+                Curve::Cubic(p0, p1, p2, p3) => {
+                    let end = if i + 1 == count { d } else { p3 };
+                    ((p1 + p2) * 0.75 - (p0 + p3) * 0.25, end)
+                }
+                _ => unreachable!(),
+            }
+        })
+        .collect()
+}
+
+fn segment(curve: Curve) -> Segment {
+    match curve {
+        Curve::Linear(a, b) => Segment::Linear(b - a),
+        Curve::Quadratic(a, b, c) => Segment::Quadratic(b - a, c - b),
+        Curve::Cubic(a, b, c, d) => Segment::Cubic(b - a, c - b, d - c),
+    }
+}
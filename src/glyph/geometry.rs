@@ -0,0 +1,348 @@
+use crate::glyph::{Contour, Glyph, Segment};
+use crate::{Number, Offset};
+
+const NODES: [(Number, Number); 5] = [
+    (0.0, 0.568_888_9),
+    (-0.538_469_3, 0.478_628_7),
+    (0.538_469_3, 0.478_628_7),
+    (-0.906_179_8, 0.236_926_9),
+    (0.906_179_8, 0.236_926_9),
+];
+const PIECES: usize = 16;
+
+/// A direction of a contour.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Direction {
+    /// The clockwise direction.
+    Clockwise,
+    /// The counterclockwise direction.
+    Counterclockwise,
+}
+
+impl Glyph {
+    /// Compute the signed area.
+    ///
+    /// The area is the sum of the signed areas of the contours.
+    pub fn area(&self) -> Number {
+        self.iter().map(Contour::area).sum()
+    }
+
+    /// Compute the exact bounding box from the extrema of the curves.
+    ///
+    /// The edges are undefined if the glyph has no contours.
+    pub fn bounds(&self) -> (Number, Number, Number, Number) {
+        let mut minimum = Offset::undefined();
+        let mut maximum = Offset::undefined();
+        for contour in self.iter() {
+            let (left, bottom, right, top) = contour.bounds();
+            minimum = minimum.min(Offset(left, bottom));
+            maximum = maximum.max(Offset(right, top));
+        }
+        (minimum.0, minimum.1, maximum.0, maximum.1)
+    }
+
+    /// Approximate the contours with polylines.
+    ///
+    /// The tolerance is the maximal distance between the curves and the lines
+    /// approximating them.
+    pub fn flatten(&self, tolerance: Number) -> Vec<Vec<Offset>> {
+        self.iter()
+            .map(|contour| contour.flatten(tolerance))
+            .collect()
+    }
+
+    /// Compute the total length of the contours.
+    pub fn length(&self) -> Number {
+        self.iter().map(Contour::length).sum()
+    }
+}
+
+impl Contour {
+    /// Compute the signed area.
+    ///
+    /// The area is positive for counterclockwise contours and negative for
+    /// clockwise ones. The contour is considered closed.
+    pub fn area(&self) -> Number {
+        let mut value = 0.0;
+        let mut current = self.position;
+        for curve in self.curves() {
+            value += match curve {
+                Curve::Linear(a, b) => cross(a, b),
+                Curve::Quadratic(a, b, c) => {
+                    (2.0 * cross(a, b) + 2.0 * cross(b, c) + cross(a, c)) / 3.0
+                }
+                Curve::Cubic(a, b, c, d) => {
+                    (6.0 * cross(a, b)
+                        + 3.0 * cross(a, c)
+                        + cross(a, d)
+                        + 3.0 * cross(b, c)
+                        + 3.0 * cross(b, d)
+                        + 6.0 * cross(c, d))
+                        / 10.0
+                }
+            };
+            current = curve.end();
+        }
+        value += cross(current, self.position);
+        value / 2.0
+    }
+
+    /// Compute the exact bounding box from the extrema of the curves.
+    pub fn bounds(&self) -> (Number, Number, Number, Number) {
+        let mut minimum = self.position;
+        let mut maximum = self.position;
+        for curve in self.curves() {
+            for point in curve.extrema() {
+                minimum = minimum.min(point);
+                maximum = maximum.max(point);
+            }
+        }
+        (minimum.0, minimum.1, maximum.0, maximum.1)
+    }
+
+    /// Return the direction.
+    ///
+    /// The direction is inferred from the sign of the area, and contours with
+    /// no area are considered counterclockwise.
+    pub fn direction(&self) -> Direction {
+        if self.area() < 0.0 {
+            Direction::Clockwise
+        } else {
+            Direction::Counterclockwise
+        }
+    }
+
+    /// Approximate the contour with a polyline.
+    ///
+    /// The first point is the position of the contour, and the contour is
+    /// considered closed. The tolerance is the maximal distance between the
+    /// curves and the lines approximating them.
+    pub fn flatten(&self, tolerance: Number) -> Vec<Offset> {
+        let mut points = vec![self.position];
+        for curve in self.curves() {
+            let deviation = curve.deviation();
+            let count = if deviation > 0.0 && tolerance > 0.0 {
+                ((deviation / tolerance).sqrt().ceil() as usize).max(1)
+            } else {
+                1
+            };
+            for i in 1..=count {
+                points.push(curve.evaluate(i as Number / count as Number));
+            }
+        }
+        points
+    }
+
+    /// Compute the length.
+    ///
+    /// The contour is considered closed, so the length includes the line from
+    /// the end back to the position if they differ.
+    pub fn length(&self) -> Number {
+        let mut value = 0.0;
+        let mut end = self.position;
+        for curve in self.curves() {
+            end = curve.end();
+            value += match curve {
+                Curve::Linear(a, b) => length(b - a),
+                _ => {
+                    let step = 1.0 / PIECES as Number;
+                    let mut sum = 0.0;
+                    for i in 0..PIECES {
+                        let middle = (i as Number + 0.5) * step;
+                        for (node, weight) in NODES {
+                            let t = middle + node * step / 2.0;
+                            sum += weight * length(curve.derivative(t)) * step / 2.0;
+                        }
+                    }
+                    sum
+                }
+            };
+        }
+        value + length(self.position - end)
+    }
+
+    pub(crate) fn curves(&self) -> impl Iterator<Item = Curve> + '_ {
+        let mut current = self.position;
+        self.segments.iter().map(move |segment| {
+            let start = current;
+            let curve = match *segment {
+                Segment::Linear(a) => Curve::Linear(start, start + a),
+                Segment::Quadratic(a, b) => Curve::Quadratic(start, start + a, start + a + b),
+                Segment::Cubic(a, b, c) => {
+                    Curve::Cubic(start, start + a, start + a + b, start + a + b + c)
+                }
+            };
+            current = curve.end();
+            curve
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Curve {
+    Linear(Offset, Offset),
+    Quadratic(Offset, Offset, Offset),
+    Cubic(Offset, Offset, Offset, Offset),
+}
+
+impl Curve {
+    pub fn start(&self) -> Offset {
+        match *self {
+            Self::Linear(a, _) | Self::Quadratic(a, _, _) | Self::Cubic(a, _, _, _) => a,
+        }
+    }
+
+    pub fn end(&self) -> Offset {
+        match *self {
+            Self::Linear(_, b) | Self::Quadratic(_, _, b) | Self::Cubic(_, _, _, b) => b,
+        }
+    }
+
+    pub fn evaluate(&self, t: Number) -> Offset {
+        let u = 1.0 - t;
+        match *self {
+            Self::Linear(a, b) => a * u + b * t,
+            Self::Quadratic(a, b, c) => a * (u * u) + b * (2.0 * u * t) + c * (t * t),
+            Self::Cubic(a, b, c, d) => {
+                a * (u * u * u) + b * (3.0 * u * u * t) + c * (3.0 * u * t * t) + d * (t * t * t)
+            }
+        }
+    }
+
+    pub fn derivative(&self, t: Number) -> Offset {
+        let u = 1.0 - t;
+        match *self {
+            Self::Linear(a, b) => b - a,
+            Self::Quadratic(a, b, c) => (b - a) * (2.0 * u) + (c - b) * (2.0 * t),
+            Self::Cubic(a, b, c, d) => {
+                (b - a) * (3.0 * u * u) + (c - b) * (6.0 * u * t) + (d - c) * (3.0 * t * t)
+            }
+        }
+    }
+
+    pub fn second_derivative(&self, t: Number) -> Offset {
+        match *self {
+            Self::Linear(..) => Offset::default(),
+            Self::Quadratic(a, b, c) => (a - b * 2.0 + c) * 2.0,
+            Self::Cubic(a, b, c, d) => {
+                (a - b * 2.0 + c) * (6.0 * (1.0 - t)) + (b - c * 2.0 + d) * (6.0 * t)
+            }
+        }
+    }
+
+    /// Return a bound on the distance between the curve and the line joining
+    /// its ends, which decreases with the square of the number of equal pieces
+    /// the curve is divided into.
+    pub fn deviation(&self) -> Number {
+        match *self {
+            Self::Linear(..) => 0.0,
+            Self::Quadratic(a, b, c) => length(a - b * 2.0 + c) / 4.0,
+            Self::Cubic(a, b, c, d) => 0.75 * length(a - b * 2.0 + c).max(length(b - c * 2.0 + d)),
+        }
+    }
+
+    /// Return the control points including the ends.
+    pub fn points(&self) -> Vec<Offset> {
+        match *self {
+            Self::Linear(a, b) => vec![a, b],
+            Self::Quadratic(a, b, c) => vec![a, b, c],
+            Self::Cubic(a, b, c, d) => vec![a, b, c, d],
+        }
+    }
+
+    /// Return the part of the curve between two parameters, which is reversed
+    /// if the first parameter is greater than the second one.
+    pub fn split(&self, t0: Number, t1: Number) -> Self {
+        let blossom = |points: &[Offset], count: usize| {
+            let mut points = points.to_vec();
+            for k in 0..(points.len() - 1) {
+                let t = if k < count { t1 } else { t0 };
+                for i in 0..(points.len() - 1 - k) {
+                    points[i] = points[i] * (1.0 - t) + points[i + 1] * t;
+                }
+            }
+            points[0]
+        };
+        match *self {
+            Self::Linear(a, b) => {
+                let points = [a, b];
+                Self::Linear(blossom(&points, 0), blossom(&points, 1))
+            }
+            Self::Quadratic(a, b, c) => {
+                let points = [a, b, c];
+                Self::Quadratic(
+                    blossom(&points, 0),
+                    blossom(&points, 1),
+                    blossom(&points, 2),
+                )
+            }
+            Self::Cubic(a, b, c, d) => {
+                let points = [a, b, c, d];
+                Self::Cubic(
+                    blossom(&points, 0),
+                    blossom(&points, 1),
+                    blossom(&points, 2),
+                    blossom(&points, 3),
+                )
+            }
+        }
+    }
+
+    /// Return the points at which the curve reaches its extrema along either
+    /// axis, followed by the end point.
+    pub fn extrema(&self) -> Vec<Offset> {
+        let mut values = vec![];
+        match *self {
+            Self::Linear(..) => {}
+            Self::Quadratic(a, b, c) => {
+                for (a, b, c) in [(a.0, b.0, c.0), (a.1, b.1, c.1)] {
+                    let denominator = a - 2.0 * b + c;
+                    if denominator != 0.0 {
+                        values.push((a - b) / denominator);
+                    }
+                }
+            }
+            Self::Cubic(a, b, c, d) => {
+                for (a, b, c, d) in [(a.0, b.0, c.0, d.0), (a.1, b.1, c.1, d.1)] {
+                    let x = -a + 3.0 * b - 3.0 * c + d;
+                    let y = 2.0 * (a - 2.0 * b + c);
+                    let z = b - a;
+                    if x.abs() < Number::EPSILON {
+                        if y != 0.0 {
+                            values.push(-z / y);
+                        }
+                        continue;
+                    }
+                    let discriminant = y * y - 4.0 * x * z;
+                    if discriminant >= 0.0 {
+                        let root = discriminant.sqrt();
+                        values.push((-y + root) / (2.0 * x));
+                        values.push((-y - root) / (2.0 * x));
+                    }
+                }
+            }
+        }
+        let mut points = values
+            .into_iter()
+            .filter(|t| *t > 0.0 && *t < 1.0)
+            .map(|t| self.evaluate(t))
+            .collect::<Vec<_>>();
+        points.push(self.end());
+        points
+    }
+}
+
+#[inline]
+pub(crate) fn cross(one: Offset, other: Offset) -> Number {
+    one.0 * other.1 - one.1 * other.0
+}
+
+#[inline]
+pub(crate) fn dot(one: Offset, other: Offset) -> Number {
+    one.0 * other.0 + one.1 * other.1
+}
+
+#[inline]
+pub(crate) fn length(value: Offset) -> Number {
+    value.0.hypot(value.1)
+}
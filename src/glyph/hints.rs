@@ -0,0 +1,59 @@
+use crate::Number;
+
+/// Hints of a glyph.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Hints {
+    /// The horizontal stems given by their lower and upper edges.
+    ///
+    /// The edges are as given in the font, so ghost stems have negative
+    /// widths.
+    pub horizontal: Vec<(Number, Number)>,
+    /// The vertical stems given by their left and right edges.
+    pub vertical: Vec<(Number, Number)>,
+    /// The hint masks in the order of the segments they apply to.
+    pub masks: Vec<Mask>,
+    /// The counter masks.
+    pub counters: Vec<Vec<bool>>,
+}
+
+/// A hint mask.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mask {
+    /// The index of the first segment the mask applies to counting the
+    /// segments of all contours in order.
+    pub segment: usize,
+    /// The flags of the active stems, horizontal ones followed by vertical
+    /// ones.
+    pub active: Vec<bool>,
+}
+
+/// Alignment zones and standard stem widths of a font.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Alignment {
+    /// The zones at the baseline and the tops of characters given by their
+    /// lower and upper edges.
+    pub blue_values: Vec<(Number, Number)>,
+    /// The zones at the bottoms of characters given by their lower and upper
+    /// edges.
+    pub other_blues: Vec<(Number, Number)>,
+    /// The dominant width of horizontal stems.
+    pub standard_horizontal_width: Option<Number>,
+    /// The dominant width of vertical stems.
+    pub standard_vertical_width: Option<Number>,
+}
+
+impl Hints {
+    /// Return the flags of the stems active for a segment.
+    ///
+    /// The segment is counted across all contours in order. All stems are
+    /// active if there are no hint masks.
+    pub fn active(&self, segment: usize) -> Vec<bool> {
+        match self.masks.iter().rev().find(|mask| mask.segment <= segment) {
+            Some(mask) => mask.active.clone(),
+            _ => match self.masks.first() {
+                Some(mask) => mask.active.clone(),
+                _ => vec![true; self.horizontal.len() + self.vertical.len()],
+            },
+        }
+    }
+}
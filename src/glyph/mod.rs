@@ -1,8 +1,24 @@
 //! Glyphs.
 
+mod autohint;
+mod boolean;
 mod builder;
+mod hints;
+mod pen;
 
-pub(crate) use builder::Builder;
+pub(crate) mod conversion;
+pub(crate) mod geometry;
+pub(crate) mod svg;
+pub(crate) mod transform;
+
+pub use boolean::Operation;
+pub use builder::Builder;
+pub use geometry::Direction;
+pub use hints::{Alignment, Hints, Mask};
+pub use pen::Pen;
+pub use transform::Transform;
+
+use opentype::truetype::GlyphID;
 
 use crate::{Number, Offset};
 
@@ -17,6 +33,22 @@ pub struct Glyph {
     pub side_bearings: (Number, Number),
     /// The contours.
     pub contours: Vec<Contour>,
+    /// The components if the glyph is a composite one.
+    pub components: Vec<Component>,
+    /// The hints if the font provides them.
+    pub hints: Option<Hints>,
+}
+
+/// A component of a composite glyph.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Component {
+    /// The glyph identifier.
+    pub glyph_id: GlyphID,
+    /// The offset.
+    pub offset: Offset,
+    /// The 2-by-2 transformation matrix given as `(xx, xy, yx, yy)`, which
+    /// maps a point `(x, y)` to `(xx * x + xy * y, yx * x + yy * y)`.
+    pub scale: (Number, Number, Number, Number),
 }
 
 /// A contour.
@@ -30,6 +62,15 @@ pub struct Contour {
     pub segments: Vec<Segment>,
 }
 
+/// A point in absolute coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Point {
+    /// A point on the curve.
+    On(Offset),
+    /// A control point off the curve.
+    Off(Offset),
+}
+
 /// A segment.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Segment {
@@ -55,6 +96,118 @@ impl Glyph {
     }
 }
 
+impl Glyph {
+    /// Return the points of the contours.
+    pub fn points(&self) -> Vec<Vec<Point>> {
+        self.contours
+            .iter()
+            .map(|contour| contour.points().collect())
+            .collect()
+    }
+
+    /// Replace the contours with ones given as points.
+    ///
+    /// Each contour starts with its first point. A single control point
+    /// preceding a point on the curve makes a quadratic curve, and two make a
+    /// cubic one. Longer runs of control points make quadratic curves with
+    /// implied points on the curve halfway between consecutive control points.
+    /// Contours are closed implicitly, and the bounding box is left intact.
+    pub fn set_points<T, U>(&mut self, contours: T)
+    where
+        T: IntoIterator<Item = U>,
+        U: IntoIterator<Item = Point>,
+    {
+        let mut builder = Builder::default();
+        for contour in contours {
+            let mut points = contour.into_iter();
+            let start = match points.next() {
+                Some(value) => value.offset(),
+                _ => continue,
+            };
+            builder.move_to(start);
+            let mut controls = vec![];
+            for point in points {
+                match point {
+                    Point::Off(value) => controls.push(value),
+                    Point::On(value) => {
+                        draw(&mut builder, &controls, value);
+                        controls.clear();
+                    }
+                }
+            }
+            if !controls.is_empty() {
+                draw(&mut builder, &controls, start);
+            }
+            builder.close();
+        }
+        self.contours = Glyph::from(builder).contours;
+    }
+}
+
+impl Contour {
+    /// Iterate over the points.
+    ///
+    /// The first point is the position of the contour, and the others follow
+    /// the segments, which the points represent exactly.
+    pub fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        let mut current = self.position;
+        std::iter::once(Point::On(current)).chain(self.segments.iter().flat_map(move |segment| {
+            let mut points = Vec::with_capacity(3);
+            match *segment {
+                Segment::Linear(a) => {
+                    current += a;
+                    points.push(Point::On(current));
+                }
+                Segment::Quadratic(a, b) => {
+                    current += a;
+                    points.push(Point::Off(current));
+                    current += b;
+                    points.push(Point::On(current));
+                }
+                Segment::Cubic(a, b, c) => {
+                    current += a;
+                    points.push(Point::Off(current));
+                    current += b;
+                    points.push(Point::Off(current));
+                    current += c;
+                    points.push(Point::On(current));
+                }
+            }
+            points
+        }))
+    }
+}
+
+impl Point {
+    /// Return the position.
+    #[inline]
+    pub fn offset(self) -> Offset {
+        match self {
+            Self::On(value) | Self::Off(value) => value,
+        }
+    }
+
+    /// Check if the point is on the curve.
+    #[inline]
+    pub fn is_on_curve(&self) -> bool {
+        matches!(self, Self::On(_))
+    }
+}
+
+fn draw(pen: &mut dyn Pen, controls: &[Offset], end: Offset) {
+    match *controls {
+        [] => pen.line_to(end),
+        [a] => pen.quad_to(a, end),
+        [a, b] => pen.curve_to(a, b, end),
+        _ => {
+            for pair in controls.windows(2) {
+                pen.quad_to(pair[0], (pair[0] + pair[1]) / 2.0);
+            }
+            pen.quad_to(controls[controls.len() - 1], end);
+        }
+    }
+}
+
 dereference! { Glyph::contours => [Contour] }
 
 dereference! { Contour::segments => [Segment] }
@@ -67,6 +220,8 @@ impl Default for Glyph {
             bounding_box: (Number::NAN, Number::NAN, Number::NAN, Number::NAN),
             side_bearings: (Number::NAN, Number::NAN),
             contours: Default::default(),
+            components: Default::default(),
+            hints: Default::default(),
         }
     }
 }
@@ -0,0 +1,22 @@
+use crate::Offset;
+
+/// A consumer of outlines.
+///
+/// All points are given in absolute coordinates. Control points precede the
+/// end point of the curve they belong to.
+pub trait Pen {
+    /// Start a new contour at a point.
+    fn move_to(&mut self, a: Offset);
+
+    /// Draw a line to a point.
+    fn line_to(&mut self, a: Offset);
+
+    /// Draw a quadratic Bézier curve to a point.
+    fn quad_to(&mut self, a: Offset, b: Offset);
+
+    /// Draw a cubic Bézier curve to a point.
+    fn curve_to(&mut self, a: Offset, b: Offset, c: Offset);
+
+    /// Close the current contour.
+    fn close(&mut self);
+}
@@ -0,0 +1,52 @@
+use crate::glyph::{Glyph, Point};
+use crate::Number;
+
+impl Glyph {
+    /// Return the contours as the data of an SVG path.
+    ///
+    /// The coordinates are multiplied by the scale, which is typically the
+    /// font size divided by the granularity of the font, and the vertical axis
+    /// is flipped if requested so that the baseline is at zero and the path
+    /// extends upward as it does in SVG.
+    pub fn to_svg_path(&self, scale: Number, flip: bool) -> String {
+        let y_scale = if flip { -scale } else { scale };
+        let point = |value: Point| {
+            let value = value.offset();
+            format!("{},{}", number(value.0 * scale), number(value.1 * y_scale))
+        };
+        let mut commands = vec![];
+        for contour in self.contours.iter() {
+            let mut points = contour.points();
+            let start = match points.next() {
+                Some(value) => value,
+                _ => continue,
+            };
+            commands.push(format!("M{}", point(start)));
+            let mut controls = vec![];
+            for value in points {
+                if !value.is_on_curve() {
+                    controls.push(point(value));
+                    continue;
+                }
+                let command = match controls.len() {
+                    0 => 'L',
+                    1 => 'Q',
+                    _ => 'C',
+                };
+                controls.push(point(value));
+                commands.push(format!("{}{}", command, controls.join(",")));
+                controls.clear();
+            }
+            commands.push("Z".into());
+        }
+        commands.join(" ")
+    }
+}
+
+pub(crate) fn number(value: Number) -> String {
+    let value = (value * 1000.0).round() / 1000.0;
+    if value == 0.0 {
+        return "0".into();
+    }
+    format!("{value}")
+}
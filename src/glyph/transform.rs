@@ -0,0 +1,207 @@
+use crate::glyph::geometry::{dot, length, Curve};
+use crate::glyph::{Builder, Component, Glyph, Pen, Segment};
+use crate::{Number, Offset};
+
+impl Glyph {
+    /// Apply an affine transformation.
+    ///
+    /// The matrix is given as `(xx, xy, yx, yy, x, y)`, which maps a point
+    /// `(a, b)` to `(xx * a + xy * b + x, yx * a + yy * b + y)`. The advance
+    /// width is scaled horizontally, and the bounding box and side bearings
+    /// are recomputed. The components are transformed along, and the hints
+    /// are left out, as they no longer apply.
+    pub fn transform(&self, matrix: (Number, Number, Number, Number, Number, Number)) -> Glyph {
+        let xx = matrix.0;
+        let mut builder = Builder::default();
+        let mut pen = Transform::new(&mut builder, matrix);
+        for contour in self.iter() {
+            pen.move_to(contour.position);
+            for curve in contour.curves() {
+                match curve {
+                    Curve::Linear(_, a) => pen.line_to(a),
+                    Curve::Quadratic(_, a, b) => pen.quad_to(a, b),
+                    Curve::Cubic(_, a, b, c) => pen.curve_to(a, b, c),
+                }
+            }
+            pen.close();
+        }
+        let mut glyph = self.adjust(builder.into(), xx * self.origin(), xx * self.advance_width);
+        glyph.components = self
+            .components
+            .iter()
+            .map(|component| component.transform(matrix))
+            .collect();
+        glyph
+    }
+
+    /// Slant by an angle.
+    ///
+    /// The angle is given in degrees counterclockwise from the vertical, which
+    /// is the convention of italic angles and the slant axis, and the baseline
+    /// stays in place.
+    pub fn slant(&self, angle: Number) -> Glyph {
+        self.transform((1.0, -angle.to_radians().tan(), 0.0, 1.0, 0.0, 0.0))
+    }
+
+    /// Embolden by offsetting the outline along its normals.
+    ///
+    /// The strength is the distance in font units by which the outline moves
+    /// outward, so stems become thicker by twice the strength. The advance
+    /// width grows by twice the strength as well, and the outline is moved to
+    /// the right by the strength to keep the left side bearing. The components
+    /// and hints are left out.
+    pub fn embolden(&self, strength: Number) -> Glyph {
+        let sign = if self.area() < 0.0 { 1.0 } else { -1.0 };
+        let mut builder = Builder::default();
+        for contour in self.iter() {
+            let points = contour
+                .points()
+                .map(|point| point.offset())
+                .collect::<Vec<_>>();
+            let mut points = (0..points.len())
+                .map(|i| points[i] + shift(&points, i, sign * strength) + Offset(strength, 0.0));
+            let mut next = || points.next().unwrap_or_default();
+            builder.move_to(next());
+            for segment in contour.iter() {
+                match segment {
+                    Segment::Linear(..) => builder.line_to(next()),
+                    Segment::Quadratic(..) => builder.quad_to(next(), next()),
+                    Segment::Cubic(..) => builder.curve_to(next(), next(), next()),
+                }
+            }
+            builder.close();
+        }
+        self.adjust(
+            builder.into(),
+            self.origin(),
+            self.advance_width + 2.0 * strength,
+        )
+    }
+
+    pub(crate) fn origin(&self) -> Number {
+        let origin = self.bounding_box.0 - self.side_bearings.0;
+        if origin.is_finite() {
+            origin
+        } else {
+            0.0
+        }
+    }
+
+    pub(crate) fn adjust(&self, mut glyph: Glyph, origin: Number, advance_width: Number) -> Glyph {
+        glyph.bounding_box = glyph.bounds();
+        glyph.advance_width = advance_width;
+        glyph.side_bearings.0 = if glyph.bounding_box.0.is_finite() {
+            glyph.bounding_box.0 - origin
+        } else {
+            self.side_bearings.0
+        };
+        glyph.side_bearings.1 = glyph.advance_width - (glyph.side_bearings.0 + glyph.width());
+        glyph
+    }
+}
+
+impl Component {
+    fn transform(&self, matrix: (Number, Number, Number, Number, Number, Number)) -> Component {
+        let (xx, xy, yx, yy, x, y) = matrix;
+        let (a, b, c, d) = self.scale;
+        let Offset(u, v) = self.offset;
+        Component {
+            glyph_id: self.glyph_id,
+            offset: Offset(xx * u + xy * v + x, yx * u + yy * v + y),
+            scale: (
+                xx * a + xy * c,
+                xx * b + xy * d,
+                yx * a + yy * c,
+                yx * b + yy * d,
+            ),
+        }
+    }
+}
+
+/// A pen applying an affine transformation before passing the outline on to
+/// another pen.
+///
+/// Transformations compose by wrapping one pen in another, in which case the
+/// transformation of the pen drawn into is applied first.
+pub struct Transform<'l> {
+    pub(crate) pen: &'l mut dyn Pen,
+    pub(crate) offset: Offset,
+    pub(crate) scale: (Number, Number, Number, Number),
+}
+
+impl<'l> Transform<'l> {
+    /// Create a pen given the matrix in the same form as for
+    /// `Glyph::transform`.
+    #[inline]
+    pub fn new(
+        pen: &'l mut dyn Pen,
+        matrix: (Number, Number, Number, Number, Number, Number),
+    ) -> Self {
+        let (xx, xy, yx, yy, x, y) = matrix;
+        Self {
+            pen,
+            offset: Offset(x, y),
+            scale: (xx, xy, yx, yy),
+        }
+    }
+
+    #[inline]
+    fn apply(&self, value: Offset) -> Offset {
+        let (xx, xy, yx, yy) = self.scale;
+        self.offset + Offset(xx * value.0 + xy * value.1, yx * value.0 + yy * value.1)
+    }
+}
+
+impl Pen for Transform<'_> {
+    #[inline]
+    fn move_to(&mut self, a: Offset) {
+        let a = self.apply(a);
+        self.pen.move_to(a);
+    }
+
+    #[inline]
+    fn line_to(&mut self, a: Offset) {
+        let a = self.apply(a);
+        self.pen.line_to(a);
+    }
+
+    #[inline]
+    fn quad_to(&mut self, a: Offset, b: Offset) {
+        let (a, b) = (self.apply(a), self.apply(b));
+        self.pen.quad_to(a, b);
+    }
+
+    #[inline]
+    fn curve_to(&mut self, a: Offset, b: Offset, c: Offset) {
+        let (a, b, c) = (self.apply(a), self.apply(b), self.apply(c));
+        self.pen.curve_to(a, b, c);
+    }
+
+    #[inline]
+    fn close(&mut self) {
+        self.pen.close();
+    }
+}
+
+// The shift of a point is along the bisector of the normals of the adjacent
+// edges, and its length is such that both edges move by the distance, which is
+// limited for sharp corners.
+fn shift(points: &[Offset], i: usize, distance: Number) -> Offset {
+    let count = points.len();
+    let point = points[i];
+    let find = |step: usize| {
+        (1..count)
+            .map(|j| points[(i + j * step) % count])
+            .find(|&other| length(other - point) > 1e-6)
+    };
+    let (previous, next) = match (find(count - 1), find(1)) {
+        (Some(previous), Some(next)) => (previous, next),
+        _ => return Offset::default(),
+    };
+    let normal = |a: Offset, b: Offset| {
+        let value = (b - a) / length(b - a);
+        Offset(-value.1, value.0)
+    };
+    let (one, other) = (normal(previous, point), normal(point, next));
+    (one + other) * (distance / (1.0 + dot(one, other)).max(0.125))
+}
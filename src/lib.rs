@@ -28,11 +28,15 @@ pub extern crate opentype;
 #[cfg(feature = "webtype")]
 pub extern crate webtype;
 
-#[macro_use(dereference, error, raise)]
+#[macro_use(dereference, error, raise, table)]
 extern crate typeface;
 
+pub mod atlas;
+pub mod distance;
 pub mod formats;
 pub mod glyph;
+pub mod raster;
+pub mod svg;
 
 mod file;
 mod font;
@@ -45,7 +49,11 @@ pub use typeface::tape::{Read, Write};
 pub use self::file::File;
 pub use self::font::{Case, Font};
 pub use self::formats::opentype::axes::{self, Axes};
+pub use self::formats::opentype::bitmaps::{self, Bitmap as EmbeddedBitmap};
 pub use self::formats::opentype::characters::{self, Characters};
+pub use self::formats::opentype::devices::{
+    self, DeviceWidths, GridFitting, LinearThresholds, VerticalDeviceMetrics,
+};
 pub use self::formats::opentype::features::{self, Directory as Features};
 pub use self::formats::opentype::names::Names;
 pub use self::formats::opentype::palettes::Palettes;
@@ -0,0 +1,218 @@
+//! Rasterization.
+
+use crate::glyph::geometry::Curve;
+use crate::glyph::{Glyph, Segment};
+use crate::{Number, Offset};
+
+const TOLERANCE: Number = 0.05;
+
+/// A coverage bitmap.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Bitmap {
+    /// The number of columns.
+    pub width: usize,
+    /// The number of rows.
+    pub height: usize,
+    /// The horizontal position of the left edge relative to the origin.
+    pub left: i32,
+    /// The vertical position of the top edge relative to the baseline.
+    pub top: i32,
+    /// The coverage row by row from top to bottom.
+    pub data: Vec<u8>,
+}
+
+impl Bitmap {
+    /// Return the coverage of a pixel.
+    #[inline]
+    pub fn get(&self, x: usize, y: usize) -> Option<u8> {
+        if x < self.width {
+            self.data.get(y * self.width + x).cloned()
+        } else {
+            None
+        }
+    }
+}
+
+/// Rasterize a glyph.
+///
+/// The scale converts font units into pixels and is typically the size in
+/// pixels divided by the granularity of the font. The offset is given in
+/// pixels and allows for positioning at subpixel precision. The coverage is
+/// computed from the exact area of each pixel the outline covers, and
+/// overlapping contours are filled according to the non-zero winding rule.
+pub fn rasterize(glyph: &Glyph, scale: Number, offset: Offset) -> Bitmap {
+    let mut paths = vec![];
+    let mut minimum = Offset::undefined();
+    let mut maximum = Offset::undefined();
+    let mut map = |value: Offset| {
+        let value = value * scale + offset;
+        minimum = minimum.min(value);
+        maximum = maximum.max(value);
+        value
+    };
+    for contour in glyph.iter() {
+        let mut current = contour.position;
+        let mut path = vec![Element::Linear(map(current))];
+        for segment in contour.iter() {
+            match *segment {
+                Segment::Linear(a) => {
+                    current += a;
+                    path.push(Element::Linear(map(current)));
+                }
+                Segment::Quadratic(a, b) => {
+                    let a = current + a;
+                    current = a + b;
+                    path.push(Element::Quadratic(map(a), map(current)));
+                }
+                Segment::Cubic(a, b, c) => {
+                    let a = current + a;
+                    let b = a + b;
+                    current = b + c;
+                    path.push(Element::Cubic(map(a), map(b), map(current)));
+                }
+            }
+        }
+        paths.push(path);
+    }
+    if !minimum.0.is_finite() || !minimum.1.is_finite() {
+        return Bitmap::default();
+    }
+    let left = minimum.0.floor();
+    let top = maximum.1.ceil();
+    let width = (maximum.0.ceil() - left) as usize;
+    let height = (top - minimum.1.floor()) as usize;
+    let mut accumulator = Accumulator::new(width, height);
+    let map = |value: Offset| Offset(value.0 - left, top - value.1);
+    for path in paths {
+        let mut current = Offset::default();
+        let mut start = Offset::default();
+        for (i, element) in path.into_iter().enumerate() {
+            match element {
+                Element::Linear(a) if i == 0 => {
+                    start = map(a);
+                    current = start;
+                }
+                Element::Linear(a) => {
+                    let a = map(a);
+                    accumulator.line(current, a);
+                    current = a;
+                }
+                Element::Quadratic(a, b) => {
+                    let (a, b) = (map(a), map(b));
+                    accumulator.curve(Curve::Quadratic(current, a, b));
+                    current = b;
+                }
+                Element::Cubic(a, b, c) => {
+                    let (a, b, c) = (map(a), map(b), map(c));
+                    accumulator.curve(Curve::Cubic(current, a, b, c));
+                    current = c;
+                }
+            }
+        }
+        accumulator.line(current, start);
+    }
+    Bitmap {
+        width,
+        height,
+        left: left as i32,
+        top: top as i32,
+        data: accumulator.finish(),
+    }
+}
+
+enum Element {
+    Linear(Offset),
+    Quadratic(Offset, Offset),
+    Cubic(Offset, Offset, Offset),
+}
+
+// The signed area each edge contributes to a pixel is stored in the pixel, and
+// the part that spills over to the right, in the next one; the coverage is
+// then the absolute value of the running sum along each row.
+struct Accumulator {
+    width: usize,
+    height: usize,
+    cells: Vec<Number>,
+}
+
+impl Accumulator {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![0.0; width * height + 2],
+        }
+    }
+
+    fn line(&mut self, a: Offset, b: Offset) {
+        if a.1 == b.1 {
+            return;
+        }
+        let (direction, a, b) = if a.1 < b.1 { (1.0, a, b) } else { (-1.0, b, a) };
+        let limit = self.width as Number;
+        let slope = (b.0 - a.0) / (b.1 - a.1);
+        let mut x = a.0;
+        let start = a.1.max(0.0);
+        if a.1 < 0.0 {
+            x -= a.1 * slope;
+        }
+        let end = (b.1.ceil() as usize).min(self.height);
+        for y in (start as usize)..end {
+            let row = y * self.width;
+            let height = ((y + 1) as Number).min(b.1) - (y as Number).max(a.1);
+            let next = x + slope * height;
+            let area = height * direction;
+            let (x0, x1) = if x < next { (x, next) } else { (next, x) };
+            let (x0, x1) = (x0.clamp(0.0, limit), x1.clamp(0.0, limit));
+            let (x0_floor, x1_ceil) = (x0.floor(), x1.ceil());
+            let (i0, i1) = (x0_floor as usize, x1_ceil as usize);
+            if i1 <= i0 + 1 {
+                let middle = 0.5 * (x0 + x1) - x0_floor;
+                self.cells[row + i0] += area - area * middle;
+                self.cells[row + i0 + 1] += area * middle;
+            } else {
+                let scale = (x1 - x0).recip();
+                let x0_fraction = x0 - x0_floor;
+                let first = 0.5 * scale * (1.0 - x0_fraction) * (1.0 - x0_fraction);
+                let x1_fraction = x1 - x1_ceil + 1.0;
+                let last = 0.5 * scale * x1_fraction * x1_fraction;
+                self.cells[row + i0] += area * first;
+                if i1 == i0 + 2 {
+                    self.cells[row + i0 + 1] += area * (1.0 - first - last);
+                } else {
+                    let second = scale * (1.5 - x0_fraction);
+                    self.cells[row + i0 + 1] += area * (second - first);
+                    for i in (i0 + 2)..(i1 - 1) {
+                        self.cells[row + i] += area * scale;
+                    }
+                    let penultimate = second + (i1 - i0 - 3) as Number * scale;
+                    self.cells[row + i1 - 1] += area * (1.0 - penultimate - last);
+                }
+                self.cells[row + i1] += area * last;
+            }
+            x = next;
+        }
+    }
+
+    fn curve(&mut self, curve: Curve) {
+        let count = ((curve.deviation() / TOLERANCE).sqrt().ceil() as usize).clamp(1, 100);
+        let mut current = curve.start();
+        for i in 1..=count {
+            let next = curve.evaluate(i as Number / count as Number);
+            self.line(current, next);
+            current = next;
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        let mut sum = 0.0;
+        self.cells
+            .iter()
+            .take(self.width * self.height)
+            .map(|value| {
+                sum += value;
+                (sum.abs().min(1.0) * 255.0).round() as u8
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,55 @@
+//! Scalable vector graphics.
+
+use std::io::Result;
+
+use crate::glyph::svg::number;
+use crate::{Font, Number};
+
+/// Lay out a text in a single line and render it as an SVG document.
+///
+/// The glyphs are placed from left to right according to their advance widths
+/// and the kerning of the font, and characters the font has no glyphs for are
+/// skipped. The size is the font size in pixels.
+pub fn document<T>(font: &mut Font<T>, text: &str, size: Number) -> Result<String>
+where
+    T: crate::Read,
+{
+    let metrics = font.metrics()?;
+    let scale = size / metrics.granularity;
+    let mut paths = vec![];
+    let mut x = 0.0;
+    let mut previous = None;
+    for character in text.chars() {
+        let glyph = match font.glyph(character)? {
+            Some(glyph) => glyph,
+            _ => continue,
+        };
+        if let Some(previous) = previous {
+            x += font.kerning(previous, character)?;
+        }
+        if !glyph.is_empty() {
+            paths.push(format!(
+                r#"<path transform="translate({} {})" d="{}"/>"#,
+                number(x * scale),
+                number(metrics.ascender * scale),
+                glyph.to_svg_path(scale, true),
+            ));
+        }
+        x += glyph.advance_width;
+        previous = Some(character);
+    }
+    let width = (x * scale).ceil().max(0.0);
+    let height = ((metrics.ascender - metrics.descender) * scale)
+        .ceil()
+        .max(0.0);
+    let mut document = format!(
+        "<svg height=\"{height}\" width=\"{width}\" xmlns=\"http://www.w3.org/2000/svg\">\n"
+    );
+    document.push_str("<style>\npath { fill: black; fill-rule: nonzero; }\n</style>\n");
+    for path in paths {
+        document.push_str(&path);
+        document.push('\n');
+    }
+    document.push_str("</svg>\n");
+    Ok(document)
+}
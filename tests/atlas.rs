@@ -0,0 +1,107 @@
+#[macro_use]
+mod support;
+
+use font::atlas::GlyphAtlas;
+use font::characters::Character;
+use font::raster;
+use font::Offset;
+
+use crate::support::{setup, Fixture};
+
+#[test]
+fn kerning() {
+    for fixture in [Fixture::OpenSans, Fixture::SourceSerif] {
+        let font = &mut setup(fixture)[0];
+        let characters = (' '..='~').collect::<Vec<_>>();
+        let mut values = vec![];
+        for &one in characters.iter() {
+            for &other in characters.iter() {
+                let value = ok!(font.kerning(one, other));
+                if value != 0.0 {
+                    values.push((one, other, value));
+                }
+            }
+        }
+        assert!(values.len() > 100);
+        assert_eq!(ok!(font.kerning_pairs(&characters)), values);
+    }
+}
+
+#[test]
+fn open_sans() {
+    let font = &mut setup(Fixture::OpenSans)[0];
+    let characters = vec![Character::Range((' ', '~'))];
+    let atlas = ok!(GlyphAtlas::new(font, &characters, 24.0, 128, 1));
+    assert_eq!(atlas.glyphs.len(), 95);
+    assert_eq!(atlas.textures.len(), 2);
+    for (i, one) in atlas.glyphs.iter().enumerate() {
+        for other in atlas.glyphs.iter().skip(i + 1) {
+            if one.texture != other.texture {
+                continue;
+            }
+            let (x1, y1, w1, h1) = one.rectangle;
+            let (x2, y2, w2, h2) = other.rectangle;
+            let disjoint = x1 + w1 < x2 || x2 + w2 < x1 || y1 + h1 < y2 || y2 + h2 < y1;
+            assert!(w1 * h1 == 0 || w2 * h2 == 0 || disjoint);
+        }
+    }
+    let scale = 24.0 / ok!(font.metrics()).granularity;
+    let entry = ok!(atlas.glyphs.iter().find(|glyph| glyph.character == 'g'));
+    let bitmap = raster::rasterize(&ok!(ok!(font.glyph('g'))), scale, Offset(0.0, 0.0));
+    assert_eq!(entry.bearings, (bitmap.left, bitmap.top));
+    assert_eq!(
+        (entry.rectangle.2, entry.rectangle.3),
+        (bitmap.width, bitmap.height)
+    );
+    let texture = &atlas.textures[entry.texture];
+    for y in 0..bitmap.height {
+        for x in 0..bitmap.width {
+            let value = texture.get(entry.rectangle.0 + x, entry.rectangle.1 + y);
+            assert_eq!(value, bitmap.get(x, y));
+        }
+    }
+    let (x, y) = (entry.rectangle.0 as f32, entry.rectangle.1 as f32);
+    assert_eq!((entry.uv.0, entry.uv.1), (x / 128.0, y / 128.0));
+    let entry = ok!(atlas.glyphs.iter().find(|glyph| glyph.character == ' '));
+    assert_eq!((entry.rectangle.2, entry.rectangle.3), (0, 0));
+    assert_eq!(entry.texture, 0);
+    assert!(entry.advance > 0.0);
+    assert!(atlas
+        .kerning
+        .iter()
+        .any(|&(one, other, value)| one == 'A' && other == 'V' && value < 0.0));
+
+    let json = atlas.to_json();
+    assert!(json.starts_with(r#"{"size":24,"#));
+    assert_eq!(json.matches(r#""character":"#).count(), 95);
+
+    let bmfont = atlas.to_bmfont("OpenSans");
+    assert!(bmfont.starts_with(r#"info face="OpenSans" size=24 "#));
+    assert!(bmfont.contains("page id=1 file=\"OpenSans_1.png\"\n"));
+    assert!(bmfont.contains("chars count=95\n"));
+    assert!(bmfont.contains("kerning first=65 second=86 amount=-1\n"));
+
+    let png = ok!(atlas.to_png(0));
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(&png[12..16], b"IHDR");
+    assert_eq!(&png[16..24], &[0, 0, 0, 128, 0, 0, 0, 128]);
+    assert_eq!(decode(&png), atlas.textures[0].data);
+    assert!(atlas.to_png(2).is_none());
+}
+
+fn decode(png: &[u8]) -> Vec<u8> {
+    let length = u32::from_be_bytes(png[33..37].try_into().unwrap()) as usize;
+    assert_eq!(&png[37..41], b"IDAT");
+    let mut data = &png[43..(41 + length - 4)];
+    let mut raw = vec![];
+    loop {
+        let last = data[0] & 1 == 1;
+        let size = u16::from_le_bytes([data[1], data[2]]) as usize;
+        raw.extend_from_slice(&data[5..(5 + size)]);
+        data = &data[(5 + size)..];
+        if last {
+            break;
+        }
+    }
+    raw.chunks(129).flat_map(|row| row[1..].to_vec()).collect()
+}
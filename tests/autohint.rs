@@ -0,0 +1,115 @@
+#[macro_use]
+mod support;
+
+use font::glyph::Alignment;
+use font::{Glyph, Metrics};
+
+use crate::support::{build, setup, square, Fixture};
+
+fn bar() -> Glyph {
+    let glyph = build(|builder| square(builder, (0.0, 0.0), 10.0));
+    Glyph {
+        advance_width: 120.0,
+        bounding_box: (0.0, 40.0, 100.0, 55.0),
+        side_bearings: (0.0, 20.0),
+        ..glyph.transform((10.0, 0.0, 0.0, 1.5, 0.0, 40.0))
+    }
+}
+
+fn detect(fixture: Fixture) -> (Metrics, Alignment) {
+    let font = &mut setup(fixture)[0];
+    let metrics = ok!(font.metrics());
+    let glyphs = "xoHO"
+        .chars()
+        .map(|character| ok!(ok!(font.glyph(character))))
+        .collect::<Vec<_>>();
+    let alignment = Alignment::detect(&metrics, &glyphs);
+    (metrics, alignment)
+}
+
+fn integral(value: f32) -> bool {
+    (value - value.round()).abs() < 1e-4
+}
+
+#[test]
+fn stem() {
+    let glyph = bar().autohint(&Alignment::default(), 0.1);
+    assert_eq!(glyph.bounding_box, (0.0, 4.0, 10.0, 6.0));
+    assert_eq!(glyph.advance_width, 12.0);
+    assert_eq!(glyph.side_bearings, (0.0, 2.0));
+}
+
+mod open_sans {
+    use crate::support::{setup, Fixture};
+
+    #[test]
+    fn detect() {
+        let (_, alignment) = crate::detect(Fixture::OpenSans);
+        assert_eq!(
+            alignment.blue_values,
+            vec![(-20.0, 0.0), (1096.0, 1110.0), (1462.0, 1485.0)],
+        );
+        assert!(alignment.other_blues.is_empty());
+        assert_eq!(alignment.standard_horizontal_width, Some(151.0));
+        assert_eq!(alignment.standard_vertical_width, Some(182.0));
+    }
+
+    #[test]
+    fn hint() {
+        let (metrics, alignment) = crate::detect(Fixture::OpenSans);
+        let font = &mut setup(Fixture::OpenSans)[0];
+        let scale = 12.0 / metrics.granularity;
+
+        let glyph = ok!(ok!(font.glyph('H'))).autohint(&alignment, scale);
+        assert_eq!(glyph.advance_width, 8.0);
+        assert_eq!((glyph.bounding_box.1, glyph.bounding_box.3), (0.0, 9.0));
+        let mut heights = glyph.points()[0]
+            .iter()
+            .map(|point| point.offset().1)
+            .collect::<Vec<_>>();
+        heights.dedup();
+        assert!(heights.iter().all(|&value| crate::integral(value)));
+        assert!(heights.contains(&4.0) && heights.contains(&5.0));
+
+        let glyph = ok!(ok!(font.glyph('o'))).autohint(&alignment, scale);
+        assert_eq!(glyph.bounding_box, (1.0, 0.0, 6.0, 6.0));
+    }
+}
+
+mod source_serif {
+    use crate::support::{setup, Fixture};
+
+    #[test]
+    fn hint() {
+        let font = &mut setup(Fixture::SourceSerif)[0];
+        let alignment = ok!(ok!(font.alignment()));
+        let glyph = ok!(ok!(font.glyph('H')));
+        let other = glyph.autohint(&alignment, 12.0 / 1000.0);
+        assert_eq!(other.advance_width, 9.0);
+        assert_eq!((other.bounding_box.1, other.bounding_box.3), (0.0, 8.0));
+        let points = glyph.points().concat();
+        let hinted = other.points().concat();
+        for (point, other) in points.iter().zip(hinted.iter()) {
+            let (point, other) = (point.offset(), other.offset());
+            if [138.0, 233.0, 556.0, 650.0].contains(&point.0) {
+                assert!(crate::integral(other.0));
+            }
+            if [0.0, 319.0, 366.0, 669.0].contains(&point.1) {
+                assert!(crate::integral(other.1));
+            }
+        }
+    }
+}
+
+#[test]
+fn formats() {
+    for fixture in [Fixture::OpenSans, Fixture::SourceSerif] {
+        let (metrics, alignment) = detect(fixture);
+        let font = &mut setup(fixture)[0];
+        assert_eq!(alignment.blue_values.len(), 3);
+        let glyph = ok!(ok!(font.glyph('x'))).autohint(&alignment, 16.0 / metrics.granularity);
+        assert!(crate::integral(glyph.bounding_box.1));
+        assert!(crate::integral(glyph.bounding_box.3));
+        assert!(crate::integral(glyph.advance_width));
+    }
+}
@@ -0,0 +1,276 @@
+#[macro_use]
+mod support;
+
+use font::bitmaps::Image;
+use font::formats::opentype::Insertion;
+use font::opentype::truetype::Tag;
+use font::Case;
+
+use crate::support::{insert, setup, Fixture};
+
+// The image of three by two pixels alternating between on and off.
+const BIT_ALIGNED: [u8; 1] = [0b1010_1000];
+const BYTE_ALIGNED: [u8; 2] = [0b1010_0000, 0b0100_0000];
+const PIXELS: [u8; 6] = [1, 0, 1, 0, 1, 0];
+
+const SMALL: [u8; 5] = [2, 3, 0, 2, 4];
+const BIG: [u8; 8] = [2, 3, 0, 2, 4, 0, 0, 0];
+
+struct Subtable {
+    glyph_id: u16,
+    index_format: u16,
+    image_format: u16,
+    image: Vec<u8>,
+}
+
+struct Strike {
+    ppem: u8,
+    bit_depth: u8,
+    subtables: Vec<Subtable>,
+}
+
+fn build(major: u16, strikes: Vec<Strike>) -> (Vec<u8>, Vec<u8>) {
+    let mut location = vec![];
+    location.extend(major.to_be_bytes());
+    location.extend(0u16.to_be_bytes());
+    location.extend((strikes.len() as u32).to_be_bytes());
+    let mut data = location.clone();
+    data.truncate(4);
+    let mut arrays = vec![];
+    let mut records = vec![];
+    let mut position = 8 + 48 * strikes.len();
+    for strike in strikes.iter() {
+        let mut entries = vec![];
+        let mut subtables = vec![];
+        let start = 8 * strike.subtables.len();
+        for subtable in strike.subtables.iter() {
+            let size = subtable.image.len() as u32;
+            let glyph_id = subtable.glyph_id;
+            entries.extend(glyph_id.to_be_bytes());
+            entries.extend(glyph_id.to_be_bytes());
+            entries.extend(((start + subtables.len()) as u32).to_be_bytes());
+            subtables.extend(subtable.index_format.to_be_bytes());
+            subtables.extend(subtable.image_format.to_be_bytes());
+            subtables.extend((data.len() as u32).to_be_bytes());
+            match subtable.index_format {
+                1 => [0, size]
+                    .iter()
+                    .for_each(|value| subtables.extend(value.to_be_bytes())),
+                2 => {
+                    subtables.extend(size.to_be_bytes());
+                    subtables.extend(BIG);
+                }
+                3 => [0, size as u16, 0]
+                    .iter()
+                    .for_each(|value| subtables.extend(value.to_be_bytes())),
+                4 => {
+                    subtables.extend(1u32.to_be_bytes());
+                    [glyph_id, 0, 0, size as u16]
+                        .iter()
+                        .for_each(|value| subtables.extend(value.to_be_bytes()));
+                }
+                _ => {
+                    subtables.extend(size.to_be_bytes());
+                    subtables.extend(BIG);
+                    subtables.extend(1u32.to_be_bytes());
+                    subtables.extend([glyph_id, 0].map(u16::to_be_bytes).concat());
+                }
+            }
+            data.extend(&subtable.image);
+        }
+        let mut record = vec![];
+        record.extend((position as u32).to_be_bytes());
+        record.extend(((entries.len() + subtables.len()) as u32).to_be_bytes());
+        record.extend((strike.subtables.len() as u32).to_be_bytes());
+        record.extend([0; 4 + 24 + 4]);
+        record.extend([strike.ppem, strike.ppem, strike.bit_depth, 1]);
+        position += entries.len() + subtables.len();
+        records.extend(record);
+        arrays.extend(entries);
+        arrays.extend(subtables);
+    }
+    location.extend(records);
+    location.extend(arrays);
+    (location, data)
+}
+
+fn glyph_ids(characters: &str) -> Vec<u16> {
+    let font = &mut setup(Fixture::OpenSans)[0];
+    characters
+        .chars()
+        .map(|character| ok!(ok!(font.glyph_id(character))))
+        .collect()
+}
+
+#[test]
+fn color() {
+    let glyph_ids = glyph_ids("ABC");
+    let png = b"\x89PNG\r\n\x1a\n".to_vec();
+    let with_size = |metrics: &[u8]| {
+        let mut image = metrics.to_vec();
+        image.extend((png.len() as u32).to_be_bytes());
+        image.extend(&png);
+        image
+    };
+    let subtables = vec![
+        Subtable {
+            glyph_id: glyph_ids[0],
+            index_format: 1,
+            image_format: 17,
+            image: with_size(&SMALL),
+        },
+        Subtable {
+            glyph_id: glyph_ids[1],
+            index_format: 3,
+            image_format: 18,
+            image: with_size(&BIG),
+        },
+        Subtable {
+            glyph_id: glyph_ids[2],
+            index_format: 2,
+            image_format: 19,
+            image: with_size(&[]),
+        },
+    ];
+    let strikes = vec![Strike {
+        ppem: 109,
+        bit_depth: 32,
+        subtables,
+    }];
+    let (location, data) = build(3, strikes);
+    let mut font = insert(vec![
+        Insertion::raw(Tag(*b"CBLC"), location),
+        Insertion::raw(Tag(*b"CBDT"), data),
+    ]);
+    for character in ['A', 'B', 'C'] {
+        let bitmap = ok!(ok!(font.bitmap(character, 12)));
+        assert_eq!(bitmap.ppem, (109, 109));
+        assert_eq!((bitmap.width, bitmap.height), (3, 2));
+        assert_eq!(bitmap.bearings, (0, 2));
+        assert_eq!(bitmap.advance_width, 4);
+        assert_eq!(bitmap.image, Image::Png(png.clone()));
+    }
+    assert!(ok!(font.bitmap('D', 12)).is_none());
+}
+
+#[test]
+fn gray() {
+    let glyph_ids = glyph_ids("ABCDEFGH");
+    let image = |metrics: &[u8], pixels: &[u8]| [metrics, pixels].concat();
+    let mut composite = SMALL.to_vec();
+    composite[1] = 6;
+    composite.push(0);
+    composite.extend(2u16.to_be_bytes());
+    composite.extend(glyph_ids[0].to_be_bytes());
+    composite.extend([0, 0]);
+    composite.extend(glyph_ids[1].to_be_bytes());
+    composite.extend([3, 0]);
+    let mut other = BIG.to_vec();
+    other[1] = 4;
+    other.extend(1u16.to_be_bytes());
+    other.extend(glyph_ids[2].to_be_bytes());
+    other.extend([1, 0]);
+    let subtables = vec![
+        (0, 1, 1, image(&SMALL, &BYTE_ALIGNED)),
+        (1, 2, 5, BIT_ALIGNED.to_vec()),
+        (2, 3, 6, image(&BIG, &BYTE_ALIGNED)),
+        (3, 4, 7, image(&BIG, &BIT_ALIGNED)),
+        (4, 5, 5, BIT_ALIGNED.to_vec()),
+        (5, 1, 2, image(&SMALL, &BIT_ALIGNED)),
+        (6, 1, 8, composite),
+        (7, 1, 9, other),
+    ];
+    let subtables = subtables
+        .into_iter()
+        .map(|(i, index_format, image_format, image)| Subtable {
+            glyph_id: glyph_ids[i],
+            index_format,
+            image_format,
+            image,
+        })
+        .collect();
+    let strikes = vec![
+        Strike {
+            ppem: 12,
+            bit_depth: 1,
+            subtables,
+        },
+        Strike {
+            ppem: 20,
+            bit_depth: 8,
+            subtables: vec![Subtable {
+                glyph_id: glyph_ids[0],
+                index_format: 1,
+                image_format: 1,
+                image: vec![1, 1, 0, 1, 1, 255],
+            }],
+        },
+    ];
+    let (location, data) = build(2, strikes);
+    let mut font = insert(vec![
+        Insertion::raw(Tag(*b"EBLC"), location),
+        Insertion::raw(Tag(*b"EBDT"), data),
+    ]);
+
+    for character in ['A', 'B', 'C', 'D', 'E', 'F'] {
+        let bitmap = ok!(ok!(font.bitmap(character, 12)));
+        assert_eq!(bitmap.ppem, (12, 12));
+        assert_eq!((bitmap.width, bitmap.height), (3, 2));
+        assert_eq!(bitmap.bearings, (0, 2));
+        assert_eq!(bitmap.advance_width, 4);
+        assert_eq!(
+            bitmap.image,
+            Image::Gray {
+                bit_depth: 1,
+                pixels: PIXELS.to_vec(),
+            },
+        );
+    }
+
+    let bitmap = ok!(ok!(font.bitmap('G', 12)));
+    assert_eq!((bitmap.width, bitmap.height), (6, 2));
+    #[rustfmt::skip]
+    assert_eq!(
+        bitmap.image,
+        Image::Gray {
+            bit_depth: 1,
+            pixels: vec![
+                1, 0, 1, 1, 0, 1,
+                0, 1, 0, 0, 1, 0,
+            ],
+        },
+    );
+    let bitmap = ok!(ok!(font.bitmap('H', 12)));
+    assert_eq!((bitmap.width, bitmap.height), (4, 2));
+    #[rustfmt::skip]
+    assert_eq!(
+        bitmap.image,
+        Image::Gray {
+            bit_depth: 1,
+            pixels: vec![
+                0, 1, 0, 1,
+                0, 0, 1, 0,
+            ],
+        },
+    );
+
+    let bitmap = ok!(ok!(font.bitmap('A', 16)));
+    assert_eq!(bitmap.ppem, (20, 20));
+    assert_eq!(
+        bitmap.image,
+        Image::Gray {
+            bit_depth: 8,
+            pixels: vec![255],
+        },
+    );
+    assert_eq!(ok!(ok!(font.bitmap('A', 30))).ppem, (20, 20));
+    assert_eq!(ok!(ok!(font.bitmap('B', 30))).ppem, (12, 12));
+    assert!(ok!(font.bitmap('Z', 12)).is_none());
+    assert!(ok!(font.bitmap('\u{E000}', 12)).is_none());
+}
+
+#[test]
+fn none() {
+    let font = &mut setup(Fixture::OpenSans)[0];
+    assert!(ok!(font.bitmap('A', 12)).is_none());
+}
@@ -0,0 +1,113 @@
+#[macro_use]
+mod support;
+
+use font::glyph::{Pen, Segment};
+use font::raster;
+use font::Offset;
+
+use crate::support::{build, circle, setup, square, trace, Fixture};
+
+#[test]
+fn squares() {
+    let one = build(|builder| square(builder, (0.0, 0.0), 10.0));
+    let other = build(|builder| square(builder, (5.0, 5.0), 10.0));
+
+    let glyph = one.union(&other);
+    assert_eq!(glyph.len(), 1);
+    assert_eq!(glyph.area(), -175.0);
+    assert_eq!(glyph.bounding_box, (0.0, 0.0, 15.0, 15.0));
+
+    let glyph = one.intersection(&other);
+    assert_eq!(glyph.len(), 1);
+    assert_eq!(glyph.area(), -25.0);
+    assert_eq!(glyph.bounding_box, (5.0, 5.0, 10.0, 10.0));
+
+    let glyph = one.difference(&other);
+    assert_eq!(glyph.len(), 1);
+    assert_eq!(glyph.area(), -75.0);
+
+    let glyph = one.xor(&other);
+    assert_eq!(glyph.len(), 2);
+    assert_eq!(glyph.area(), -150.0);
+}
+
+#[test]
+fn circles() {
+    let one = build(|builder| circle(builder, (0.0, 0.0), 100.0));
+    let other = build(|builder| circle(builder, (100.0, 0.0), 100.0));
+    let glyph = one.union(&other);
+    assert_eq!(glyph.len(), 1);
+    assert!(glyph[0]
+        .iter()
+        .all(|segment| matches!(segment, Segment::Cubic(..))));
+    let lens = 2.0 * 100.0 * 100.0 * (std::f32::consts::PI / 3.0 - 3f32.sqrt() / 4.0);
+    let expected = 2.0 * std::f32::consts::PI * 100.0 * 100.0 - lens;
+    assert!((glyph.area() - expected).abs() < 20.0);
+    assert_eq!(glyph.bounding_box, (-100.0, -100.0, 200.0, 100.0));
+
+    let glyph = one.intersection(&other);
+    assert_eq!(glyph.len(), 1);
+    assert!((glyph.area() - lens).abs() < 20.0);
+}
+
+#[test]
+fn nested() {
+    let mut glyph = build(|builder| {
+        square(builder, (0.0, 0.0), 10.0);
+        square(builder, (0.0, 0.0), 10.0);
+        square(builder, (2.0, 2.0), 4.0);
+    });
+    glyph.remove_overlaps();
+    #[rustfmt::skip]
+    assert_eq!(trace(&glyph), vec![
+        ( 0.0, 10.0),
+        (10.0, 10.0),
+        (10.0,  0.0),
+        ( 0.0,  0.0),
+        ( 0.0, 10.0),
+    ]);
+
+    let mut glyph = build(|builder| {
+        square(builder, (0.0, 0.0), 10.0);
+        builder.move_to((2.0, 2.0).into());
+        builder.line_to((6.0, 2.0).into());
+        builder.line_to((6.0, 6.0).into());
+        builder.line_to((2.0, 6.0).into());
+        builder.close();
+    });
+    glyph.remove_overlaps();
+    assert_eq!(glyph.len(), 2);
+    assert_eq!(glyph.area(), -84.0);
+}
+
+#[test]
+fn fixtures() {
+    for (fixture, characters, count) in [
+        (Fixture::AdobeVFPrototype, "$AOg&", 4),
+        (Fixture::MonteCarlo, "ABgh", 0),
+        (Fixture::OpenSans, "o&g", 0),
+        (Fixture::SourceSerif, "o&$@", 0),
+    ] {
+        let font = &mut setup(fixture)[0];
+        let scale = 64.0 / ok!(font.metrics()).granularity;
+        let mut removed = 0;
+        for character in characters.chars() {
+            let glyph = ok!(ok!(font.glyph(character)));
+            let mut other = glyph.clone();
+            other.remove_overlaps();
+            removed += glyph.len() - other.len();
+            assert!(other.area().abs() <= glyph.area().abs() + 1.0);
+            let one = raster::rasterize(&glyph, scale, Offset(0.0, 0.0));
+            let two = raster::rasterize(&other, scale, Offset(0.0, 0.0));
+            assert_eq!((one.width, one.height), (two.width, two.height));
+            let difference = one
+                .data
+                .iter()
+                .zip(two.data.iter())
+                .map(|(one, other)| (*one as i32 - *other as i32).unsigned_abs())
+                .sum::<u32>();
+            assert!(difference < one.data.len() as u32, "{character}");
+        }
+        assert_eq!(removed, count);
+    }
+}
@@ -0,0 +1,106 @@
+#[macro_use]
+mod support;
+
+use std::io::Cursor;
+
+use font::formats::opentype::{read, FontBuilder, Outlines};
+use font::glyph::{Glyph, Segment};
+use font::opentype::truetype::tables::names::NameID;
+use font::{Case, Metrics};
+
+use crate::support::{build, circle, square, trace};
+
+#[test]
+fn cubic() {
+    let data = ok!(builder().build(Outlines::Cubic));
+    let mut font = ok!(read(Cursor::new(data))).remove(0);
+
+    let metrics = ok!(font.metrics());
+    assert_eq!(metrics.granularity, 2048.0);
+    assert_eq!(metrics.ascender, 1600.0);
+    assert_eq!(metrics.descender, -400.0);
+
+    let glyph = ok!(ok!(font.glyph('o')));
+    assert_eq!(glyph.advance_width, 1200.0);
+    assert_eq!(glyph.bounding_box, (100.0, 0.0, 1100.0, 1000.0));
+    assert_eq!(glyph.contours.len(), 1);
+    assert!(glyph.contours[0]
+        .iter()
+        .all(|segment| matches!(segment, Segment::Cubic(..))));
+
+    let glyph = ok!(ok!(font.glyph('l')));
+    assert_eq!(trace(&glyph), trace(&l()));
+    assert!(ok!(font.glyph('x')).is_none());
+}
+
+#[test]
+fn quadratic() {
+    let data = ok!(builder().build(Outlines::Quadratic));
+    let mut font = ok!(read(Cursor::new(data))).remove(0);
+
+    let glyph = ok!(ok!(font.glyph('o')));
+    assert_eq!(glyph.advance_width, 1200.0);
+    assert!((glyph.bounding_box.0 - 100.0).abs() <= 1.0);
+    assert!((glyph.bounding_box.2 - 1100.0).abs() <= 1.0);
+    assert!(glyph.contours[0]
+        .iter()
+        .all(|segment| matches!(segment, Segment::Quadratic(..))));
+    assert!(glyph.contours[0].len() > 4);
+
+    let glyph = ok!(ok!(font.glyph('l')));
+    assert_eq!(glyph.advance_width, 600.0);
+    let mut points = trace(&l());
+    points.push(points[0]);
+    assert_eq!(trace(&glyph), points);
+
+    let names = ok!(font.names());
+    let names = names.borrow();
+    let family = names
+        .iter()
+        .find(|((_, _, _, id), _)| *id == NameID::FontFamilyName)
+        .and_then(|(_, value)| value);
+    assert_eq!(family.as_deref(), Some("Builder Sans"));
+
+    let mut cursor = Cursor::new(vec![]);
+    ok!(font::formats::opentype::write(font, &mut cursor, |_| {
+        font::formats::opentype::Disposition::Retain
+    }));
+    let mut font = ok!(read(Cursor::new(cursor.into_inner()))).remove(0);
+    assert!(ok!(font.glyph('o')).is_some());
+}
+
+fn builder() -> FontBuilder {
+    let mut builder = FontBuilder::new(Metrics {
+        granularity: 2048.0,
+        clipping_ascender: 1800.0,
+        ascender: 1600.0,
+        cap_height: 1400.0,
+        x_height: 1000.0,
+        baseline: 0.0,
+        descender: -400.0,
+        clipping_descender: -500.0,
+        line_gap: 0.0,
+    });
+    builder
+        .glyph("l", l())
+        .glyph("o", o())
+        .character('l', "l")
+        .character('o', "o")
+        .name(NameID::FontFamilyName, "Builder Sans")
+        .name(NameID::FontSubfamilyName, "Regular");
+    builder
+}
+
+fn l() -> Glyph {
+    Glyph {
+        advance_width: 600.0,
+        ..build(|builder| square(builder, (100.0, 0.0), 400.0))
+    }
+}
+
+fn o() -> Glyph {
+    Glyph {
+        advance_width: 1200.0,
+        ..build(|builder| circle(builder, (600.0, 500.0), 500.0))
+    }
+}
@@ -0,0 +1,38 @@
+#[macro_use]
+mod support;
+
+use std::fs::File;
+use std::io::Cursor;
+
+use font::formats::opentype::{read, write_collection};
+use font::Case;
+
+use crate::support::{path, Fixture};
+
+#[test]
+fn write() {
+    for version in [1, 2] {
+        let fonts = [Fixture::OpenSans, Fixture::OpenSans, Fixture::CrimsonText]
+            .into_iter()
+            .map(|fixture| ok!(ok!(read(ok!(File::open(path(fixture))))).pop()))
+            .collect::<Vec<_>>();
+        let mut cursor: Cursor<Vec<u8>> = Cursor::new(vec![]);
+        ok!(write_collection(fonts, &mut cursor, version));
+
+        let data = cursor.into_inner();
+        let size = ok!(std::fs::metadata(path(Fixture::OpenSans))).len()
+            + ok!(std::fs::metadata(path(Fixture::CrimsonText))).len();
+        assert!((data.len() as u64) < size + 512);
+        assert_eq!(&data[..4], b"ttcf");
+        assert_eq!(&data[4..12], &[0, version as u8, 0, 0, 0, 0, 0, 3]);
+
+        let mut fonts = ok!(read(Cursor::new(data)));
+        assert_eq!(fonts.len(), 3);
+        assert_eq!(ok!(fonts[0].metrics()).granularity, 2048.0);
+        assert_eq!(ok!(fonts[1].metrics()).granularity, 2048.0);
+        assert_eq!(ok!(fonts[2].metrics()).granularity, 1024.0);
+        for font in fonts.iter_mut() {
+            ok!(ok!(font.glyph('a')));
+        }
+    }
+}
@@ -0,0 +1,65 @@
+#[macro_use]
+mod support;
+
+use font::glyph::Segment;
+use font::Glyph;
+
+use crate::support::{build, circle, setup, Fixture};
+
+fn count(glyph: &Glyph) -> (usize, usize, usize) {
+    let mut counts = (0, 0, 0);
+    for segment in glyph.iter().flat_map(|contour| contour.iter()) {
+        match segment {
+            Segment::Linear(..) => counts.0 += 1,
+            Segment::Quadratic(..) => counts.1 += 1,
+            Segment::Cubic(..) => counts.2 += 1,
+        }
+    }
+    counts
+}
+
+#[test]
+fn to_cubic() {
+    let font = &mut setup(Fixture::OpenSans)[0];
+    for character in ['a', 'o', 'S', '&'] {
+        let glyph = ok!(ok!(font.glyph(character)));
+        let other = glyph.to_cubic();
+        let (linear, quadratic, _) = count(&glyph);
+        assert!(quadratic > 0);
+        assert_eq!(count(&other), (linear, 0, quadratic));
+        assert_eq!(other.bounding_box, glyph.bounding_box);
+        assert!((other.area() - glyph.area()).abs() < 1e-2 * glyph.area().abs());
+        let (one, other) = (glyph.bounds(), other.bounds());
+        assert!((one.0 - other.0).abs() < 1e-2 && (one.1 - other.1).abs() < 1e-2);
+        assert!((one.2 - other.2).abs() < 1e-2 && (one.3 - other.3).abs() < 1e-2);
+    }
+}
+
+#[test]
+fn to_quadratic() {
+    let glyph = build(|builder| circle(builder, (0.0, 0.0), 100.0));
+    for tolerance in [1.0, 0.1, 0.01] {
+        let other = glyph.to_quadratic(tolerance);
+        let (linear, quadratic, cubic) = count(&other);
+        assert_eq!((linear, cubic), (0, 0));
+        assert!(quadratic >= 4);
+        for point in other.flatten(0.001)[0].iter() {
+            assert!((point.0.hypot(point.1) - 100.0).abs() <= tolerance + 0.03);
+        }
+    }
+    assert!(count(&glyph.to_quadratic(0.01)).1 > count(&glyph.to_quadratic(1.0)).1);
+
+    let font = &mut setup(Fixture::SourceSerif)[0];
+    for character in ['a', 'o', 'S', '&'] {
+        let glyph = ok!(ok!(font.glyph(character)));
+        let other = glyph.to_quadratic(0.5);
+        let (linear, _, cubic) = count(&glyph);
+        assert!(cubic > 0);
+        assert_eq!(count(&other).0, linear);
+        assert_eq!(count(&other).2, 0);
+        assert!((other.area() - glyph.area()).abs() < 1e-2 * glyph.area().abs());
+        let (one, other) = (glyph.bounds(), other.bounds());
+        assert!((one.0 - other.0).abs() < 0.5 && (one.1 - other.1).abs() < 0.5);
+        assert!((one.2 - other.2).abs() < 0.5 && (one.3 - other.3).abs() < 0.5);
+    }
+}
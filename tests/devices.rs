@@ -0,0 +1,121 @@
+#[macro_use]
+mod support;
+
+use font::formats::opentype::Insertion;
+use font::opentype::truetype::Tag;
+use font::Case;
+
+use crate::support::{insert, setup, Fixture};
+
+const GLYPH_COUNT: usize = 938;
+
+#[test]
+fn device_widths() {
+    let size = (2 + GLYPH_COUNT + 3) & !3;
+    let mut data = vec![0, 0, 0, 2];
+    data.extend((size as u32).to_be_bytes());
+    for ppem in [12u8, 16] {
+        let mut record = vec![ppem, ppem];
+        record.extend((0..GLYPH_COUNT).map(|i| (i % 7) as u8 + ppem / 4));
+        record.resize(size, 0);
+        data.extend(record);
+    }
+    let mut font = insert(vec![Insertion::raw(Tag(*b"hdmx"), data)]);
+    let table = ok!(ok!(font.device_widths()));
+    assert_eq!(table.records.len(), 2);
+    assert_eq!(table.records[1].ppem, 16);
+    assert_eq!(table.records[1].maximal_width, 16);
+    assert_eq!(table.records[1].widths.len(), GLYPH_COUNT);
+    assert_eq!(table.get(9, 12), Some(5));
+    assert_eq!(table.get(9, 16), Some(6));
+    assert_eq!(table.get(9, 14), None);
+    assert_eq!(table.get(GLYPH_COUNT as u16, 12), None);
+}
+
+#[test]
+fn grid_fitting() {
+    use font::devices::Behavior;
+
+    let font = &mut setup(Fixture::OpenSans)[0];
+    let table = ok!(ok!(font.grid_fitting()));
+    assert_eq!(table.ranges.len(), 3);
+    assert_eq!(
+        table.get(8),
+        Some(Behavior {
+            grid_fit: false,
+            grayscale: true,
+            symmetric_grid_fit: false,
+            symmetric_smoothing: true,
+        }),
+    );
+    assert_eq!(
+        table.get(12),
+        Some(Behavior {
+            grid_fit: true,
+            grayscale: true,
+            symmetric_grid_fit: true,
+            symmetric_smoothing: false,
+        }),
+    );
+    let behavior = ok!(table.get(100));
+    assert!(behavior.grid_fit && behavior.grayscale && behavior.symmetric_smoothing);
+
+    let font = &mut setup(Fixture::AdobeBlank)[0];
+    let table = ok!(ok!(font.grid_fitting()));
+    assert_eq!(table.ranges.len(), 2);
+    assert!(ok!(table.get(8)).grayscale);
+    assert!(!ok!(table.get(8)).grid_fit);
+    assert!(ok!(table.get(9)).grid_fit);
+
+    let font = &mut setup(Fixture::SourceSerif)[0];
+    assert!(ok!(font.grid_fitting()).is_none());
+    assert!(ok!(font.device_widths()).is_none());
+    assert!(ok!(font.linear_thresholds()).is_none());
+    assert!(ok!(font.vertical_device_metrics()).is_none());
+}
+
+#[test]
+fn linear_thresholds() {
+    let mut data = vec![0, 0];
+    data.extend((GLYPH_COUNT as u16).to_be_bytes());
+    data.extend((0..GLYPH_COUNT).map(|i| if i == 3 { 50 } else { 1 }));
+    let mut font = insert(vec![Insertion::raw(Tag(*b"LTSH"), data)]);
+    let table = ok!(ok!(font.linear_thresholds()));
+    assert_eq!(table.thresholds.len(), GLYPH_COUNT);
+    assert_eq!(table.get(3), Some(50));
+    assert_eq!(table.get(4), Some(1));
+    assert_eq!(table.get(GLYPH_COUNT as u16), None);
+}
+
+#[test]
+fn malformed() {
+    let data = vec![0, 0, 0, 1, 0, 0, 0, 4, 12, 12, 0, 0];
+    let mut font = insert(vec![Insertion::raw(Tag(*b"hdmx"), data)]);
+    assert!(font.device_widths().is_err());
+}
+
+#[test]
+fn vertical_device_metrics() {
+    #[rustfmt::skip]
+    let data = vec![
+        0, 1, 0, 2, 0, 2,
+        1, 1, 1, 1,
+        1, 0, 0, 0,
+        0, 18, 0, 34,
+        0, 2, 10, 11,
+        0, 10, 0, 9, 255, 253,
+        0, 11, 0, 10, 255, 253,
+        0, 1, 10, 10,
+        0, 10, 0, 10, 255, 252,
+    ];
+    let mut font = insert(vec![Insertion::raw(Tag(*b"VDMX"), data)]);
+    let table = ok!(ok!(font.vertical_device_metrics()));
+    assert_eq!(table.ratios.len(), 2);
+    assert_eq!(table.ratios[0].y, (1, 1));
+    assert_eq!(table.ratios[0].records.len(), 2);
+    assert_eq!(table.ratios[1].x, 0);
+    assert_eq!(table.get(11, (1, 1)), Some((10, -3)));
+    assert_eq!(table.get(10, (1, 1)), Some((9, -3)));
+    assert_eq!(table.get(10, (2, 1)), Some((10, -4)));
+    assert_eq!(table.get(12, (1, 1)), None);
+}
@@ -0,0 +1,132 @@
+#[macro_use]
+mod support;
+
+use font::distance;
+use font::glyph::{Builder, Pen};
+use font::raster;
+use font::{Glyph, Offset};
+
+use crate::support::square;
+
+#[test]
+fn overlap() {
+    let mut builder = Builder::default();
+    square(&mut builder, (0.0, 0.0), 10.0);
+    square(&mut builder, (5.0, 0.0), 10.0);
+    let glyph: Glyph = builder.into();
+    let field = distance::single(&glyph, 1.0, 16.0, 2);
+    assert_eq!((field.width, field.height), (19, 14));
+    assert_eq!((field.left, field.top), (-2, 12));
+    assert_eq!(field.get(1, 6), Some(&[120][..]));
+    assert_eq!(field.get(2, 6), Some(&[135][..]));
+    assert_eq!(field.get(9, 6), Some(&[199][..]));
+    let field = distance::multiple(&glyph, 1.0, 16.0, 2);
+    assert_eq!(field.get(9, 6), Some(&[199, 247, 199][..]));
+}
+
+#[test]
+fn corner() {
+    let mut builder = Builder::default();
+    square(&mut builder, (0.0, 0.0), 100.0);
+    let glyph: Glyph = builder.into();
+    let field = distance::single(&glyph, 0.1, 4.0, 1);
+    assert_eq!((field.width, field.height, field.channels), (12, 12, 1));
+    assert_eq!(field.get(0, 0), Some(&[82][..]));
+    assert_eq!(field.get(1, 1), Some(&[159][..]));
+    assert_eq!(field.get(0, 5), Some(&[96][..]));
+    let field = distance::multiple(&glyph, 0.1, 4.0, 1);
+    assert_eq!((field.width, field.height, field.channels), (12, 12, 3));
+    assert_eq!(field.get(0, 0), Some(&[96, 96, 96][..]));
+    assert_eq!(field.get(1, 1), Some(&[159, 159, 159][..]));
+    assert_eq!(field.get(0, 5), Some(&[255, 96, 96][..]));
+}
+
+#[test]
+fn teardrop() {
+    let mut builder = Builder::default();
+    builder.move_to((0.0, 0.0).into());
+    builder.curve_to(
+        (100.0, 50.0).into(),
+        (100.0, 150.0).into(),
+        (0.0, 150.0).into(),
+    );
+    builder.curve_to(
+        (-100.0, 150.0).into(),
+        (-100.0, 50.0).into(),
+        (0.0, 0.0).into(),
+    );
+    builder.close();
+    let glyph: Glyph = builder.into();
+    let field = distance::multiple(&glyph, 0.2, 4.0, 2);
+    assert!((0..field.width * field.height).any(|i| {
+        let values = &field.data[(3 * i)..(3 * i + 3)];
+        values[0] != values[1] || values[1] != values[2]
+    }));
+    check(&glyph, 0.2, 2);
+}
+
+fn check(glyph: &Glyph, scale: f32, padding: usize) {
+    let bitmap = raster::rasterize(glyph, scale, Offset(0.0, 0.0));
+    for field in [
+        distance::single(glyph, scale, 4.0, padding),
+        distance::multiple(glyph, scale, 4.0, padding),
+    ] {
+        assert_eq!(field.width, bitmap.width + 2 * padding);
+        assert_eq!(field.height, bitmap.height + 2 * padding);
+        for y in 0..bitmap.height {
+            for x in 0..bitmap.width {
+                let coverage = ok!(bitmap.get(x, y));
+                let value = median(ok!(field.get(x + padding, y + padding)));
+                if coverage == 255 {
+                    assert!(value >= 128, "{x} {y}");
+                } else if coverage == 0 {
+                    assert!(value <= 128, "{x} {y}");
+                }
+            }
+        }
+    }
+}
+
+fn median(values: &[u8]) -> u8 {
+    match *values {
+        [a] => a,
+        [a, b, c] => a.min(b).max(a.max(b).min(c)),
+        _ => unreachable!(),
+    }
+}
+
+mod adobe_vf_prototype {
+    use crate::support::{setup, Fixture};
+
+    #[test]
+    fn dollar() {
+        let font = &mut setup(Fixture::AdobeVFPrototype)[0];
+        let scale = 32.0 / ok!(font.metrics()).granularity;
+        let glyph = ok!(ok!(font.glyph('$')));
+        crate::check(&glyph, scale, 2);
+    }
+}
+
+mod open_sans {
+    use crate::support::{setup, Fixture};
+
+    #[test]
+    fn a() {
+        let font = &mut setup(Fixture::OpenSans)[0];
+        let scale = 32.0 / ok!(font.metrics()).granularity;
+        let glyph = ok!(ok!(font.glyph('a')));
+        crate::check(&glyph, scale, 2);
+    }
+}
+
+mod source_serif {
+    use crate::support::{setup, Fixture};
+
+    #[test]
+    fn a() {
+        let font = &mut setup(Fixture::SourceSerif)[0];
+        let scale = 32.0 / ok!(font.metrics()).granularity;
+        let glyph = ok!(ok!(font.glyph('A')));
+        crate::check(&glyph, scale, 2);
+    }
+}
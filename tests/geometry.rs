@@ -0,0 +1,85 @@
+#[macro_use]
+mod support;
+
+use font::glyph::{Builder, Direction, Pen};
+use font::{Glyph, Offset};
+
+use crate::support::{build, circle, setup, Fixture};
+
+#[test]
+fn area() {
+    let mut builder = Builder::default();
+    builder.move_to((0.0, 0.0).into());
+    builder.line_to((0.0, 10.0).into());
+    builder.line_to((10.0, 10.0).into());
+    builder.line_to((10.0, 0.0).into());
+    builder.close();
+    builder.move_to((0.0, 0.0).into());
+    builder.quad_to((1.0, 2.0).into(), (2.0, 0.0).into());
+    builder.close();
+    let glyph: Glyph = builder.into();
+    assert_eq!(glyph[0].area(), -100.0);
+    assert_eq!(glyph[0].direction(), Direction::Clockwise);
+    assert!((glyph[1].area() + 4.0 / 3.0).abs() < 1e-6);
+    assert!((glyph.area() + 100.0 + 4.0 / 3.0).abs() < 1e-4);
+
+    let glyph = build(|builder| circle(builder, (0.0, 0.0), 100.0));
+    assert_eq!(glyph[0].direction(), Direction::Counterclockwise);
+    assert!((glyph.area() - std::f32::consts::PI * 100.0 * 100.0).abs() < 10.0);
+}
+
+#[test]
+fn bounds() {
+    let mut builder = Builder::default();
+    builder.move_to((0.0, 0.0).into());
+    builder.quad_to((50.0, 100.0).into(), (100.0, 0.0).into());
+    builder.curve_to(
+        (100.0, -40.0).into(),
+        (0.0, -40.0).into(),
+        (0.0, 0.0).into(),
+    );
+    builder.close();
+    let glyph: Glyph = builder.into();
+    assert_eq!(glyph.bounds(), (0.0, -30.0, 100.0, 50.0));
+
+    let font = &mut setup(Fixture::OpenSans)[0];
+    for character in ['a', 'o', 'S', '&'] {
+        let glyph = ok!(ok!(font.glyph(character)));
+        let (left, bottom, right, top) = glyph.bounds();
+        let (expected_left, expected_bottom, expected_right, expected_top) = glyph.bounding_box;
+        assert!(left >= expected_left && bottom >= expected_bottom);
+        assert!(right <= expected_right && top <= expected_top);
+        assert!(expected_right - right < 1.0 && top - expected_top > -1.0);
+    }
+}
+
+#[test]
+fn flatten() {
+    let glyph = build(|builder| circle(builder, (0.0, 0.0), 100.0));
+    let contours = glyph.flatten(0.1);
+    assert_eq!(contours.len(), 1);
+    let points = &contours[0];
+    assert_eq!(points[0], Offset(100.0, 0.0));
+    assert_eq!(points[points.len() - 1], Offset(100.0, 0.0));
+    for pair in points.windows(2) {
+        let middle = (pair[0] + pair[1]) / 2.0;
+        assert!(100.0 - middle.0.hypot(middle.1) <= 0.15);
+    }
+    assert!(glyph.flatten(1.0)[0].len() < points.len());
+}
+
+#[test]
+fn length() {
+    let glyph = build(|builder| circle(builder, (0.0, 0.0), 100.0));
+    assert!((glyph.length() - 2.0 * std::f32::consts::PI * 100.0).abs() < 0.1);
+
+    let mut builder = Builder::default();
+    builder.move_to((0.0, 0.0).into());
+    builder.line_to((3.0, 4.0).into());
+    builder.quad_to((4.5, 6.0).into(), (6.0, 8.0).into());
+    let glyph: Glyph = builder.into();
+    assert!((glyph.length() - 20.0).abs() < 1e-4);
+
+    let glyph = ok!(ok!(setup(Fixture::SourceSerif)[0].glyph('H')));
+    assert!((glyph.length() - 5009.1).abs() < 0.1);
+}
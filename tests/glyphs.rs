@@ -156,9 +156,42 @@ mod source_serif {
         assert_eq!(glyph.bounding_box, (34.0, 0.0, 412.0, 491.0));
         assert_eq!(glyph.side_bearings, (34.0, 11.0));
     }
+
+    #[test]
+    fn bounding_box() {
+        use font::characters::Character;
+
+        let font = &mut setup(Fixture::SourceSerif)[0];
+        let (left, bottom, right, top) = ok!(font.bounding_box());
+        let mut count = 0;
+        for character in ok!(font.characters()) {
+            let (start, end) = match character {
+                Character::Scalar(value) => (value, value),
+                Character::Range(value) => value,
+            };
+            for character in start..=end {
+                let glyph = match ok!(font.glyph(character)) {
+                    Some(glyph) if !glyph.is_empty() => glyph,
+                    _ => continue,
+                };
+                let bounds = glyph.bounds();
+                let (one, other) = (glyph.bounding_box, bounds);
+                assert!((one.0 - other.0).abs() < 1e-3, "{character}");
+                assert!((one.1 - other.1).abs() < 1e-3, "{character}");
+                assert!((one.2 - other.2).abs() < 1e-3, "{character}");
+                assert!((one.3 - other.3).abs() < 1e-3, "{character}");
+                assert!(bounds.0 >= left && bounds.1 >= bottom, "{character}");
+                assert!(bounds.2 <= right && bounds.3 <= top, "{character}");
+                count += 1;
+            }
+        }
+        assert!(count > 400);
+    }
 }
 
 mod open_sans {
+    use font::Offset;
+
     use crate::support::{setup, trace, Fixture};
 
     #[test]
@@ -217,6 +250,36 @@ mod open_sans {
         ]);
     }
 
+    #[test]
+    fn a_ring_components() {
+        let font = &mut setup(Fixture::OpenSans)[0];
+        let glyph_id = ok!(ok!(font.glyph_id('å')));
+        let glyph = ok!(ok!(font.glyph('å')));
+        assert_eq!(glyph.components.len(), 2);
+        assert_eq!(glyph.components[0].glyph_id, ok!(ok!(font.glyph_id('a'))));
+        assert_eq!(glyph.components[0].offset, Offset(0.0, 0.0));
+        assert_eq!(glyph.components[1].offset, Offset(-38.0, 0.0));
+
+        let other = ok!(font.glyph_by_id(glyph_id, false));
+        assert_eq!(other.len(), 0);
+        assert_eq!(other.components, glyph.components);
+        assert_eq!(other.advance_width, glyph.advance_width);
+        assert_eq!(other.bounding_box, glyph.bounding_box);
+
+        let mut points = vec![];
+        for component in other.components.iter() {
+            let child = ok!(font.glyph_by_id(component.glyph_id, false));
+            assert!(child.components.is_empty());
+            let (xx, xy, yx, yy) = component.scale;
+            let (x, y) = (component.offset.0, component.offset.1);
+            points.extend(trace(&child.transform((xx, xy, yx, yy, x, y))));
+        }
+        assert_eq!(points, trace(&glyph));
+
+        let other = ok!(font.glyph_by_id(glyph_id, true));
+        assert_eq!(trace(&other), trace(&glyph));
+    }
+
     #[test]
     fn copyright() {
         let font = &mut setup(Fixture::OpenSans)[0];
@@ -439,3 +502,138 @@ mod zen_loop {
         ]);
     }
 }
+
+mod pen {
+    use font::glyph::{Pen, Transform};
+    use font::Offset;
+
+    use crate::support::{setup, Fixture};
+
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl Pen for Recorder {
+        fn move_to(&mut self, a: Offset) {
+            self.0.push(format!("M {} {}", a.0, a.1));
+        }
+
+        fn line_to(&mut self, a: Offset) {
+            self.0.push(format!("L {} {}", a.0, a.1));
+        }
+
+        fn quad_to(&mut self, a: Offset, b: Offset) {
+            self.0.push(format!("Q {} {} {} {}", a.0, a.1, b.0, b.1));
+        }
+
+        fn curve_to(&mut self, a: Offset, b: Offset, c: Offset) {
+            self.0
+                .push(format!("C {} {} {} {} {} {}", a.0, a.1, b.0, b.1, c.0, c.1));
+        }
+
+        fn close(&mut self) {
+            self.0.push("Z".into());
+        }
+    }
+
+    #[test]
+    fn open_sans() {
+        let font = &mut setup(Fixture::OpenSans)[0];
+        let mut pen = Recorder::default();
+        assert!(ok!(font.draw('i', &mut pen)));
+        assert_eq!(
+            pen.0.join(" "),
+            "M 227 0 L 59 0 L 293 1096 L 461 1096 L 227 0 Z \
+             M 340 1376 Q 340 1432 372 1467.5 Q 404 1503 455 1503 Q 543 1503 543 1413 \
+             Q 543 1358 509.5 1320 Q 476 1282 432 1282 Q 392 1282 366 1306.5 \
+             Q 340 1331 340 1376 Z",
+        );
+        assert!(!ok!(font.draw('\u{E000}', &mut Recorder::default())));
+    }
+
+    #[test]
+    fn source_serif() {
+        let font = &mut setup(Fixture::SourceSerif)[0];
+        let mut pen = Recorder::default();
+        assert!(ok!(font.draw('o', &mut pen)));
+        assert_eq!(
+            pen.0.join(" "),
+            "M 274 445 C 361 445 409 371 409 236 C 409 102 361 30 274 30 \
+             C 188 30 140 102 140 236 C 140 371 188 445 274 445 Z \
+             M 274 491 C 159 491 45 402 45 237 C 45 71 158 -15 274 -15 \
+             C 391 -15 504 71 504 237 C 504 402 390 491 274 491 Z",
+        );
+        assert!(!ok!(font.draw('\u{E000}', &mut Recorder::default())));
+    }
+
+    #[test]
+    fn transform() {
+        let font = &mut setup(Fixture::SourceSerif)[0];
+        let mut pen = Recorder::default();
+        assert!(ok!(font.draw(
+            'l',
+            &mut Transform::new(&mut pen, (2.0, 0.0, 0.0, 1.0, 10.0, 0.0)),
+        )));
+        assert_eq!(
+            pen.0.join(" "),
+            "M 396 50 C 394 103 392 157 392 210 L 392 579 L 400 721 L 372 729 \
+             L 60 684 L 60 649 L 224 639 L 224 210 C 224 157 222 104 220 50 \
+             L 60 36 L 60 0 L 556 0 L 556 36 Z",
+        );
+    }
+}
+
+mod points {
+    use font::glyph::{Point, Segment};
+    use font::{Glyph, Offset};
+
+    use crate::support::{setup, trace, Fixture};
+
+    #[test]
+    fn implied() {
+        let mut glyph = Glyph::default();
+        glyph.set_points([vec![
+            Point::On(Offset(0.0, 0.0)),
+            Point::Off(Offset(0.0, 100.0)),
+            Point::Off(Offset(100.0, 100.0)),
+            Point::Off(Offset(100.0, 0.0)),
+        ]]);
+        assert_eq!(glyph.len(), 1);
+        assert_eq!(
+            glyph[0].segments,
+            vec![
+                Segment::Quadratic(Offset(0.0, 100.0), Offset(50.0, 0.0)),
+                Segment::Quadratic(Offset(50.0, 0.0), Offset(0.0, -50.0)),
+                Segment::Quadratic(Offset(0.0, -50.0), Offset(-100.0, 0.0)),
+            ],
+        );
+    }
+
+    #[test]
+    fn open_sans() {
+        test(Fixture::OpenSans, &['a', 'å', 'o']);
+    }
+
+    #[test]
+    fn source_serif() {
+        test(Fixture::SourceSerif, &['a', 'o', 'r']);
+    }
+
+    fn test(fixture: Fixture, characters: &[char]) {
+        let font = &mut setup(fixture)[0];
+        for &character in characters {
+            let glyph = ok!(ok!(font.glyph(character)));
+            let points = glyph
+                .points()
+                .into_iter()
+                .flatten()
+                .filter(|point| point.is_on_curve())
+                .map(|point| point.offset().into())
+                .collect::<Vec<(f32, f32)>>();
+            assert_eq!(points, trace(&glyph));
+            let mut other = glyph.clone();
+            other.set_points(glyph.points());
+            assert_eq!(other.points(), glyph.points());
+            assert_eq!(trace(&other), trace(&glyph));
+        }
+    }
+}
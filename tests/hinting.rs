@@ -0,0 +1,122 @@
+#[macro_use]
+mod support;
+
+mod crimson_text {
+    use crate::support::{setup, trace, Fixture};
+
+    #[test]
+    fn hyphen() {
+        let font = &mut setup(Fixture::CrimsonText)[0];
+        let glyph = ok!(ok!(font.hinted_glyph('-', 12)));
+        assert_eq!(glyph.len(), 1);
+        assert_eq!(glyph.advance_width, 4.0);
+        assert_eq!(glyph.bounding_box, (0.6875, 2.15625, 3.734375, 2.859375));
+        assert_eq!(glyph.side_bearings, (0.6875, 0.265625));
+        #[rustfmt::skip]
+        assert_eq!(&trace(&glyph), &vec![
+            (0.765625 , 2.15625  ),
+            (0.6875   , 2.21875  ),
+            (0.828125 , 2.859375 ),
+            (3.671875 , 2.859375 ),
+            (3.734375 , 2.828125 ),
+            (3.7109375, 2.4765625),
+            (3.625    , 2.15625  ),
+            (3.59375  , 2.15625  ),
+            (0.765625 , 2.15625  ),
+        ]);
+    }
+
+    #[test]
+    fn period() {
+        let font = &mut setup(Fixture::CrimsonText)[0];
+        let glyph = ok!(ok!(font.hinted_glyph('.', 12)));
+        assert_eq!(glyph.len(), 1);
+        assert_eq!(glyph.advance_width, 3.0);
+        #[rustfmt::skip]
+        assert_eq!(&trace(&glyph), &vec![
+            (1.375    , 0.0      ),
+            (0.890625 , 0.1953125),
+            (0.6875   , 0.671875 ),
+            (0.8828125, 1.140625 ),
+            (1.375    , 1.34375  ),
+            (1.859375 , 1.140625 ),
+            (2.046875 , 0.671875 ),
+            (1.8515625, 0.1953125),
+            (1.375    , 0.0      ),
+        ]);
+    }
+
+    #[test]
+    fn sizes() {
+        let font = &mut setup(Fixture::CrimsonText)[0];
+        let glyph = ok!(ok!(font.hinted_glyph('.', 12)));
+        let other = ok!(ok!(font.hinted_glyph('.', 24)));
+        assert_eq!(other.advance_width, 5.0);
+        assert!(glyph.bounding_box != other.bounding_box);
+        let another = ok!(ok!(font.hinted_glyph('.', 12)));
+        assert_eq!(trace(&glyph), trace(&another));
+    }
+}
+
+mod open_sans {
+    use crate::support::{setup, trace, Fixture};
+
+    #[test]
+    fn i_acute() {
+        let font = &mut setup(Fixture::OpenSans)[0];
+        let glyph = ok!(ok!(font.hinted_glyph('í', 12)));
+        assert_eq!(glyph.len(), 2);
+        assert_eq!(glyph.advance_width, 3.0);
+        assert!(glyph.components.is_empty());
+        #[rustfmt::skip]
+        assert_eq!(&trace(&glyph), &vec![
+            (1.328125 , 0.0      ),
+            (0.34375  , 0.0      ),
+            (1.71875  , 6.0      ),
+            (2.703125 , 6.0      ),
+            (1.328125 , 0.0      ),
+
+            (2.125    , 7.171875 ),
+            (2.859375 , 8.0859375),
+            (3.484375 , 9.0      ),
+            (4.59375  , 9.0      ),
+            (4.59375  , 8.875    ),
+            (3.7734375, 7.953125 ),
+            (2.734375 , 7.0      ),
+            (2.125    , 7.0      ),
+            (2.125    , 7.171875 ),
+        ]);
+    }
+
+    #[test]
+    fn l() {
+        let font = &mut setup(Fixture::OpenSans)[0];
+        let glyph = ok!(ok!(font.hinted_glyph('l', 12)));
+        assert_eq!(glyph.advance_width, 3.0);
+        assert_eq!(glyph.bounding_box, (0.328125, 0.0, 3.265625, 9.0));
+        #[rustfmt::skip]
+        assert_eq!(&trace(&glyph), &vec![
+            (1.3125  , 0.0),
+            (0.328125, 0.0),
+            (2.28125 , 9.0),
+            (3.265625, 9.0),
+            (1.3125  , 0.0),
+        ]);
+    }
+
+    #[test]
+    fn missing() {
+        let font = &mut setup(Fixture::OpenSans)[0];
+        assert!(ok!(font.hinted_glyph('\u{E000}', 12)).is_none());
+    }
+}
+
+mod source_serif {
+    use crate::support::{setup, Fixture};
+
+    #[test]
+    fn unsupported() {
+        let font = &mut setup(Fixture::SourceSerif)[0];
+        assert!(font.hinted_glyph('a', 12).is_err());
+    }
+}
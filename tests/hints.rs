@@ -0,0 +1,70 @@
+#[macro_use]
+mod support;
+
+use font::glyph::Mask;
+
+use crate::support::{setup, Fixture};
+
+#[test]
+fn alignment() {
+    let font = &mut setup(Fixture::SourceSerif)[0];
+    let alignment = ok!(ok!(font.alignment()));
+    #[rustfmt::skip]
+    assert_eq!(alignment.blue_values, vec![
+        (-20.0, 0.0),
+        (473.0, 491.0),
+        (525.0, 540.0),
+        (644.0, 659.0),
+        (669.0, 689.0),
+        (729.0, 749.0),
+    ]);
+    assert_eq!(alignment.other_blues, vec![(-249.0, -239.0)]);
+    assert_eq!(alignment.standard_horizontal_width, Some(41.0));
+    assert_eq!(alignment.standard_vertical_width, Some(85.0));
+
+    let font = &mut setup(Fixture::OpenSans)[0];
+    assert!(ok!(font.alignment()).is_none());
+}
+
+#[test]
+fn masks() {
+    let font = &mut setup(Fixture::SourceSerif)[0];
+    let glyph = ok!(ok!(font.glyph('a')));
+    let hints = ok!(glyph.hints);
+    assert_eq!(
+        hints.horizontal,
+        vec![(-15.0, 51.0), (-10.0, 42.0), (445.0, 491.0)],
+    );
+    assert_eq!(hints.vertical, vec![(45.0, 134.0), (324.0, 408.0)]);
+    assert_eq!(
+        hints.masks[..2],
+        [
+            Mask {
+                segment: 0,
+                active: vec![true, false, true, true, true],
+            },
+            Mask {
+                segment: 5,
+                active: vec![false, true, true, true, true],
+            },
+        ],
+    );
+    assert_eq!(hints.masks.len(), 4);
+    assert_eq!(hints.active(4), vec![true, false, true, true, true]);
+    assert_eq!(hints.active(5), vec![false, true, true, true, true]);
+    assert!(hints.counters.is_empty());
+}
+
+#[test]
+fn stems() {
+    let font = &mut setup(Fixture::SourceSerif)[0];
+    let glyph = ok!(ok!(font.glyph('o')));
+    let hints = ok!(glyph.hints);
+    assert_eq!(hints.horizontal, vec![(-15.0, 30.0), (445.0, 491.0)]);
+    assert_eq!(hints.vertical, vec![(45.0, 140.0), (409.0, 504.0)]);
+    assert!(hints.masks.is_empty());
+    assert_eq!(hints.active(3), vec![true; 4]);
+
+    let font = &mut setup(Fixture::OpenSans)[0];
+    assert!(ok!(ok!(font.glyph('o'))).hints.is_none());
+}
@@ -0,0 +1,131 @@
+#[macro_use]
+mod support;
+
+use std::io::Cursor;
+
+use font::formats::opentype::{merge, merge_with, read, write, Disposition};
+use font::opentype::tables::GlyphPositioning;
+use font::opentype::truetype::tables::MaximumProfile;
+use font::opentype::truetype::Tag;
+use font::Case;
+
+use crate::support::{setup, Fixture};
+
+#[test]
+fn open_sans_qahiri() {
+    let fonts = [
+        ok!(read(ok!(std::fs::File::open(support::path(
+            Fixture::OpenSans
+        )))))
+        .remove(0),
+        ok!(read(ok!(std::fs::File::open(support::path(
+            Fixture::Qahiri
+        )))))
+        .remove(0),
+    ];
+    let mut font = ok!(merge(&fonts));
+
+    let mut latin = setup(Fixture::OpenSans);
+    let mut arabic = setup(Fixture::Qahiri);
+    let scale = ok!(latin[0].metrics()).granularity / ok!(arabic[0].metrics()).granularity;
+    let one = ok!(ok!(latin[0].glyph('a')));
+    let other = ok!(ok!(font.glyph('a')));
+    assert_eq!(one.advance_width, other.advance_width);
+    assert_eq!(one.bounding_box, other.bounding_box);
+    let one = ok!(ok!(arabic[0].glyph('ا')));
+    let other = ok!(ok!(font.glyph('ا')));
+    assert!((one.advance_width * scale - other.advance_width).abs() <= 1.0);
+    assert!((one.bounding_box.2 * scale - other.bounding_box.2).abs() <= 1.0);
+    assert_eq!(one.contours.len(), other.contours.len());
+
+    let features = ok!(font.features());
+    let scripts = features
+        .scripts
+        .iter()
+        .map(|(script, _)| ok!(Tag::from(*script).as_str()).to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(scripts, ["arab", "DFLT", "latn"]);
+    let count = ok!(latin[0].features()).features.len() + ok!(arabic[0].features()).features.len();
+    assert_eq!(features.features.len(), count);
+
+    let mut cursor = Cursor::new(vec![]);
+    ok!(write(font, &mut cursor, |_| Disposition::Retain));
+    let fonts = ok!(read(Cursor::new(cursor.into_inner())));
+    assert_eq!(fonts.len(), 1);
+}
+
+#[test]
+fn positioning() {
+    let fonts = [
+        ok!(read(ok!(std::fs::File::open(support::path(
+            Fixture::OpenSans
+        )))))
+        .remove(0),
+        ok!(read(ok!(std::fs::File::open(support::path(
+            Fixture::CrimsonText
+        )))))
+        .remove(0),
+    ];
+    let mut font = ok!(merge_with(&fonts, |_, indices| indices[indices.len() - 1]));
+    let mut crimson = setup(Fixture::CrimsonText);
+    let mut open = setup(Fixture::OpenSans);
+    let scale = ok!(open[0].metrics()).granularity / ok!(crimson[0].metrics()).granularity;
+
+    let (glyph_count, _) = tables(ok!(std::fs::read(support::path(Fixture::OpenSans))));
+    let glyph_id = ok!(ok!(crimson[0].glyph_id('A')));
+    assert_eq!(ok!(ok!(font.glyph_id('A'))), glyph_id + glyph_count);
+    let characters = "AVTWYaeoy.,".chars().collect::<Vec<_>>();
+    let pairs = ok!(crimson[0].kerning_pairs(&characters));
+    assert!(!pairs.is_empty());
+    for (one, other, value) in pairs {
+        assert!((ok!(font.kerning(one, other)) - value * scale).abs() <= 1.0);
+    }
+
+    let mut cursor = Cursor::new(vec![]);
+    ok!(write(font, &mut cursor, |_| Disposition::Retain));
+    let (_, count) = tables(cursor.into_inner());
+    let (_, other) = tables(ok!(std::fs::read(support::path(Fixture::CrimsonText))));
+    assert!(count > 0);
+    assert_eq!(count, other);
+}
+
+#[test]
+fn precedence() {
+    let fonts = [
+        ok!(read(ok!(std::fs::File::open(support::path(
+            Fixture::OpenSans
+        )))))
+        .remove(0),
+        ok!(read(ok!(std::fs::File::open(support::path(
+            Fixture::CrimsonText
+        )))))
+        .remove(0),
+    ];
+    let mut font = ok!(merge_with(&fonts, |character, indices| {
+        if character == 'a' {
+            indices[indices.len() - 1]
+        } else {
+            indices[0]
+        }
+    }));
+    let mut crimson = setup(Fixture::CrimsonText);
+    let mut open = setup(Fixture::OpenSans);
+    let scale = ok!(open[0].metrics()).granularity / ok!(crimson[0].metrics()).granularity;
+    let one = ok!(ok!(crimson[0].glyph('a')));
+    let other = ok!(ok!(font.glyph('a')));
+    assert!((one.advance_width * scale - other.advance_width).abs() <= 1.0);
+    let one = ok!(ok!(open[0].glyph('b')));
+    let other = ok!(ok!(font.glyph('b')));
+    assert_eq!(one.advance_width, other.advance_width);
+}
+
+fn tables(data: Vec<u8>) -> (u16, usize) {
+    let mut cursor = Cursor::new(data);
+    let file = ok!(font::opentype::File::read(&mut cursor));
+    let maximum_profile = ok!(ok!(file[0].take::<_, MaximumProfile>(&mut cursor)));
+    let positioning = ok!(ok!(file[0].take::<_, GlyphPositioning>(&mut cursor)));
+    (
+        maximum_profile.glyph_count() as u16,
+        positioning.lookups.records.len(),
+    )
+}
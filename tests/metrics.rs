@@ -16,6 +16,10 @@ fn crimson_text() {
     assert_eq!(values.descender, -359.0);
     assert_eq!(values.clipping_descender, -297.0);
     assert_eq!(values.line_gap, 0.0);
+    assert_eq!(
+        ok!(file[0].bounding_box()),
+        (-190.0, -297.0, 1549.0, 1038.0)
+    );
 }
 
 #[test]
@@ -38,6 +42,10 @@ fn open_sans() {
     assert_eq!(values.descender, -492.0);
     assert_eq!(values.clipping_descender, -600.0);
     assert_eq!(values.line_gap, 132.0);
+    assert_eq!(
+        ok!(file[0].bounding_box()),
+        (-1020.0, -549.0, 2521.0, 2146.0)
+    );
 }
 
 #[test]
@@ -53,4 +61,5 @@ fn source_serif() {
     assert_eq!(values.descender, -270.0);
     assert_eq!(values.clipping_descender, -335.0);
     assert_eq!(values.line_gap, 0.0);
+    assert_eq!(ok!(file[0].bounding_box()), (-178.0, -335.0, 1138.0, 918.0));
 }
@@ -0,0 +1,144 @@
+#[macro_use]
+mod support;
+
+use std::path::PathBuf;
+
+use font::glyph::{Builder, Pen};
+use font::raster::{self, Bitmap};
+use font::{Glyph, Offset};
+
+#[test]
+fn square() {
+    let mut builder = Builder::default();
+    builder.move_to((0.0, 0.0).into());
+    builder.line_to((10.0, 0.0).into());
+    builder.line_to((10.0, 10.0).into());
+    builder.line_to((0.0, 10.0).into());
+    builder.close();
+    let glyph: Glyph = builder.into();
+    let bitmap = raster::rasterize(&glyph, 0.1, Offset(0.5, 0.5));
+    assert_eq!((bitmap.width, bitmap.height), (2, 2));
+    assert_eq!((bitmap.left, bitmap.top), (0, 2));
+    assert_eq!(bitmap.data, vec![64; 4]);
+    let bitmap = raster::rasterize(&glyph, 0.2, Offset(0.0, 0.0));
+    assert_eq!(bitmap.data, vec![255; 4]);
+}
+
+#[test]
+fn overlap() {
+    let mut builder = Builder::default();
+    for _ in 0..2 {
+        builder.move_to((0.0, 0.0).into());
+        builder.line_to((2.0, 0.0).into());
+        builder.line_to((2.0, 2.0).into());
+        builder.line_to((0.0, 2.0).into());
+        builder.close();
+    }
+    builder.move_to((0.5, 0.5).into());
+    builder.line_to((0.5, 1.5).into());
+    builder.line_to((1.5, 1.5).into());
+    builder.line_to((1.5, 0.5).into());
+    builder.close();
+    let glyph: Glyph = builder.into();
+    let bitmap = raster::rasterize(&glyph, 1.0, Offset(0.0, 0.0));
+    assert_eq!(bitmap.data, vec![255; 4]);
+}
+
+#[test]
+fn circle() {
+    let mut builder = Builder::default();
+    let k = 0.5523 * 50.0;
+    builder.move_to((50.0, 0.0).into());
+    builder.curve_to((50.0, k).into(), (k, 50.0).into(), (0.0, 50.0).into());
+    builder.curve_to((-k, 50.0).into(), (-50.0, k).into(), (-50.0, 0.0).into());
+    builder.curve_to((-50.0, -k).into(), (-k, -50.0).into(), (0.0, -50.0).into());
+    builder.curve_to((k, -50.0).into(), (50.0, -k).into(), (50.0, 0.0).into());
+    builder.close();
+    let glyph: Glyph = builder.into();
+    let bitmap = raster::rasterize(&glyph, 0.2, Offset(0.0, 0.0));
+    assert_eq!((bitmap.width, bitmap.height), (20, 20));
+    assert_eq!((bitmap.left, bitmap.top), (-10, 10));
+    let area = bitmap
+        .data
+        .iter()
+        .map(|&value| value as f32 / 255.0)
+        .sum::<f32>();
+    assert!((area - std::f32::consts::PI * 100.0).abs() < 2.0);
+}
+
+mod monte_carlo {
+    use font::raster;
+    use font::Offset;
+
+    use crate::support::{setup, Fixture};
+
+    #[test]
+    fn i() {
+        let font = &mut setup(Fixture::MonteCarlo)[0];
+        let scale = 48.0 / ok!(font.metrics()).granularity;
+        let glyph = ok!(ok!(font.glyph('i')));
+        let bitmap = raster::rasterize(&glyph, scale, Offset(0.25, 0.0));
+        crate::compare(&bitmap, "MonteCarlo-Regular-i.pgm");
+    }
+}
+
+mod open_sans {
+    use font::raster;
+    use font::Offset;
+
+    use crate::support::{setup, Fixture};
+
+    #[test]
+    fn a() {
+        let font = &mut setup(Fixture::OpenSans)[0];
+        let scale = 32.0 / ok!(font.metrics()).granularity;
+        let glyph = ok!(ok!(font.glyph('a')));
+        let bitmap = raster::rasterize(&glyph, scale, Offset(0.0, 0.0));
+        crate::compare(&bitmap, "OpenSans-Italic-a.pgm");
+    }
+}
+
+mod source_serif {
+    use font::raster;
+    use font::Offset;
+
+    use crate::support::{setup, Fixture};
+
+    #[test]
+    fn ampersand() {
+        let font = &mut setup(Fixture::SourceSerif)[0];
+        let scale = 32.0 / ok!(font.metrics()).granularity;
+        let glyph = ok!(ok!(font.glyph('&')));
+        let bitmap = raster::rasterize(&glyph, scale, Offset(0.5, 0.5));
+        crate::compare(&bitmap, "SourceSerifPro-Regular-ampersand.pgm");
+    }
+}
+
+fn compare(bitmap: &Bitmap, name: &str) {
+    let path = PathBuf::from("tests")
+        .join("fixtures")
+        .join("raster")
+        .join(name);
+    if std::env::var("GENERATE").is_ok() {
+        ok!(std::fs::write(&path, encode(bitmap)));
+    }
+    assert_eq!(encode(bitmap), ok!(std::fs::read_to_string(path)));
+}
+
+fn encode(bitmap: &Bitmap) -> String {
+    let mut lines = vec![
+        "P2".into(),
+        format!("# left {} top {}", bitmap.left, bitmap.top),
+        format!("{} {}", bitmap.width, bitmap.height),
+        "255".into(),
+    ];
+    for row in bitmap.data.chunks(bitmap.width) {
+        let row = row
+            .iter()
+            .map(|value| format!("{value:3}"))
+            .collect::<Vec<_>>();
+        lines.push(row.join(" "));
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
@@ -0,0 +1,184 @@
+#[macro_use]
+mod support;
+
+use std::fs::File;
+use std::io::Cursor;
+
+use font::formats::opentype::{read, subset, subset_with, Subroutines};
+use font::opentype::layout::Feature;
+use font::opentype::tables::GlyphSubstitution;
+use font::opentype::truetype::tables::{FontHeader, MaximumProfile};
+use font::opentype::truetype::Tag;
+use font::Case;
+
+use crate::support::{path, trace, Fixture};
+
+#[test]
+fn open_sans() {
+    let features = [Feature::StandardLigatures, Feature::Kerning];
+    let data = run(Fixture::OpenSans, "fiAV", &features, Subroutines::Flatten);
+
+    let mut cursor = Cursor::new(&data);
+    let file = ok!(font::opentype::File::read(&mut cursor));
+    let table = ok!(file[0].take::<_, GlyphSubstitution>(&mut cursor));
+    let tags = ok!(table)
+        .features
+        .headers
+        .iter()
+        .map(|header| header.tag)
+        .collect::<Vec<_>>();
+    assert_eq!(tags, [Tag(*b"liga"); 3]);
+    let table = ok!(file[0].take::<_, MaximumProfile>(&mut cursor));
+    assert_eq!(ok!(table).glyph_count(), 8);
+    let mut fonts = ok!(read(Cursor::new(data)));
+    let font = &mut fonts[0];
+    let tables = ok!(font.tables());
+    assert!(tables.contains(&Tag(*b"GSUB")));
+    assert!(!tables.contains(&Tag(*b"GPOS")));
+    assert!(!tables.contains(&Tag(*b"DSIG")));
+    assert!(tables.contains(&Tag(*b"kern")));
+    assert!(ok!(font.glyph('z')).is_none());
+    let mut other = ok!(read(ok!(File::open(path(Fixture::OpenSans))))).remove(0);
+    for (one, another) in [('A', 'V'), ('V', 'A'), ('f', 'i')] {
+        assert_eq!(
+            ok!(font.kerning(one, another)),
+            ok!(other.kerning(one, another)),
+        );
+    }
+    assert!(ok!(font.kerning('A', 'V')) < 0.0);
+}
+
+#[test]
+fn open_sans_large() {
+    let path = path(Fixture::OpenSans);
+    let mut fonts = ok!(read(ok!(File::open(&path))));
+    let font = &mut fonts[0];
+    let characters = (0x20..0x10000)
+        .filter_map(char::from_u32)
+        .collect::<Vec<_>>();
+    let data = ok!(subset(font, &characters, &[]));
+
+    let mut cursor = Cursor::new(&data);
+    let file = ok!(font::opentype::File::read(&mut cursor));
+    let record = file[0]
+        .offsets
+        .records
+        .iter()
+        .find(|record| record.tag == b"glyf");
+    assert!(ok!(record.ok_or("found no glyph data")).size > 0xFFFF);
+    let table = ok!(file[0].take::<_, FontHeader>(&mut cursor));
+    assert_eq!(ok!(table).glyph_mapping_format, 0);
+    let mut other = ok!(read(Cursor::new(data.clone()))).remove(0);
+    for character in characters {
+        let one = match ok!(font.glyph(character)) {
+            Some(one) => one,
+            _ => continue,
+        };
+        let other = ok!(ok!(other.glyph(character)));
+        assert_eq!(one.advance_width, other.advance_width);
+        assert_eq!(trace(&one), trace(&other));
+    }
+}
+
+#[test]
+fn source_serif() {
+    let characters = "abcdefghijklmnopqrstuvwxyz";
+    let sizes = [
+        Subroutines::Retain,
+        Subroutines::Flatten,
+        Subroutines::Factor,
+    ]
+    .map(|subroutines| {
+        let data = run(Fixture::SourceSerif, characters, &[], subroutines);
+        ok!(font::opentype::File::read(&mut Cursor::new(&data)));
+        let size = data.len();
+        let mut fonts = ok!(read(Cursor::new(data)));
+        let font = &mut fonts[0];
+        let tables = ok!(font.tables());
+        assert!(tables.contains(&Tag(*b"CFF ")));
+        assert!(!tables.contains(&Tag(*b"GSUB")));
+        assert!(ok!(font.glyph('A')).is_none());
+        size
+    });
+    assert!(sizes[1] < sizes[0]);
+    assert!(sizes[2] < sizes[1]);
+}
+
+#[test]
+fn source_serif_factor() {
+    let path = path(Fixture::SourceSerif);
+    let mut fonts = ok!(read(ok!(File::open(&path))));
+    let font = &mut fonts[0];
+    let characters = (0x20..0x10000)
+        .filter_map(char::from_u32)
+        .collect::<Vec<_>>();
+    let flat = ok!(subset_with(font, &characters, &[], Subroutines::Flatten));
+    let data = ok!(subset_with(font, &characters, &[], Subroutines::Factor));
+    assert!(data.len() < flat.len());
+
+    let mut other = ok!(read(Cursor::new(data))).remove(0);
+    for character in "AaÁfiż&".chars() {
+        let one = ok!(ok!(font.glyph(character)));
+        let other = ok!(ok!(other.glyph(character)));
+        assert_eq!(one.advance_width, other.advance_width);
+        assert_eq!(trace(&one), trace(&other));
+    }
+}
+
+#[test]
+fn source_serif_accent() {
+    use font::opentype::postscript::compact1::FontSet;
+
+    let path = path(Fixture::SourceSerif);
+    let mut data = ok!(std::fs::read(&path));
+    let mut fonts = ok!(read(ok!(File::open(&path))));
+    let glyph_id = ok!(ok!(fonts[0].glyph_id('Á')));
+    let mut cursor = Cursor::new(&data);
+    let file = ok!(font::opentype::File::read(&mut cursor));
+    let font_set = ok!(ok!(file[0].take::<_, FontSet>(&mut cursor)));
+    let code = &font_set.character_strings[0][glyph_id as usize];
+    let position = ok!(data
+        .windows(code.len())
+        .position(|window| window == &code[..]));
+    // Compose the acute accent shifted by (100, 200) with the letter A.
+    let accent = [239, 28, 0, 200, 204, 247, 86, 14];
+    data[position..(position + accent.len())].copy_from_slice(&accent);
+
+    let mut fonts = ok!(read(Cursor::new(data)));
+    let font = &mut fonts[0];
+    let data = ok!(subset(font, &['Á'], &[]));
+    let mut other = ok!(read(Cursor::new(data))).remove(0);
+    let one = ok!(ok!(font.glyph('Á')));
+    let other = ok!(ok!(other.glyph('Á')));
+    assert_eq!(one.components.len(), 2);
+    assert_eq!(trace(&one), trace(&other));
+    assert_eq!(one.contours.len(), other.contours.len());
+}
+
+fn run(
+    fixture: Fixture,
+    characters: &str,
+    features: &[Feature],
+    subroutines: Subroutines,
+) -> Vec<u8> {
+    let path = path(fixture);
+    let mut fonts = ok!(read(ok!(File::open(&path))));
+    let font = &mut fonts[0];
+    let size = ok!(std::fs::metadata(&path)).len();
+    let characters = characters.chars().collect::<Vec<_>>();
+    let data = ok!(subset_with(font, &characters, features, subroutines));
+    assert!((data.len() as u64) < size / 2);
+
+    let mut other = ok!(read(Cursor::new(data.clone()))).remove(0);
+    for &character in characters.iter() {
+        let one = ok!(ok!(font.glyph(character)));
+        let other = ok!(ok!(other.glyph(character)));
+        assert_eq!(one.advance_width, other.advance_width);
+        assert_eq!(one.bounding_box, other.bounding_box);
+        assert_eq!(one.contours.len(), other.contours.len());
+        for (one, other) in one.contours.iter().zip(other.contours.iter()) {
+            assert_eq!(one.segments, other.segments);
+        }
+    }
+    data
+}
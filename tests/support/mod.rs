@@ -2,10 +2,16 @@
 
 macro_rules! ok(($result:expr) => ($result.unwrap()));
 
+use std::io::Cursor;
 use std::path::PathBuf;
 
+use font::formats::opentype::{read, write_with, Disposition, Font, Insertion};
+use font::glyph::{Builder, Pen};
 use font::{File, Glyph};
 
+pub const K: f32 = 0.552_284_8;
+
+#[derive(Clone, Copy)]
 pub enum Fixture {
     AdobeBlank,
     AdobeVFPrototype,
@@ -45,6 +51,58 @@ pub fn path(fixture: Fixture) -> PathBuf {
     PathBuf::from("tests").join("fixtures").join(file_name)
 }
 
+pub fn build(draw: impl Fn(&mut Builder)) -> Glyph {
+    let mut builder = Builder::default();
+    draw(&mut builder);
+    builder.into()
+}
+
+pub fn circle(builder: &mut Builder, (x, y): (f32, f32), r: f32) {
+    let k = r * K;
+    builder.move_to((x + r, y).into());
+    builder.curve_to(
+        (x + r, y + k).into(),
+        (x + k, y + r).into(),
+        (x, y + r).into(),
+    );
+    builder.curve_to(
+        (x - k, y + r).into(),
+        (x - r, y + k).into(),
+        (x - r, y).into(),
+    );
+    builder.curve_to(
+        (x - r, y - k).into(),
+        (x - k, y - r).into(),
+        (x, y - r).into(),
+    );
+    builder.curve_to(
+        (x + k, y - r).into(),
+        (x + r, y - k).into(),
+        (x + r, y).into(),
+    );
+    builder.close();
+}
+
+pub fn square(builder: &mut Builder, (x, y): (f32, f32), size: f32) {
+    builder.move_to((x, y).into());
+    builder.line_to((x, y + size).into());
+    builder.line_to((x + size, y + size).into());
+    builder.line_to((x + size, y).into());
+    builder.close();
+}
+
+pub fn insert(insertions: Vec<Insertion>) -> Font<Cursor<Vec<u8>>> {
+    let font = ok!(ok!(read(ok!(std::fs::File::open(path(Fixture::OpenSans))))).pop());
+    let mut cursor: Cursor<Vec<u8>> = Cursor::new(vec![]);
+    ok!(write_with(
+        font,
+        &mut cursor,
+        |_| Disposition::Retain,
+        insertions
+    ));
+    ok!(ok!(read(Cursor::new(cursor.into_inner()))).pop())
+}
+
 pub fn trace(glyph: &Glyph) -> Vec<(f32, f32)> {
     use font::glyph::Segment::*;
     use font::Offset;
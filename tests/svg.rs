@@ -0,0 +1,55 @@
+#[macro_use]
+mod support;
+
+use font::glyph::{Builder, Pen};
+use font::Glyph;
+
+#[test]
+fn path() {
+    let mut builder = Builder::default();
+    builder.move_to((0.0, 0.0).into());
+    builder.line_to((100.0, 0.0).into());
+    builder.quad_to((100.0, 50.0).into(), (50.0, 100.0).into());
+    builder.curve_to((25.0, 100.0).into(), (0.0, 75.0).into(), (0.0, 50.0).into());
+    builder.close();
+    let glyph: Glyph = builder.into();
+    assert_eq!(
+        glyph.to_svg_path(0.5, true),
+        "M0,0 L50,0 Q50,-25,25,-50 C12.5,-50,0,-37.5,0,-25 Z",
+    );
+    assert_eq!(
+        glyph.to_svg_path(1.0, false),
+        "M0,0 L100,0 Q100,50,50,100 C25,100,0,75,0,50 Z",
+    );
+}
+
+mod open_sans {
+    use crate::support::{setup, Fixture};
+
+    #[test]
+    fn document() {
+        let font = &mut setup(Fixture::OpenSans)[0];
+        assert_eq!(ok!(font.kerning('A', 'V')), -82.0);
+        assert_eq!(ok!(font.kerning('A', 'A')), 0.0);
+        let document = ok!(font::svg::document(font, "AV", 100.0));
+        assert!(document.starts_with(r#"<svg height="101" width="107" "#));
+        assert!(
+            document.contains(r#"<path transform="translate(51.514 76.514)" d="M23.389,-9.619 "#)
+        );
+        assert_eq!(document.matches("<path ").count(), 2);
+    }
+}
+
+mod source_serif {
+    use crate::support::{setup, Fixture};
+
+    #[test]
+    fn document() {
+        let font = &mut setup(Fixture::SourceSerif)[0];
+        assert_eq!(ok!(font.kerning('A', 'V')), -129.0);
+        assert_eq!(ok!(font.kerning('T', 'o')), -70.0);
+        let document = ok!(font::svg::document(font, "A V", 100.0));
+        assert_eq!(document.matches("<path ").count(), 2);
+        assert!(document.ends_with("</svg>\n"));
+    }
+}
@@ -32,3 +32,73 @@ fn noto_naskh_arabic() {
         ],
     );
 }
+
+#[test]
+fn open_sans() {
+    use std::io::Cursor;
+
+    use font::formats::opentype::{read, write_with, Disposition, Insertion};
+    use font::Case;
+
+    let path = crate::support::path(Fixture::OpenSans);
+    let font = ok!(ok!(read(ok!(std::fs::File::open(path)))).pop());
+    let mut cursor: Cursor<Vec<u8>> = Cursor::new(vec![]);
+    ok!(write_with(
+        font,
+        &mut cursor,
+        |tag| match &**tag {
+            b"DSIG" => Disposition::Drop,
+            b"kern" => Disposition::Rename(Tag(*b"KERN")),
+            _ => Disposition::Retain,
+        },
+        vec![Insertion::raw(Tag(*b"meta"), vec![0, 0, 0, 1, 0, 0, 0, 0])],
+    ));
+
+    let data = cursor.into_inner();
+    assert_eq!(&data[4..12], &[0, 19, 1, 0, 0, 4, 0, 48]);
+    let mut font = ok!(ok!(read(Cursor::new(data))).pop());
+    assert_eq!(
+        ok!(font.tables()),
+        vec![
+            Tag(*b"GDEF"),
+            Tag(*b"GPOS"),
+            Tag(*b"GSUB"),
+            Tag(*b"KERN"),
+            Tag(*b"OS/2"),
+            Tag(*b"cmap"),
+            Tag(*b"cvt "),
+            Tag(*b"fpgm"),
+            Tag(*b"gasp"),
+            Tag(*b"glyf"),
+            Tag(*b"head"),
+            Tag(*b"hhea"),
+            Tag(*b"hmtx"),
+            Tag(*b"loca"),
+            Tag(*b"maxp"),
+            Tag(*b"meta"),
+            Tag(*b"name"),
+            Tag(*b"post"),
+            Tag(*b"prep"),
+        ],
+    );
+    assert_eq!(ok!(ok!(font.glyph('a'))).len(), 2);
+}
+
+#[test]
+fn checksum() {
+    use std::io::Cursor;
+
+    use font::formats::opentype::{read, write, Disposition};
+
+    let path = crate::support::path(Fixture::OpenSans);
+    let font = ok!(ok!(read(ok!(std::fs::File::open(path)))).pop());
+    let mut cursor: Cursor<Vec<u8>> = Cursor::new(vec![]);
+    ok!(write(font, &mut cursor, |_| Disposition::Retain));
+
+    let data = cursor.into_inner();
+    let sum = data
+        .chunks(4)
+        .map(|chunk| u32::from_be_bytes(ok!(chunk.try_into())))
+        .fold(0u32, |sum, value| sum.wrapping_add(value));
+    assert_eq!(sum, 0xB1B0AFBA);
+}
@@ -0,0 +1,98 @@
+#[macro_use]
+mod support;
+
+use font::Glyph;
+
+use crate::support::{build, setup, trace, Fixture};
+
+fn square(clockwise: bool) -> Glyph {
+    let glyph = build(|builder| support::square(builder, (0.0, 0.0), 10.0));
+    let glyph = match clockwise {
+        true => glyph,
+        _ => glyph.transform((-1.0, 0.0, 0.0, 1.0, 10.0, 0.0)),
+    };
+    Glyph {
+        advance_width: 20.0,
+        bounding_box: (0.0, 0.0, 10.0, 10.0),
+        side_bearings: (0.0, 10.0),
+        ..glyph
+    }
+}
+
+#[test]
+fn embolden() {
+    for clockwise in [false, true] {
+        let glyph = square(clockwise).embolden(1.0);
+        assert_eq!(glyph.bounding_box, (0.0, -1.0, 12.0, 11.0));
+        assert_eq!(glyph.advance_width, 22.0);
+        assert_eq!(glyph.side_bearings, (0.0, 10.0));
+        assert_eq!(glyph.area().abs(), 144.0);
+    }
+
+    let font = &mut setup(Fixture::OpenSans)[0];
+    for character in ['o', 'H', '&'] {
+        let glyph = ok!(ok!(font.glyph(character)));
+        let other = glyph.embolden(20.0);
+        assert!(other.area().abs() > glyph.area().abs());
+        assert_eq!(other.advance_width, glyph.advance_width + 40.0);
+        assert!(other.width() > glyph.width() + 39.0);
+        assert!(other.height() > glyph.height() + 39.0);
+    }
+    let glyph = ok!(ok!(font.glyph('o')));
+    let other = glyph.embolden(20.0);
+    assert!((other.side_bearings.0 - glyph.side_bearings.0).abs() < 1.0);
+    assert!((other.width() - glyph.width() - 40.0).abs() < 1.0);
+    assert!((other.height() - glyph.height() - 40.0).abs() < 1.0);
+}
+
+#[test]
+fn slant() {
+    let glyph = square(true).slant(-45.0);
+    let (left, bottom, right, top) = glyph.bounding_box;
+    assert_eq!((left, bottom, top), (0.0, 0.0, 10.0));
+    assert!((right - 20.0).abs() < 1e-4);
+    assert_eq!(glyph.advance_width, 20.0);
+    assert!(glyph.side_bearings.1.abs() < 1e-4);
+}
+
+#[test]
+fn transform() {
+    let glyph = square(true).transform((2.0, 0.0, 0.0, 3.0, 5.0, -1.0));
+    assert_eq!(glyph.bounding_box, (5.0, -1.0, 25.0, 29.0));
+    assert_eq!(glyph.advance_width, 40.0);
+    assert_eq!(glyph.side_bearings, (5.0, 15.0));
+    assert_eq!(glyph.area(), -600.0);
+
+    let font = &mut setup(Fixture::SourceSerif)[0];
+    let glyph = ok!(ok!(font.glyph('a')));
+    let other = glyph.transform((1.0, 0.0, 0.0, 1.0, 0.0, 0.0));
+    assert_eq!(other.contours.len(), glyph.contours.len());
+    assert_eq!(other.advance_width, glyph.advance_width);
+    assert!((other.area() - glyph.area()).abs() < 1e-2);
+    assert!((other.side_bearings.0 - glyph.side_bearings.0).abs() < 1.0);
+}
+
+#[test]
+fn components() {
+    let font = &mut setup(Fixture::OpenSans)[0];
+    let glyph_id = ok!(ok!(font.glyph_id('å')));
+    let matrix = (2.0, 0.5, 0.0, 3.0, 5.0, -1.0);
+    let glyph = ok!(font.glyph_by_id(glyph_id, true)).transform(matrix);
+    let other = ok!(font.glyph_by_id(glyph_id, false)).transform(matrix);
+    assert_eq!(other.components.len(), 2);
+    let mut points = vec![];
+    for component in other.components.iter() {
+        let child = ok!(font.glyph_by_id(component.glyph_id, false));
+        let (xx, xy, yx, yy) = component.scale;
+        let (x, y) = (component.offset.0, component.offset.1);
+        points.extend(trace(&child.transform((xx, xy, yx, yy, x, y))));
+    }
+    assert_eq!(points, trace(&glyph));
+
+    let font = &mut setup(Fixture::SourceSerif)[0];
+    let glyph = ok!(ok!(font.glyph('a')));
+    assert!(glyph.hints.is_some());
+    assert!(glyph.transform(matrix).hints.is_none());
+    assert!(glyph.slant(10.0).hints.is_none());
+    assert!(glyph.embolden(10.0).hints.is_none());
+}